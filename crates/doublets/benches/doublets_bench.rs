@@ -66,12 +66,23 @@ fn bench_create_million_points(c: &mut Criterion) {
   });
 }
 
+fn bench_bulk_load_million_links(c: &mut Criterion) {
+  c.bench_function("bulk_load_million_links", |b| {
+    b.iter(|| {
+      let mut store = create_heap_store::<usize>().unwrap();
+      let pairs = (0..1_000_000).map(|i| (i % 1000 + 1, i % 997 + 1));
+      black_box(store.bulk_load(pairs).unwrap());
+    });
+  });
+}
+
 criterion_group!(
   benches,
   bench_create_point,
   bench_create_link,
   bench_search,
   bench_iteration,
-  bench_create_million_points
+  bench_create_million_points,
+  bench_bulk_load_million_links
 );
 criterion_main!(benches);
@@ -0,0 +1,167 @@
+use {
+  crate::{
+    Error, FormatMismatch, Index, Link, Result,
+    header::{CHECKSUM_SEED, extend_checksum},
+  },
+  std::io::{self, BufReader, BufWriter, Read, Write},
+};
+
+/// Magic number every dump starts with
+const MAGIC: [u8; 8] = *b"DBLTDUMP";
+
+/// Version of the dump format, bump on any incompatible change
+const VERSION: u16 = 1;
+
+/// Longest LEB128 encoding of a `u64`
+const MAX_VARINT: usize = 10;
+
+/// Writer of portable snapshots of links
+///
+/// A dump doesn't depend on the index type, the link layout or the tree
+/// strategies of the store it was taken from:
+///
+/// | size    | field                                         |
+/// |---------|-----------------------------------------------|
+/// | 8       | magic `DBLTDUMP`                              |
+/// | 2       | format version, little-endian                 |
+/// | varint  | number of links                               |
+/// | varints | index delta, source and target of every link  |
+/// | 4       | checksum of all previous bytes, little-endian |
+///
+/// Varints are unsigned LEB128 encodings of up to 64 bits. Links are
+/// written in index order, each index as the difference from the previous
+/// one, starting at zero.
+pub(crate) struct Dumper<W: Write> {
+  writer: BufWriter<W>,
+  hash: u32,
+  last: usize,
+}
+
+impl<W: Write> Dumper<W> {
+  pub fn new(writer: W, links: usize) -> io::Result<Self> {
+    let mut dumper =
+      Self { writer: BufWriter::new(writer), hash: CHECKSUM_SEED, last: 0 };
+    dumper.write(&MAGIC)?;
+    dumper.write(&VERSION.to_le_bytes())?;
+    dumper.varint(links as u64)?;
+    Ok(dumper)
+  }
+
+  fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+    self.hash = extend_checksum(self.hash, bytes);
+    self.writer.write_all(bytes)
+  }
+
+  fn varint(&mut self, mut value: u64) -> io::Result<()> {
+    let mut bytes = [0; MAX_VARINT];
+    let mut len = 0;
+    loop {
+      let byte = (value & 0x7f) as u8;
+      value >>= 7;
+      if value == 0 {
+        bytes[len] = byte;
+        len += 1;
+        break;
+      }
+      bytes[len] = byte | 0x80;
+      len += 1;
+    }
+    self.write(&bytes[..len])
+  }
+
+  /// Append a link, links must come in index order
+  pub fn link<T: Index>(&mut self, link: Link<T>) -> io::Result<()> {
+    let index = link.index.as_usize();
+    self.varint((index - self.last) as u64)?;
+    self.varint(link.source.as_usize() as u64)?;
+    self.varint(link.target.as_usize() as u64)?;
+    self.last = index;
+    Ok(())
+  }
+
+  pub fn finish(mut self) -> io::Result<()> {
+    let hash = self.hash;
+    self.writer.write_all(&hash.to_le_bytes())?;
+    self.writer.flush()
+  }
+}
+
+/// Read every link of a dump written by [`Dumper`], checking its checksum
+pub(crate) fn read<T: Index, R: Read>(reader: R) -> Result<Vec<Link<T>>, T> {
+  let io = |err: io::Error| Error::Io(err.kind());
+  let mut reader =
+    Undumper { reader: BufReader::new(reader), hash: CHECKSUM_SEED };
+
+  let mut magic = [0; MAGIC.len()];
+  reader.read(&mut magic).map_err(io)?;
+  if magic != MAGIC {
+    return Err(Error::IncompatibleFormat(FormatMismatch::Magic));
+  }
+  let mut version = [0; 2];
+  reader.read(&mut version).map_err(io)?;
+  let version = u16::from_le_bytes(version);
+  if version != VERSION {
+    return Err(Error::IncompatibleFormat(FormatMismatch::Version {
+      found: version,
+      expected: VERSION,
+    }));
+  }
+
+  let count = reader.varint().map_err(io)?;
+  // a corrupted count must not reserve all the memory
+  let mut links = Vec::with_capacity(count.min(1 << 16) as usize);
+  let mut last = 0_u64;
+  for _ in 0..count {
+    let delta = reader.varint().map_err(io)?;
+    let index = delta.checked_add(last).ok_or(Error::Overflow)?;
+    let source = reader.varint().map_err(io)?;
+    let target = reader.varint().map_err(io)?;
+    last = index;
+    let value = |value: u64| {
+      usize::try_from(value)
+        .ok()
+        .and_then(T::try_from_usize)
+        .filter(|&value| value != T::ANY)
+        .ok_or(Error::Overflow)
+    };
+    links.push(Link::new(value(index)?, value(source)?, value(target)?));
+  }
+
+  let expected = reader.hash;
+  let mut found = [0; 4];
+  reader.read(&mut found).map_err(io)?;
+  if u32::from_le_bytes(found) != expected {
+    return Err(Error::IncompatibleFormat(FormatMismatch::Checksum));
+  }
+  Ok(links)
+}
+
+struct Undumper<R> {
+  reader: BufReader<R>,
+  hash: u32,
+}
+
+impl<R: Read> Undumper<R> {
+  fn read(&mut self, bytes: &mut [u8]) -> io::Result<()> {
+    self.reader.read_exact(bytes)?;
+    self.hash = extend_checksum(self.hash, bytes);
+    Ok(())
+  }
+
+  fn varint(&mut self) -> io::Result<u64> {
+    let mut value = 0_u64;
+    for shift in (0..MAX_VARINT).map(|i| i * 7) {
+      let mut byte = [0];
+      self.read(&mut byte)?;
+      let bits = u64::from(byte[0] & 0x7f);
+      if bits > u64::MAX >> shift {
+        break;
+      }
+      value |= bits << shift;
+      if byte[0] & 0x80 == 0 {
+        return Ok(value);
+      }
+    }
+    Err(io::ErrorKind::InvalidData.into())
+  }
+}
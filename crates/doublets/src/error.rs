@@ -1,4 +1,8 @@
-use {crate::Index, core::fmt::Debug, thiserror::Error};
+use {
+  crate::{Index, ParseError},
+  core::fmt::Debug,
+  thiserror::Error,
+};
 /// Errors that can occur during doublets operations
 #[derive(Debug, Error, Clone, PartialEq, Eq)]
 pub enum Error<T: Index> {
@@ -8,11 +12,40 @@ pub enum Error<T: Index> {
   AlreadyExists(T, T, T),
   #[error("Link {0:?} has usages and cannot be deleted")]
   HasUsages(T),
+  #[error("Link index {0:?} is reserved by the store")]
+  Reserved(T),
+  #[error("Change of link {0:?} was rejected by a trigger")]
+  Rejected(T),
   #[error("Memory allocation failed")]
   AllocationFailed,
   #[error("Operation would overflow capacity")]
   Overflow,
   #[error("Invalid query parameters")]
   InvalidQuery,
+  #[error("Incompatible store format: {0}")]
+  IncompatibleFormat(FormatMismatch),
+  #[error("I/O operation failed: {0}")]
+  Io(std::io::ErrorKind),
+  #[error("Invalid links notation at {0}")]
+  Parse(ParseError),
+}
+
+/// Reason why stored data cannot be opened by a store
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum FormatMismatch {
+  #[error("unknown magic number")]
+  Magic,
+  #[error("checksum does not match")]
+  Checksum,
+  #[error("format version {found}, expected {expected}")]
+  Version { found: u16, expected: u16 },
+  #[error("index width {found} bytes, expected {expected}")]
+  IndexWidth { found: u8, expected: u8 },
+  #[error("source strategy {found}, expected {expected}")]
+  SourceStrategy { found: u8, expected: u8 },
+  #[error("target strategy {found}, expected {expected}")]
+  TargetStrategy { found: u8, expected: u8 },
+  #[error("link layout hash {found:#010x}, expected {expected:#010x}")]
+  Layout { found: u32, expected: u32 },
 }
 pub type Result<R, T> = core::result::Result<R, Error<T>>;
@@ -0,0 +1,269 @@
+use {
+  crate::{Index, RawLink, error::FormatMismatch},
+  core::mem::offset_of,
+};
+
+/// Magic number every store file starts with
+const MAGIC: [u8; 4] = *b"DBLT";
+
+/// Version of the on-disk format, bump on any incompatible change
+const VERSION: u8 = 2;
+
+/// Description of the data a store lays out in memory
+///
+/// Everything here is fixed by the store's type parameters, so two stores
+/// may share memory only if their formats are equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Format {
+  pub version: u8,
+  pub index_width: u8,
+  pub source_strategy: u8,
+  pub target_strategy: u8,
+  pub layout: u32,
+}
+
+impl Format {
+  pub const fn new<T: Index>(source: u8, target: u8) -> Self {
+    Self {
+      version: VERSION,
+      index_width: size_of::<T>() as u8,
+      source_strategy: source,
+      target_strategy: target,
+      layout: layout_hash::<T>(),
+    }
+  }
+
+  /// Check that a stored format can be read as `self`
+  fn check(&self, stored: &Format) -> Result<(), FormatMismatch> {
+    macro_rules! check {
+      ($field:ident => $variant:ident) => {
+        if stored.$field != self.$field {
+          return Err(FormatMismatch::$variant {
+            found: stored.$field.into(),
+            expected: self.$field.into(),
+          });
+        }
+      };
+    }
+
+    check!(version => Version);
+    check!(index_width => IndexWidth);
+    check!(source_strategy => SourceStrategy);
+    check!(target_strategy => TargetStrategy);
+    check!(layout => Layout);
+    Ok(())
+  }
+}
+
+/// Bookkeeping of a [`Store`](crate::Store) persisted in reserved slots
+///
+/// Link index 0 is never handed out, so its slot keeps the format
+/// description, counters and tree roots next to the links themselves.
+/// A store backed by a file mapping can then be reopened without losing
+/// its state, and a file written by an incompatible store is rejected.
+///
+/// Counters are stored with the width of the index type, so the header fits
+/// into slot 0 for `u32` and wider indices. Narrower links need a few more
+/// leading slots, see [`slots`].
+///
+/// The header is encoded as little-endian bytes:
+///
+/// | offset | size  | field                                 |
+/// |--------|-------|---------------------------------------|
+/// | 0      | 4     | magic `DBLT`                          |
+/// | 4      | 1     | format version                        |
+/// | 5      | 1     | index width `W` in bytes              |
+/// | 6      | 1     | source strategy id                    |
+/// | 7      | 1     | target strategy id                    |
+/// | 8      | 4     | [`RawLink`] layout hash, which covers |
+/// |        |       | its size                              |
+/// | 12     | 4     | checksum of all other header bytes    |
+/// | 16     | 5 × W | allocated, free count, first free,    |
+/// |        |       | source root and target root           |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Header {
+  pub format: Format,
+  /// Number of used slots including the reserved ones
+  pub allocated: usize,
+  pub free_count: usize,
+  /// Head of the free list or 0 if it is empty
+  pub first_free: usize,
+  /// Root of the source tree or 0 if it is empty
+  pub source_root: usize,
+  /// Root of the target tree or 0 if it is empty
+  pub target_root: usize,
+}
+
+const PRELUDE: usize = 16;
+const CHECKSUM: core::ops::Range<usize> = 12..16;
+
+/// Size of the encoded header for the given index width
+const fn size(width: usize) -> usize {
+  PRELUDE + 5 * width
+}
+
+/// Number of leading slots occupied by the header of a `RawLink<T>` store
+///
+/// Indices below this value are never handed out as links, so the first
+/// link is 3 for `u8`, 2 for `u16` and 1 for wider indices.
+pub(crate) const fn slots<T: Index>() -> usize {
+  size(size_of::<T>()).div_ceil(size_of::<RawLink<T>>())
+}
+
+impl Header {
+  /// Header of an empty store
+  pub fn empty<T: Index>(format: Format) -> Self {
+    Self {
+      format,
+      allocated: slots::<T>(),
+      free_count: 0,
+      first_free: 0,
+      source_root: 0,
+      target_root: 0,
+    }
+  }
+
+  /// Read the header from the bytes of the reserved slots
+  ///
+  /// Returns `None` for zeroed memory, which never held a store, and an
+  /// error if the stored header is damaged or its format is not `format`.
+  pub fn read(
+    bytes: &[u8],
+    format: &Format,
+  ) -> Option<Result<Self, FormatMismatch>> {
+    if bytes[..PRELUDE].iter().all(|&byte| byte == 0) {
+      None
+    } else {
+      Some(Self::decode(bytes, format))
+    }
+  }
+
+  /// Write the header into the bytes of the reserved slots
+  pub fn write(&self, bytes: &mut [u8]) {
+    let format = &self.format;
+    let width = format.index_width as usize;
+    let bytes = &mut bytes[..size(width)];
+
+    bytes[0..4].copy_from_slice(&MAGIC);
+    bytes[4] = format.version;
+    bytes[5] = format.index_width;
+    bytes[6] = format.source_strategy;
+    bytes[7] = format.target_strategy;
+    bytes[8..12].copy_from_slice(&format.layout.to_le_bytes());
+
+    let counters = [
+      self.allocated,
+      self.free_count,
+      self.first_free,
+      self.source_root,
+      self.target_root,
+    ];
+    let chunks = bytes[PRELUDE..].chunks_exact_mut(width);
+    for (chunk, value) in chunks.zip(counters) {
+      chunk.copy_from_slice(&(value as u128).to_le_bytes()[..width]);
+    }
+
+    bytes[CHECKSUM].fill(0);
+    let checksum = checksum(bytes);
+    bytes[CHECKSUM].copy_from_slice(&checksum.to_le_bytes());
+  }
+
+  fn decode(bytes: &[u8], format: &Format) -> Result<Self, FormatMismatch> {
+    let u32_at =
+      |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+
+    if bytes[0..4] != MAGIC {
+      return Err(FormatMismatch::Magic);
+    }
+
+    // counters of another width cannot even be located
+    let width = format.index_width as usize;
+    if bytes[5] != format.index_width {
+      return Err(FormatMismatch::IndexWidth {
+        found: bytes[5],
+        expected: format.index_width,
+      });
+    }
+
+    let unsigned = [
+      &bytes[..CHECKSUM.start],
+      &[0; CHECKSUM.end - CHECKSUM.start],
+      &bytes[CHECKSUM.end..size(width)],
+    ];
+    let checksum = unsigned
+      .iter()
+      .fold(CHECKSUM_SEED, |hash, bytes| extend_checksum(hash, bytes));
+    if checksum != u32_at(CHECKSUM.start) {
+      return Err(FormatMismatch::Checksum);
+    }
+
+    let counter = |n: usize| {
+      let at = PRELUDE + n * width;
+      let mut le = [0; 16];
+      le[..width].copy_from_slice(&bytes[at..at + width]);
+      u128::from_le_bytes(le) as usize
+    };
+
+    let stored = Format {
+      version: bytes[4],
+      index_width: bytes[5],
+      source_strategy: bytes[6],
+      target_strategy: bytes[7],
+      layout: u32_at(8),
+    };
+    format.check(&stored)?;
+
+    Ok(Self {
+      format: stored,
+      allocated: counter(0),
+      free_count: counter(1),
+      first_free: counter(2),
+      source_root: counter(3),
+      target_root: counter(4),
+    })
+  }
+}
+
+/// Checksum of no bytes, which [`extend_checksum`] starts from
+pub(crate) const CHECKSUM_SEED: u32 = 0x811c_9dc5;
+
+/// 32-bit FNV-1a hash used for checksums of persisted data
+pub(crate) fn checksum(bytes: &[u8]) -> u32 {
+  extend_checksum(CHECKSUM_SEED, bytes)
+}
+
+/// Continue the checksum of some bytes with the bytes following them
+pub(crate) fn extend_checksum(hash: u32, bytes: &[u8]) -> u32 {
+  bytes
+    .iter()
+    .fold(hash, |hash, &byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+/// Hash of field offsets and sizes of [`RawLink`]
+///
+/// Catches layout changes that keep the total size of a link the same.
+const fn layout_hash<T: Index>() -> u32 {
+  let layout = [
+    size_of::<RawLink<T>>(),
+    align_of::<RawLink<T>>(),
+    offset_of!(RawLink<T>, source),
+    offset_of!(RawLink<T>, target),
+    offset_of!(RawLink<T>, source_tree),
+    offset_of!(RawLink<T>, target_tree),
+    offset_of!(RawLink<T>, is_free),
+    size_of::<trees::Node<T>>(),
+  ];
+  // FNV-1a over little-endian `u64`s, as `checksum` would hash them
+  let mut hash = CHECKSUM_SEED;
+  let mut i = 0;
+  while i < layout.len() {
+    let bytes = (layout[i] as u64).to_le_bytes();
+    let mut j = 0;
+    while j < bytes.len() {
+      hash = (hash ^ bytes[j] as u32).wrapping_mul(0x0100_0193);
+      j += 1;
+    }
+    i += 1;
+  }
+  hash
+}
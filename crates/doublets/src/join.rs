@@ -0,0 +1,238 @@
+use {
+  crate::{Index, Link, Links},
+  core::ops,
+};
+
+/// Variable shared between the patterns of a [`Join`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Var(usize);
+
+/// Part of a pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Term<T: Index> {
+  /// Matches anything and binds nothing
+  Any,
+  /// Matches exactly the given index
+  Value(T),
+  /// Matches whatever the variable is bound to, binds it otherwise
+  Var(Var),
+}
+
+impl<T: Index> From<T> for Term<T> {
+  fn from(value: T) -> Self {
+    Term::Value(value)
+  }
+}
+
+impl<T: Index> From<Var> for Term<T> {
+  fn from(var: Var) -> Self {
+    Term::Var(var)
+  }
+}
+
+/// Several `[index, source, target]` patterns matched together, like a
+/// basic graph pattern of a triple store
+///
+/// [`Join::run`] yields a [`Binding`] of every variable for each way to
+/// match all patterns at once, so a link is reported as many times as it
+/// completes a match.
+///
+/// Patterns are evaluated one at a time, each time picking the one with
+/// the fewest matches under the variables bound so far, as counted by the
+/// source and target indexes. Bindings are produced lazily, only the
+/// matches of the patterns on the current path are iterated.
+///
+/// # Examples
+///
+/// Links `x` targeting `a` that are the source of some link to `b`:
+///
+/// ```
+/// use doublets::{Doublets, Join, Term, create_heap_store};
+///
+/// let mut store = create_heap_store::<usize>().unwrap();
+/// let a = store.create_point().unwrap();
+/// let b = store.create_point().unwrap();
+/// let x = store.create_link(b, a).unwrap();
+/// let y = store.create_link(b, a).unwrap();
+/// store.create_link(x, b).unwrap();
+/// store.create_link(y, a).unwrap();
+///
+/// let mut join = Join::new();
+/// let var = join.var();
+/// join.pattern(var, Term::Any, a).pattern(Term::Any, var, b);
+///
+/// let found: Vec<_> = join.run(&store).map(|binding| binding[var]).collect();
+/// assert_eq!(found, [x]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Join<T: Index> {
+  vars: usize,
+  patterns: Vec<[Term<T>; 3]>,
+}
+
+impl<T: Index> Default for Join<T> {
+  fn default() -> Self {
+    Self { vars: 0, patterns: Vec::new() }
+  }
+}
+
+impl<T: Index> Join<T> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Declare a new variable
+  pub fn var(&mut self) -> Var {
+    self.vars += 1;
+    Var(self.vars - 1)
+  }
+
+  /// Add a pattern of a link, its source and its target
+  pub fn pattern(
+    &mut self,
+    index: impl Into<Term<T>>,
+    source: impl Into<Term<T>>,
+    target: impl Into<Term<T>>,
+  ) -> &mut Self {
+    self.patterns.push([index.into(), source.into(), target.into()]);
+    self
+  }
+
+  /// Match the patterns against `links`
+  ///
+  /// Without patterns there is a single empty match. Variables used by no
+  /// pattern are bound to `T::ANY`.
+  pub fn run<'a, D: Links<T> + ?Sized>(
+    &'a self,
+    links: &'a D,
+  ) -> Bindings<'a, T, D> {
+    Bindings {
+      links,
+      patterns: &self.patterns,
+      values: vec![None; self.vars],
+      used: vec![false; self.patterns.len()],
+      frames: Vec::new(),
+      started: false,
+    }
+  }
+}
+
+/// Values of the variables of a [`Join`] in a single match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding<T: Index>(Vec<T>);
+
+impl<T: Index> Binding<T> {
+  /// Values of the variables in the order they were declared
+  pub fn values(&self) -> &[T] {
+    &self.0
+  }
+}
+
+impl<T: Index> ops::Index<Var> for Binding<T> {
+  type Output = T;
+
+  fn index(&self, var: Var) -> &T {
+    &self.0[var.0]
+  }
+}
+
+/// Pattern being matched with the matches left to try
+struct Frame<'a, T: Index, D: Links<T> + ?Sized + 'a> {
+  pattern: usize,
+  matches: D::Query<'a>,
+  /// Variables bound by the current match
+  bound: Vec<usize>,
+}
+
+/// Matches of a [`Join`], see [`Join::run`]
+pub struct Bindings<'a, T: Index, D: Links<T> + ?Sized + 'a> {
+  links: &'a D,
+  patterns: &'a [[Term<T>; 3]],
+  values: Vec<Option<T>>,
+  /// Whether a pattern is on the current path
+  used: Vec<bool>,
+  frames: Vec<Frame<'a, T, D>>,
+  started: bool,
+}
+
+impl<'a, T: Index, D: Links<T> + ?Sized> Bindings<'a, T, D> {
+  /// Query of a pattern with the bound variables filled in
+  fn query(&self, pattern: usize) -> [T; 3] {
+    self.patterns[pattern].map(|term| match term {
+      Term::Value(value) => value,
+      Term::Var(var) => self.values[var.0].unwrap_or(T::ANY),
+      Term::Any => T::ANY,
+    })
+  }
+
+  /// Start matching the unused pattern with the fewest matches
+  fn descend(&mut self) {
+    let Some(pattern) = (0..self.patterns.len())
+      .filter(|&pattern| !self.used[pattern])
+      .min_by_key(|&pattern| self.links.count(self.query(pattern)))
+    else {
+      return;
+    };
+    self.used[pattern] = true;
+    let matches = self.links.query(self.query(pattern));
+    self.frames.push(Frame { pattern, matches, bound: Vec::new() });
+  }
+
+  /// Bind the unbound variables of the top pattern to `link`, returns
+  /// whether a variable repeated in the pattern agrees
+  fn bind(&mut self, link: Link<T>) -> bool {
+    let Some(frame) = self.frames.last_mut() else {
+      return false;
+    };
+    let parts = [link.index, link.source, link.target];
+    for (term, part) in self.patterns[frame.pattern].into_iter().zip(parts) {
+      let Term::Var(var) = term else { continue };
+      match self.values[var.0] {
+        Some(value) if value != part => return false,
+        Some(_) => {}
+        None => {
+          self.values[var.0] = Some(part);
+          frame.bound.push(var.0);
+        }
+      }
+    }
+    true
+  }
+
+  fn binding(&self) -> Binding<T> {
+    Binding(self.values.iter().map(|value| value.unwrap_or(T::ANY)).collect())
+  }
+}
+
+impl<T: Index, D: Links<T> + ?Sized> Iterator for Bindings<'_, T, D> {
+  type Item = Binding<T>;
+
+  fn next(&mut self) -> Option<Binding<T>> {
+    if !self.started {
+      self.started = true;
+      if self.patterns.is_empty() {
+        return Some(self.binding());
+      }
+      self.descend();
+    }
+
+    loop {
+      let frame = self.frames.last_mut()?;
+      for var in frame.bound.drain(..) {
+        self.values[var] = None;
+      }
+      let Some(link) = frame.matches.next() else {
+        self.used[frame.pattern] = false;
+        self.frames.pop();
+        continue;
+      };
+      if !self.bind(link) {
+        continue;
+      }
+      if self.frames.len() == self.patterns.len() {
+        return Some(self.binding());
+      }
+      self.descend();
+    }
+  }
+}
@@ -0,0 +1,258 @@
+use {
+  crate::{Index, Link, header::checksum},
+  std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+  },
+};
+
+/// Operation code, index and the before and after states of a link
+const BODY: usize = 1 + 5 * size_of::<u64>();
+/// Body followed by its checksum
+const RECORD: usize = BODY + size_of::<u32>();
+
+/// Flag of the opcode marking records followed by more of the same batch
+const MORE: u8 = 0x80;
+
+/// Records after which a store makes a checkpoint by default, about 3 MB
+const DEFAULT_LIMIT: usize = 1 << 16;
+
+/// When a [`Journal`] forces its records to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPolicy {
+  /// Sync every record before the change is applied
+  ///
+  /// Every change that reached the links is guaranteed to be recovered.
+  Always,
+  /// Sync once every given number of records
+  ///
+  /// A crash can lose up to that many of the latest changes. The operating
+  /// system may also write changed links back to their file before their
+  /// records reach the journal, then a crash leaves changes in the links
+  /// the journal knows nothing about. Run [`Store::verify`] after such a
+  /// crash and [`Store::rebuild_indexes`] if it finds faults.
+  ///
+  /// [`Store::verify`]: crate::Store::verify
+  /// [`Store::rebuild_indexes`]: crate::Store::rebuild_indexes
+  Batch(usize),
+  /// Leave syncing to the operating system and checkpoints
+  ///
+  /// Links may reach the disk before their records, as with
+  /// [`SyncPolicy::Batch`].
+  Never,
+}
+
+/// Kind of a journaled change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+  Create = 1,
+  Update = 2,
+  Delete = 3,
+}
+
+/// Single journaled change of a link
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Record<T: Index> {
+  pub op: Op,
+  pub index: T,
+  pub before: Link<T>,
+  pub after: Link<T>,
+}
+
+impl<T: Index> Record<T> {
+  /// `more` marks every record of a batch but the last one
+  fn encode(&self, more: bool) -> [u8; RECORD] {
+    let mut bytes = [0; RECORD];
+    bytes[0] = self.op as u8 | if more { MORE } else { 0 };
+    let values = [
+      self.index,
+      self.before.source,
+      self.before.target,
+      self.after.source,
+      self.after.target,
+    ];
+    for (chunk, value) in bytes[1..BODY].chunks_exact_mut(8).zip(values) {
+      chunk.copy_from_slice(&(value.as_usize() as u64).to_le_bytes());
+    }
+    let sum = checksum(&bytes[..BODY]);
+    bytes[BODY..].copy_from_slice(&sum.to_le_bytes());
+    bytes
+  }
+
+  /// Record and whether more of its batch follow, `None` for torn or
+  /// corrupted records
+  fn decode(bytes: &[u8]) -> Option<(Self, bool)> {
+    let (body, sum) = bytes.split_at(BODY);
+    if checksum(body).to_le_bytes() != sum {
+      return None;
+    }
+    let op = match body[0] & !MORE {
+      1 => Op::Create,
+      2 => Op::Update,
+      3 => Op::Delete,
+      _ => return None,
+    };
+    let mut values = body[1..].chunks_exact(8).map(|chunk| {
+      let value = u64::from_le_bytes(chunk.try_into().unwrap());
+      usize::try_from(value).ok().and_then(T::try_from_usize)
+    });
+    let mut next = || values.next().flatten();
+    let index = next()?;
+    let before = Link::new(index, next()?, next()?);
+    let after = Link::new(index, next()?, next()?);
+    Some((Self { op, index, before, after }, body[0] & MORE != 0))
+  }
+}
+
+/// Append-only write-ahead journal of a file-backed store
+///
+/// Every create, update and delete is appended as a checksummed record
+/// before it touches the links. Opening a store with a non-empty journal
+/// replays the records and rebuilds the trees, so a crash in the middle of
+/// an operation can't leave them inconsistent.
+///
+/// Records of one operation are appended as a batch, every record but the
+/// last one is marked as followed by more. A batch missing its last
+/// record was torn by a crash while it was being written, so the operation
+/// never started: the whole batch is discarded, along with anything after
+/// it.
+///
+/// The journal is emptied by [`Store::checkpoint`](crate::Store::checkpoint)
+/// once the links are flushed to disk. The store makes a checkpoint by
+/// itself before the journal grows past its [`limit`](Journal::limit).
+///
+/// Records hold indices of up to 64 bits, so stores with wider index types
+/// can't be journaled.
+#[derive(Debug)]
+pub struct Journal {
+  file: File,
+  policy: SyncPolicy,
+  /// Records appended since the last sync
+  unsynced: usize,
+  /// Records in the file
+  len: usize,
+  /// Records after which the store makes a checkpoint
+  limit: usize,
+}
+
+impl Journal {
+  /// Use `file` as a journal, keeping the records it already holds
+  pub fn new(file: File, policy: SyncPolicy) -> Self {
+    Self { file, policy, unsynced: 0, len: 0, limit: DEFAULT_LIMIT }
+  }
+
+  /// Make a checkpoint once the journal holds `records` records
+  ///
+  /// Smaller limits keep the journal and the recovery short, but flush the
+  /// links more often. The limit is at least one record.
+  pub fn with_limit(mut self, records: usize) -> Self {
+    self.limit = records.max(1);
+    self
+  }
+
+  /// Open or create the journal file at `path`
+  pub fn from_path<P: AsRef<Path>>(
+    path: P,
+    policy: SyncPolicy,
+  ) -> io::Result<Self> {
+    let file = OpenOptions::new()
+      .create(true)
+      .truncate(false)
+      .read(true)
+      .write(true)
+      .open(path)?;
+    Ok(Self::new(file, policy))
+  }
+
+  /// Policy of forcing records to disk
+  pub fn policy(&self) -> SyncPolicy {
+    self.policy
+  }
+
+  /// Number of records after which the store makes a checkpoint
+  pub fn limit(&self) -> usize {
+    self.limit
+  }
+
+  /// Whether the store should make a checkpoint before appending more
+  pub(crate) fn is_full(&self) -> bool {
+    self.len >= self.limit
+  }
+
+  /// Force appended records to disk
+  pub fn sync(&mut self) -> io::Result<()> {
+    self.file.sync_data()?;
+    self.unsynced = 0;
+    Ok(())
+  }
+
+  /// Append a batch of records of changes that are applied only if this
+  /// succeeds
+  ///
+  /// The batch is replayed later only as a whole. On failure the journal
+  /// is cut back to where it was if possible, a batch torn by a crash is
+  /// discarded by [`read`](Journal::read).
+  pub(crate) fn append<T: Index>(
+    &mut self,
+    records: &[Record<T>],
+  ) -> io::Result<()> {
+    let last = records.len().saturating_sub(1);
+    let bytes: Vec<u8> = records
+      .iter()
+      .enumerate()
+      .flat_map(|(i, record)| record.encode(i < last))
+      .collect();
+    let result = self.write(&bytes, records.len());
+    if result.is_err() {
+      // best effort, a torn batch is discarded by `read` anyway
+      let _ = self.file.set_len((self.len * RECORD) as u64);
+    } else {
+      self.len += records.len();
+    }
+    result
+  }
+
+  fn write(&mut self, bytes: &[u8], records: usize) -> io::Result<()> {
+    self.file.seek(SeekFrom::Start((self.len * RECORD) as u64))?;
+    self.file.write_all(bytes)?;
+    self.unsynced += records;
+    match self.policy {
+      SyncPolicy::Always => self.sync(),
+      SyncPolicy::Batch(size) if self.unsynced >= size => self.sync(),
+      SyncPolicy::Batch(_) | SyncPolicy::Never => Ok(()),
+    }
+  }
+
+  /// Read records of complete batches, cutting off everything after the
+  /// last one
+  pub(crate) fn read<T: Index>(&mut self) -> io::Result<Vec<Record<T>>> {
+    let mut bytes = Vec::new();
+    self.file.seek(SeekFrom::Start(0))?;
+    self.file.read_to_end(&mut bytes)?;
+
+    let mut records = Vec::new();
+    let mut complete = 0;
+    for (record, more) in bytes.chunks_exact(RECORD).map_while(Record::decode) {
+      records.push(record);
+      if !more {
+        complete = records.len();
+      }
+    }
+    records.truncate(complete);
+    let valid = (records.len() * RECORD) as u64;
+    if valid < bytes.len() as u64 {
+      self.file.set_len(valid)?;
+      self.sync()?;
+    }
+    self.len = records.len();
+    Ok(records)
+  }
+
+  /// Drop every record
+  pub(crate) fn clear(&mut self) -> io::Result<()> {
+    self.file.set_len(0)?;
+    self.len = 0;
+    self.sync()
+  }
+}
@@ -1,17 +1,39 @@
 #![doc = include_str!("../README.md")]
 
+mod dump;
 mod error;
 mod handler;
+mod header;
+mod join;
+mod journal;
 mod link;
+mod notation;
+mod policy;
+mod shared;
+mod stats;
 mod store;
+mod strategy;
+mod subscription;
 mod traits;
+mod transaction;
+mod traversal;
+mod verify;
 
 pub use {
-  error::{Error, Result},
+  error::{Error, FormatMismatch, Result},
   handler::{Flow, IntoFlow, ReadHandler, WriteHandler},
+  join::{Binding, Bindings, Join, Term, Var},
+  journal::{Journal, SyncPolicy},
   link::{Index, Link},
-  store::{
-    ArtStrategy, RawLink, SbtStrategy, Store, TreeStrategy, create_heap_store,
-  },
+  notation::{Numbering, ParseError, Syntax},
+  policy::{OnDelete, Policy},
+  shared::SharedStore,
+  stats::{Stats, TreeStats},
+  store::{Query, RawLink, Store, create_heap_store},
+  strategy::{ArtStrategy, SbtStrategy, TreeSide, TreeStrategy},
+  subscription::{Filter, Listener, Subscription, Trigger},
   traits::{Doublets, Links},
+  transaction::Transaction,
+  traversal::{Bfs, Components, Dfs, Direction, Step, Traversal},
+  verify::{Report, Side, TreeFault, Violation},
 };
@@ -1,5 +1,5 @@
 use core::{
-  fmt::{self, Debug, Formatter},
+  fmt::{self, Debug, Display, Formatter},
   num::{
     NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize,
     NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize,
@@ -9,10 +9,22 @@ use core::{
 /// Trait for types that can be used as link identifiers
 ///
 /// This trait is implemented for primitives (usize, u64, etc.)
+/// Links are stored with the index type itself, so narrow types
+/// keep [`RawLink`](crate::RawLink) narrow as well.
 /// The Repr type is used for memory-efficient tree storage using
 /// NonZero variants.
 pub trait Index:
-  Copy + Clone + Eq + PartialEq + Ord + PartialOrd + Debug + Send + Sync
+  trees::Idx
+  + bytemuck::Pod
+  + Copy
+  + Clone
+  + Eq
+  + PartialEq
+  + Ord
+  + PartialOrd
+  + Debug
+  + Send
+  + Sync
 {
   /// The representation type for tree storage (typically NonZero variant)
   type Repr: Copy
@@ -26,15 +38,22 @@ pub trait Index:
     + Sync;
 
   /// Special constant values known at compile time
+  ///
+  /// `ZERO` is the null link, `ANY` is the query wildcard. `ANY` equals
+  /// `MAX`, which is never allocated, so queries can match null references.
   const ZERO: Self;
   const ANY: Self;
   const ONE: Self;
+  const MAX: Self;
 
   /// Check if the value is zero
   fn is_zero(&self) -> bool;
 
-  /// Convert from usize
-  fn from_usize(val: usize) -> Self;
+  /// Convert from usize, `None` if the value does not fit
+  ///
+  /// Use it for values that may overflow, [`trees::Idx::from_usize`]
+  /// panics on them.
+  fn try_from_usize(val: usize) -> Option<Self>;
 
   /// Convert to usize
   fn as_usize(&self) -> usize;
@@ -58,8 +77,9 @@ macro_rules! impl_index {
       type Repr = $nonzero;
 
       const ZERO: Self = 0;
-      const ANY: Self = 0;
+      const ANY: Self = <$prim>::MAX;
       const ONE: Self = 1;
+      const MAX: Self = <$prim>::MAX;
 
       #[inline]
       fn is_zero(&self) -> bool {
@@ -67,8 +87,8 @@ macro_rules! impl_index {
       }
 
       #[inline]
-      fn from_usize(val: usize) -> Self {
-        val as Self
+      fn try_from_usize(val: usize) -> Option<Self> {
+        Self::try_from(val).ok()
       }
 
       #[inline]
@@ -135,7 +155,7 @@ macro_rules! reserve_constants {
       // Compile-time assertion that constants are in valid range
       const _: () = {
         if $value == 0 {
-          panic!("Reserved constant cannot be zero (reserved for ANY)");
+          panic!("Reserved constant cannot be zero (reserved for null)");
         }
       };
     )*
@@ -215,6 +235,13 @@ impl<T: Index> Debug for Link<T> {
   }
 }
 
+/// Links notation of the link, `(index: source target)`
+impl<T: Index> Display for Link<T> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "({:?}: {:?} {:?})", self.index, self.source, self.target)
+  }
+}
+
 unsafe impl<T: Index> bytemuck::Pod for Link<T> where T: bytemuck::Pod {}
 #[rustfmt::skip]
 unsafe impl<T: Index> bytemuck::Zeroable for Link<T>
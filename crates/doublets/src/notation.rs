@@ -0,0 +1,189 @@
+use {
+  crate::{Index, Link, header},
+  std::{
+    collections::BTreeSet,
+    io::{self, BufWriter, Write},
+    iter::Peekable,
+    str::Chars,
+  },
+  thiserror::Error,
+};
+
+/// How links imported from links notation get their indices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Numbering {
+  /// Links take the indices written in the text, which must be free
+  ///
+  /// Sources and targets are kept as they are, so exported stores are
+  /// restored exactly, down to which slots between the links are free.
+  Preserve,
+  /// Links are appended with fresh indices, as by
+  /// [`Store::bulk_load`](crate::Store::bulk_load)
+  ///
+  /// References to indices defined in the text follow their links, other
+  /// references are kept as they are.
+  Renumber,
+}
+
+/// What is wrong with links notation
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+  #[error("expected {0}")]
+  Expected(&'static str),
+  #[error("number does not fit the index type")]
+  OutOfRange,
+  #[error("index is reserved")]
+  Reserved,
+  #[error("index is defined twice")]
+  Redefined,
+  #[error("link has no index to preserve")]
+  Unnumbered,
+}
+
+/// Position and reason of an error in links notation
+///
+/// Lines and columns count from one, columns in characters.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("line {line}, column {column}: {syntax}")]
+pub struct ParseError {
+  pub line: usize,
+  pub column: usize,
+  pub syntax: Syntax,
+}
+
+/// Link parsed from links notation, with the position it starts at
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Entry<T> {
+  pub index: Option<T>,
+  pub source: T,
+  pub target: T,
+  pub line: usize,
+  pub column: usize,
+}
+
+impl<T> Entry<T> {
+  pub fn error(&self, syntax: Syntax) -> ParseError {
+    ParseError { line: self.line, column: self.column, syntax }
+  }
+}
+
+/// Write links one per line as `(index: source target)`
+pub(crate) fn write<T: Index, W: Write>(
+  links: impl Iterator<Item = Link<T>>,
+  writer: W,
+) -> io::Result<()> {
+  let mut writer = BufWriter::new(writer);
+  for link in links {
+    writeln!(writer, "{link}")?;
+  }
+  writer.flush()
+}
+
+/// Parse links written as `(index: source target)` or `(source target)`,
+/// separated by any whitespace
+pub(crate) fn parse<T: Index>(text: &str) -> Result<Vec<Entry<T>>, ParseError> {
+  let mut parser =
+    Parser { chars: text.chars().peekable(), line: 1, column: 1 };
+  let mut defined = BTreeSet::new();
+  let mut entries = Vec::new();
+  while parser.skip_whitespace() {
+    let entry = parser.link::<T>()?;
+    if let Some(index) = entry.index
+      && !defined.insert(index)
+    {
+      return Err(entry.error(Syntax::Redefined));
+    }
+    entries.push(entry);
+  }
+  Ok(entries)
+}
+
+struct Parser<'a> {
+  chars: Peekable<Chars<'a>>,
+  line: usize,
+  column: usize,
+}
+
+impl Parser<'_> {
+  fn error(&self, syntax: Syntax) -> ParseError {
+    ParseError { line: self.line, column: self.column, syntax }
+  }
+
+  fn bump(&mut self) -> Option<char> {
+    let char = self.chars.next()?;
+    if char == '\n' {
+      self.line += 1;
+      self.column = 1;
+    } else {
+      self.column += 1;
+    }
+    Some(char)
+  }
+
+  /// Skip whitespace, returns whether anything follows it
+  fn skip_whitespace(&mut self) -> bool {
+    while self.chars.peek().is_some_and(|char| char.is_whitespace()) {
+      self.bump();
+    }
+    self.chars.peek().is_some()
+  }
+
+  fn expect(
+    &mut self,
+    char: char,
+    what: &'static str,
+  ) -> Result<(), ParseError> {
+    self.skip_whitespace();
+    match self.chars.peek() {
+      Some(&next) if next == char => {
+        self.bump();
+        Ok(())
+      }
+      _ => Err(self.error(Syntax::Expected(what))),
+    }
+  }
+
+  fn number<T: Index>(&mut self) -> Result<T, ParseError> {
+    self.skip_whitespace();
+    let start = self.error(Syntax::OutOfRange);
+
+    let mut digits = 0;
+    let mut value = Some(0_usize);
+    while let Some(digit) = self.chars.peek().and_then(|char| char.to_digit(10))
+    {
+      self.bump();
+      digits += 1;
+      value = value
+        .and_then(|value| value.checked_mul(10))
+        .and_then(|value| value.checked_add(digit as usize));
+    }
+    if digits == 0 {
+      return Err(ParseError { syntax: Syntax::Expected("a number"), ..start });
+    }
+    value
+      .and_then(T::try_from_usize)
+      .filter(|&value| value != T::ANY)
+      .ok_or(start)
+  }
+
+  fn link<T: Index>(&mut self) -> Result<Entry<T>, ParseError> {
+    let (line, column) = (self.line, self.column);
+    self.expect('(', "`(`")?;
+    self.skip_whitespace();
+    let reserved = self.error(Syntax::Reserved);
+    let first = self.number::<T>()?;
+    self.skip_whitespace();
+    let (index, source) = if self.chars.peek() == Some(&':') {
+      self.bump();
+      if first.as_usize() < header::slots::<T>() {
+        return Err(reserved);
+      }
+      (Some(first), self.number::<T>()?)
+    } else {
+      (None, first)
+    };
+    let target = self.number::<T>()?;
+    self.expect(')', "`)`")?;
+    Ok(Entry { index, source, target, line, column })
+  }
+}
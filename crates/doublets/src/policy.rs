@@ -0,0 +1,52 @@
+/// What happens to the usages of a deleted link
+///
+/// A usage is another link referencing the deleted one as its source or
+/// target. References of a link to itself are not usages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnDelete {
+  /// Delete the link and leave its usages dangling
+  #[default]
+  Allow,
+  /// Refuse to delete a link with usages with [`Error::HasUsages`]
+  ///
+  /// [`Error::HasUsages`]: crate::Error::HasUsages
+  Forbid,
+  /// Delete the usages as well, and their usages in turn
+  Cascade,
+  /// Replace references to the deleted link in its usages with null
+  Nullify,
+}
+
+/// Integrity rules enforced by a [`Store`](crate::Store)
+///
+/// The default policy enforces nothing, as the store did before policies
+/// existed.
+///
+/// # Examples
+///
+/// ```
+/// use doublets::{Doublets, Error, OnDelete, Policy, create_heap_store};
+///
+/// let policy = Policy { unique: true, on_delete: OnDelete::Forbid };
+/// let mut store = create_heap_store::<usize>().unwrap().with_policy(policy);
+/// let a = store.create_point().unwrap();
+/// let b = store.create_point().unwrap();
+/// let link = store.create_link(a, b).unwrap();
+///
+/// assert_eq!(store.create_link(a, b), Err(Error::AlreadyExists(link, a, b)));
+/// assert_eq!(store.delete_link(a), Err(Error::HasUsages(a)));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Policy {
+  /// Refuse to create or update a link into a duplicate of another one
+  /// with [`Error::AlreadyExists`]
+  ///
+  /// Links with null source and target are exempt: clearing references
+  /// with [`OnDelete::Nullify`] or compaction can leave several of them,
+  /// and refusing those would block the deletes that caused them.
+  ///
+  /// [`Error::AlreadyExists`]: crate::Error::AlreadyExists
+  pub unique: bool,
+  /// What happens to the usages of a deleted link
+  pub on_delete: OnDelete,
+}
@@ -0,0 +1,165 @@
+use {
+  crate::{
+    Doublets, Flow, Index, Link, Links, ReadHandler, Result, WriteHandler,
+  },
+  core::marker::PhantomData,
+  std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// [`Links`] store shared between threads
+///
+/// Reads take the lock shared, so any number of threads run `get`, `search`
+/// or `each` at once, while writes take it exclusively. Every method locks
+/// for the duration of a single call; [`SharedStore::read`] and
+/// [`SharedStore::write`] hold the lock across several calls, e.g. for a
+/// [`Store::transaction`](crate::Store::transaction).
+///
+/// # Panics
+///
+/// A writer that panics may leave the store half-changed, so every later
+/// call panics as well instead of observing it.
+///
+/// # Example
+///
+/// ```
+/// use {
+///   doublets::{SharedStore, create_heap_store},
+///   std::thread,
+/// };
+///
+/// let shared = SharedStore::new(create_heap_store::<usize>()?);
+/// let point = shared.create_point()?;
+///
+/// thread::scope(|scope| {
+///   for _ in 0..4 {
+///     scope.spawn(|| shared.get_or_create(point, point));
+///   }
+/// });
+/// assert_eq!(shared.count([]), 1);
+/// # Ok::<(), doublets::Error<usize>>(())
+/// ```
+pub struct SharedStore<T: Index, L: Links<T>> {
+  lock: RwLock<L>,
+  marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Index, L: Links<T>> SharedStore<T, L> {
+  pub fn new(links: L) -> Self {
+    Self { lock: RwLock::new(links), marker: PhantomData }
+  }
+
+  /// Unwrap the store once it is no longer shared
+  pub fn into_inner(self) -> L {
+    self.lock.into_inner().expect("store was poisoned by a panicked writer")
+  }
+
+  /// Access the store without locking, exclusive access proves no other
+  /// thread holds it
+  pub fn get_mut(&mut self) -> &mut L {
+    self.lock.get_mut().expect("store was poisoned by a panicked writer")
+  }
+
+  /// Lock the store for reading, blocking while a writer holds it
+  pub fn read(&self) -> RwLockReadGuard<'_, L> {
+    self.lock.read().expect("store was poisoned by a panicked writer")
+  }
+
+  /// Lock the store for writing, blocking while anyone else holds it
+  pub fn write(&self) -> RwLockWriteGuard<'_, L> {
+    self.lock.write().expect("store was poisoned by a panicked writer")
+  }
+
+  /// See [`Links::get`]
+  pub fn get(&self, index: T) -> Option<Link<T>> {
+    self.read().get(index)
+  }
+
+  /// See [`Links::count`]
+  pub fn count<const N: usize>(&self, query: [T; N]) -> T {
+    self.read().count(query)
+  }
+
+  /// See [`Doublets::search`]
+  pub fn search(&self, source: T, target: T) -> Option<T> {
+    self.read().search(source, target)
+  }
+
+  /// See [`Links::each`], the store stays locked until iteration ends
+  pub fn each<const N: usize, H: ReadHandler<T>>(
+    &self,
+    query: [T; N],
+    handler: &mut H,
+  ) -> Flow {
+    self.read().each(query, handler)
+  }
+
+  /// Collect links matching a query, see [`Links::query`]
+  pub fn collect<const N: usize>(&self, query: [T; N]) -> Vec<Link<T>> {
+    self.read().query(query).collect()
+  }
+
+  /// See [`Links::create`]
+  pub fn create<const N: usize, H: WriteHandler<T>>(
+    &self,
+    query: [T; N],
+    handler: &mut H,
+  ) -> Result<Flow, T> {
+    self.write().create(query, handler)
+  }
+
+  /// See [`Links::update`]
+  pub fn update<const N1: usize, const N2: usize, H: WriteHandler<T>>(
+    &self,
+    query: [T; N1],
+    change: [T; N2],
+    handler: &mut H,
+  ) -> Result<Flow, T> {
+    self.write().update(query, change, handler)
+  }
+
+  /// See [`Links::delete`]
+  pub fn delete<const N: usize, H: WriteHandler<T>>(
+    &self,
+    query: [T; N],
+    handler: &mut H,
+  ) -> Result<Flow, T> {
+    self.write().delete(query, handler)
+  }
+
+  /// See [`Doublets::create_link`]
+  pub fn create_link(&self, source: T, target: T) -> Result<T, T> {
+    self.write().create_link(source, target)
+  }
+
+  /// See [`Doublets::create_point`]
+  pub fn create_point(&self) -> Result<T, T> {
+    self.write().create_point()
+  }
+
+  /// See [`Doublets::update_link`]
+  pub fn update_link(&self, index: T, source: T, target: T) -> Result<T, T> {
+    self.write().update_link(index, source, target)
+  }
+
+  /// See [`Doublets::delete_link`]
+  pub fn delete_link(&self, index: T) -> Result<T, T> {
+    self.write().delete_link(index)
+  }
+
+  /// See [`Doublets::get_or_create`]
+  ///
+  /// Searches under a read lock first, and again under the write lock
+  /// before creating, so racing threads end up with the same link.
+  pub fn get_or_create(&self, source: T, target: T) -> Result<T, T> {
+    if let Some(index) = self.search(source, target) {
+      return Ok(index);
+    }
+    self.write().get_or_create(source, target)
+  }
+}
+
+impl<T: Index, L: Links<T>> From<L> for SharedStore<T, L> {
+  fn from(links: L) -> Self {
+    Self::new(links)
+  }
+}
@@ -0,0 +1,43 @@
+use crate::Index;
+
+/// Shape of one index of a store
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TreeStats {
+  /// Number of levels, zero for an empty index
+  pub height: usize,
+  /// Mean number of nodes above a link
+  pub average_depth: f64,
+}
+
+impl TreeStats {
+  pub(crate) fn new(depths: &[usize]) -> Self {
+    let height = depths.iter().max().map_or(0, |&max| max + 1);
+    let average_depth = match depths.len() {
+      0 => 0.0,
+      len => depths.iter().sum::<usize>() as f64 / len as f64,
+    };
+    Self { height, average_depth }
+  }
+}
+
+/// Memory use and index shape of a store, see
+/// [`Store::stats`](crate::Store::stats)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats<T: Index> {
+  /// Slots handed out so far, including free and reserved ones
+  pub allocated: usize,
+  /// Number of links
+  pub links: usize,
+  /// Length of the free list
+  pub free: usize,
+  /// Slots the memory holds
+  pub capacity: usize,
+  /// Bytes the memory holds
+  pub bytes: usize,
+  pub source_tree: TreeStats,
+  pub target_tree: TreeStats,
+  /// Link used as the source by the most other links, with their number
+  pub fan_out: Option<(T, usize)>,
+  /// Link used as the target by the most other links, with their number
+  pub fan_in: Option<(T, usize)>,
+}
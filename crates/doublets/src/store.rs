@@ -1,362 +1,1569 @@
 use crate::{
-  Error, Flow, Index, Link, Links, ReadHandler, Result, WriteHandler,
+  Error, Flow, FormatMismatch, Index, Link, Links, ReadHandler, Result,
+  SbtStrategy, TreeSide, TreeStrategy, WriteHandler,
+  dump::{self, Dumper},
+  header::{self, Format, Header},
+  journal::{Journal, Op, Record},
+  notation::{self, Entry, Numbering, Syntax},
+  policy::{OnDelete, Policy},
+  stats::{Stats, TreeStats},
+  strategy::{SourceSide, TargetSide},
+  subscription::{Filter, Hooks, Subscription},
+  transaction::{Transaction, Undo},
+  verify::{Report, Side, Violation},
 };
 
 use {
+  core::{cmp::Reverse, ops::Range},
   mem::{Alloc, RawMem},
-  trees::{AdaptiveRadix, Node, SizeBalanced, Tree},
+  std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+  },
+  trees::Node,
 };
 
-/// Marker trait for tree strategies that can insert and remove from trees.
+/// Number of slots a freshly created store starts with
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Bulk loads of at least this share of all links rebuild the trees,
+/// smaller ones are inserted one by one
+const REBUILD_SHARE: usize = 4;
+
+/// Query/change array arity constants for method signatures
+const NC_SOURCE: usize = 2; // Change includes source
+const NC_TARGET: usize = 3; // Change includes target
+
+/// Raw link data stored in memory with tree navigation
 ///
-/// Both SBT (Size-Balanced Tree) and ART (Adaptive Radix Tree) strategies
-/// maintain BST ordering by (source, target) or (target, source) tuples,
-/// enabling O(log n + k) range traversal for all strategies.
-pub trait TreeStrategy<T: trees::Idx>: Send + Sync {
-  /// Insert into tree using this strategy
-  fn insert<Tr>(tree: &mut Tr, root: Option<T>, idx: T) -> Option<T>
-  where
-    Tr: Tree<T> + SizeBalanced<T> + AdaptiveRadix<T>;
+/// Stores source, target, and tree index information for efficient
+/// searching by source and target using size-balanced trees.
+/// All fields use the index type, so a `RawLink<u32>` takes half the
+/// memory of a `RawLink<u64>`.
+///
+/// This is the internal representation used by [`Store`]. Users should
+/// typically interact with [`Link`] instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct RawLink<T> {
+  pub(crate) source: T,
+  pub(crate) target: T,
+  /// Tree node for indexing by source
+  pub(crate) source_tree: Node<T>,
+  /// Tree node for indexing by target
+  pub(crate) target_tree: Node<T>,
+  /// Special marker: T::MAX if in free list, 0 otherwise
+  pub(crate) is_free: T,
+}
 
-  /// Remove from tree using this strategy
-  fn remove<Tr>(tree: &mut Tr, root: Option<T>, idx: T) -> Option<T>
-  where
-    Tr: Tree<T> + SizeBalanced<T> + AdaptiveRadix<T>;
+impl<T: Index> Default for RawLink<T> {
+  fn default() -> Self {
+    bytemuck::Zeroable::zeroed()
+  }
 }
 
-/// Size-Balanced Tree strategy marker
-pub struct SbtStrategy;
+// SAFETY: `repr(C)` of nine `T: Pod` fields, tree nodes included, so there
+// is no padding and every bit pattern is valid
+unsafe impl<T: Index> bytemuck::Pod for RawLink<T> {}
+unsafe impl<T: Index> bytemuck::Zeroable for RawLink<T> {}
 
-impl<T: trees::Idx> TreeStrategy<T> for SbtStrategy {
-  fn insert<Tr>(tree: &mut Tr, root: Option<T>, idx: T) -> Option<T>
-  where
-    Tr: Tree<T> + SizeBalanced<T> + AdaptiveRadix<T>,
-  {
-    SizeBalanced::insert_sbt(tree, root, idx)
+/// Doublets store implementation using tree-based indexing
+///
+/// Generic over tree strategies for both source and target indexing.
+///
+/// The first slots of the memory keep the store header, so the first link
+/// gets index 3 with `u8` indices, 2 with `u16` and 1 with wider ones.
+///
+/// # Type Parameters
+/// * `T` - Index type (usually usize)
+/// * `M` - Memory backend (default: heap allocation)
+/// * `SourceStrategy` - Tree strategy for source indexing
+///   (SbtStrategy or ArtStrategy)
+/// * `TargetStrategy` - Tree strategy for target indexing
+///   (SbtStrategy or ArtStrategy)
+///
+/// # Examples
+///
+/// The recommended way to create a store is using [`create_heap_store`]:
+///
+/// ```
+/// use doublets::{Doublets, create_heap_store};
+///
+/// let mut store = create_heap_store::<usize>().unwrap();
+/// let a = store.create_point().unwrap();
+/// ```
+///
+/// For custom tree strategies, use `Store::new()` directly:
+///
+/// ```
+/// use doublets::{Doublets, SbtStrategy, ArtStrategy, RawLink, Store};
+/// use mem::Alloc;
+///
+/// // Create a store with SBT for source and ART for target indexing
+/// type Mem = Alloc<RawLink<usize>>;
+/// let mut store: Store<usize, Mem, SbtStrategy, ArtStrategy> =
+///   Store::new(Alloc::new()).unwrap();
+/// let a = store.create_point().unwrap();
+/// ```
+pub struct Store<
+  T,
+  M = Alloc<RawLink<T>>,
+  SourceStrategy = SbtStrategy,
+  TargetStrategy = SbtStrategy,
+> where
+  T: Index,
+  M: RawMem<Item = RawLink<T>> + Send + Sync,
+  SourceStrategy: TreeStrategy<T>,
+  TargetStrategy: TreeStrategy<T>,
+{
+  mem: M,
+  allocated: usize,
+  free_count: usize,
+  first_free: Option<T>,
+  /// Root of tree indexing links by source
+  source_root: Option<T>,
+  /// Root of tree indexing links by target
+  target_root: Option<T>,
+  /// Side storage of the source index
+  source_storage: SourceStrategy::Storage,
+  /// Side storage of the target index
+  target_storage: TargetStrategy::Storage,
+  /// Undo log of the running transaction
+  undo: Option<Vec<Undo<T>>>,
+  /// Write-ahead journal of the changes since the last checkpoint
+  journal: Option<Journal>,
+  /// Integrity rules enforced on changes
+  policy: Policy,
+  /// Listeners and triggers of changes
+  hooks: Hooks<T>,
+  _phantom: core::marker::PhantomData<(T, SourceStrategy, TargetStrategy)>,
+}
+
+impl<T, M, SourceStrategy, TargetStrategy>
+  Store<T, M, SourceStrategy, TargetStrategy>
+where
+  T: Index,
+  M: RawMem<Item = RawLink<T>> + Send + Sync,
+  SourceStrategy: TreeStrategy<T>,
+  TargetStrategy: TreeStrategy<T>,
+{
+  /// Create a new doublets store with default capacity
+  ///
+  /// Same as [`Store::create`].
+  pub fn new(mem: M) -> Result<Self, T> {
+    Self::create(mem)
+  }
+
+  /// Create an empty store with default capacity
+  ///
+  /// Anything `mem` already holds in its first slots is overwritten, use
+  /// [`Store::open`] to continue working with previously stored links.
+  pub fn create(mut mem: M) -> Result<Self, T> {
+    mem.grow(DEFAULT_CAPACITY).map_err(|_| Error::AllocationFailed)?.zeroed();
+
+    let header = Header::empty::<T>(Self::FORMAT);
+    let mut store = Self::with_header(mem, header)?;
+    store.sync_header();
+    Ok(store)
+  }
+
+  /// Open a store previously written into `mem`
+  ///
+  /// Counters, free list and tree roots are reloaded from the header slot,
+  /// so the store continues exactly where the last session left it.
+  /// Indexes kept outside of the links, such as [`ArtStrategy`] tries,
+  /// are rebuilt from the links.
+  /// Memory holding only zeroes is treated as a fresh empty store.
+  ///
+  /// [`ArtStrategy`]: crate::ArtStrategy
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::IncompatibleFormat`] if the memory was written by a
+  /// store with another format version, link layout, index type or tree
+  /// strategies, or if it does not hold a store at all.
+  ///
+  /// # Examples
+  ///
+  /// ```ignore
+  /// use doublets::{Doublets, Store};
+  /// use mem::FileMapped;
+  ///
+  /// let mem = FileMapped::from_path("links.bin")?;
+  /// // SAFETY: file always represents initialized bytes
+  /// let mut store: Store<usize, _> = unsafe { Store::open(mem)? };
+  /// store.create_point()?;
+  /// ```
+  ///
+  /// # Safety
+  ///
+  /// `mem` must grow into already initialized memory, as
+  /// [`FileMapped`](mem::FileMapped) does.
+  pub unsafe fn open(mut mem: M) -> Result<Self, T> {
+    let slots = header::slots::<T>();
+    let len = mem.as_slice().len();
+    if len < slots {
+      let page = mem.grow(slots - len).map_err(|_| Error::AllocationFailed)?;
+      // SAFETY: caller guarantees that grown memory is initialized
+      unsafe { page.assumed() };
+    }
+
+    let format = Self::FORMAT;
+    let bytes = bytemuck::cast_slice(&mem.as_slice()[..slots]);
+    let header = match Header::read(bytes, &format) {
+      None => Header::empty::<T>(format),
+      Some(header) => header.map_err(Error::IncompatibleFormat)?,
+    };
+
+    let len = mem.as_slice().len();
+    if header.allocated > len {
+      let addition = header.allocated - len;
+      let page = mem.grow(addition).map_err(|_| Error::AllocationFailed)?;
+      // SAFETY: caller guarantees that grown memory is initialized
+      unsafe { page.assumed() };
+    }
+
+    // keep `allocated < len` as `allocate_index` expects
+    let len = mem.as_slice().len();
+    let headroom = DEFAULT_CAPACITY.max(header.allocated + 1);
+    if len < headroom {
+      mem.grow(headroom - len).map_err(|_| Error::AllocationFailed)?.zeroed();
+    }
+
+    let mut store = Self::with_header(mem, header)?;
+    store.rebuild_side_indexes();
+    store.sync_header();
+    Ok(store)
+  }
+
+  /// Enforce `policy` on the following changes
+  #[must_use]
+  pub fn with_policy(mut self, policy: Policy) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// Enforce `policy` on the following changes
+  ///
+  /// Links that already break the policy are left as they are.
+  pub fn set_policy(&mut self, policy: Policy) {
+    self.policy = policy;
   }
 
-  fn remove<Tr>(tree: &mut Tr, root: Option<T>, idx: T) -> Option<T>
+  /// Integrity rules enforced on changes
+  pub fn policy(&self) -> Policy {
+    self.policy
+  }
+
+  /// Call `listener` after every change matching `filter`
+  ///
+  /// Listeners get the same `(before, after)` states as a
+  /// [`WriteHandler`], with [`Link::nothing`] before a create and after a
+  /// delete, whichever operation made the change. Reverts of a failed
+  /// transaction are reported as ordinary changes, the same way they are
+  /// journaled. Bulk loads and imports report a create per link,
+  /// compaction and rebuilding the indexes report nothing.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use {
+  ///   doublets::{Doublets, Filter, create_heap_store},
+  ///   std::sync::{Arc, Mutex},
+  /// };
+  ///
+  /// let mut store = create_heap_store::<usize>().unwrap();
+  /// let seen = Arc::new(Mutex::new(Vec::new()));
+  /// let log = Arc::clone(&seen);
+  /// store.subscribe(Filter::pattern(1, 1), move |before, after| {
+  ///   log.lock().unwrap().push((before, after));
+  /// });
+  ///
+  /// let a = store.create_point().unwrap();
+  /// store.create_link(a, a + 1).unwrap();
+  /// // the point `(1: 1 1)` is reported by a single create, the link
+  /// // doesn't match
+  /// assert_eq!(seen.lock().unwrap().len(), 1);
+  /// ```
+  pub fn subscribe<F>(&mut self, filter: Filter<T>, listener: F) -> Subscription
   where
-    Tr: Tree<T> + SizeBalanced<T> + AdaptiveRadix<T>,
+    F: FnMut(Link<T>, Link<T>) + Send + Sync + 'static,
   {
-    SizeBalanced::remove_sbt(tree, root, idx)
+    self.hooks.listen(filter, Box::new(listener))
   }
-}
 
-/// Adaptive Radix Tree strategy marker.
-///
-/// ART uses the tree's `is_left_of` comparison to maintain BST ordering,
-/// enabling efficient range traversal. The key-byte mechanism is used
-/// internally for cache-efficient storage layout, while the ordering
-/// respects (source, target) or (target, source) tuple comparison.
-pub struct ArtStrategy;
-
-impl<T: trees::Idx> TreeStrategy<T> for ArtStrategy {
-  fn insert<Tr>(tree: &mut Tr, root: Option<T>, idx: T) -> Option<T>
+  /// Ask `trigger` before every change matching `filter`
+  ///
+  /// Triggers get the same `(before, after)` states as listeners, while
+  /// nothing is changed yet. An error vetoes the change and is returned by
+  /// the operation, so it is reverted along with the rest of the
+  /// operation, as when a transaction fails. Bulk loads and imports ask
+  /// about every link before loading any. Reverts can't be vetoed.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use doublets::{Doublets, Error, Filter, Index, create_heap_store};
+  ///
+  /// let mut store = create_heap_store::<usize>().unwrap();
+  /// let a = store.create_point().unwrap();
+  /// let deletes = Filter { create: false, update: false, ..Filter::default() };
+  /// store.add_trigger(deletes, |before, _after| {
+  ///   Err(Error::Rejected(before.index))
+  /// });
+  ///
+  /// assert_eq!(store.delete_link(a), Err(Error::Rejected(a)));
+  /// assert_eq!(store.count_all(), 1);
+  /// ```
+  pub fn add_trigger<F>(
+    &mut self,
+    filter: Filter<T>,
+    trigger: F,
+  ) -> Subscription
   where
-    Tr: Tree<T> + SizeBalanced<T> + AdaptiveRadix<T>,
+    F: FnMut(Link<T>, Link<T>) -> Result<(), T> + Send + Sync + 'static,
   {
-    // Use SBT-style BST insertion to maintain proper ordering for range queries
-    // This ensures in-order traversal visits nodes in (source, target) order
-    SizeBalanced::insert_sbt(tree, root, idx)
+    self.hooks.trigger(filter, Box::new(trigger))
+  }
+
+  /// Remove a listener or trigger, returns whether it was registered
+  pub fn unsubscribe(&mut self, subscription: Subscription) -> bool {
+    self.hooks.remove(subscription)
+  }
+
+  /// Fail with [`Error::AlreadyExists`] if the unique policy is on and
+  /// another link than `index` has the given source and target
+  ///
+  /// Links with null source and target are exempt, as several of them are
+  /// left behind when [`OnDelete::Nullify`] or [`Store::compact`] clears
+  /// their references.
+  fn check_unique(&self, index: T, source: T, target: T) -> Result<(), T> {
+    if !self.policy.unique || (source.is_zero() && target.is_zero()) {
+      return Ok(());
+    }
+    match self.query([T::ANY, source, target]).find(|link| link.index != index)
+    {
+      Some(link) => Err(Error::AlreadyExists(link.index, source, target)),
+      None => Ok(()),
+    }
+  }
+
+  /// Links other than `index` itself referencing it
+  fn usages(&self, index: T) -> Vec<Link<T>> {
+    let mut usages: Vec<_> = self
+      .query([T::ANY, index, T::ANY])
+      .chain(self.query([T::ANY, T::ANY, index]))
+      .filter(|link| link.index != index)
+      .collect();
+    usages.sort_unstable_by_key(|link| link.index);
+    usages.dedup_by_key(|link| link.index);
+    usages
+  }
+
+  /// Links deleted together with `index` by [`OnDelete::Cascade`], in the
+  /// order they are found
+  fn cascade(&self, index: T) -> Vec<T> {
+    let mut doomed = vec![index];
+    let mut seen = BTreeSet::from([index]);
+    let mut next = 0;
+    while let Some(&current) = doomed.get(next) {
+      for usage in self.usages(current) {
+        if seen.insert(usage.index) {
+          doomed.push(usage.index);
+        }
+      }
+      next += 1;
+    }
+    doomed
+  }
+
+  /// Delete a single link regardless of the delete policy
+  fn delete_one<H: WriteHandler<T>>(
+    &mut self,
+    index: T,
+    handler: &mut H,
+  ) -> Result<Flow, T> {
+    let before = self.get(index).ok_or(Error::NotExists(index))?;
+
+    let after = Link::nothing();
+    self.hooks.check(before, after)?;
+    self.log(Op::Delete, index, before, after)?;
+    self.record(Undo::Delete { before });
+
+    // Detach from both trees before freeing
+    self.detach_from_source_tree(index);
+    self.detach_from_target_tree(index);
+
+    self.free_index(index);
+    self.sync_header();
+    self.hooks.notify(before, after);
+
+    Ok(handler.handle(before, after))
   }
 
-  fn remove<Tr>(tree: &mut Tr, root: Option<T>, idx: T) -> Option<T>
+  /// Indices of links matching a query, collected before changing them
+  fn matching<const N: usize>(&self, query: [T; N]) -> Vec<T> {
+    self.query(query).map(|link| link.index).collect()
+  }
+
+  /// Update a single existing link, `T::ANY` in `change` keeps the part
+  fn update_one<const N: usize, H: WriteHandler<T>>(
+    &mut self,
+    index: T,
+    change: [T; N],
+    handler: &mut H,
+  ) -> Result<Flow, T> {
+    // links can't be moved to another index
+    if N >= 1 && change[0] != T::ANY && change[0] != index {
+      return Err(Error::InvalidQuery);
+    }
+
+    let before = self.get(index).ok_or(Error::NotExists(index))?;
+
+    let new_source = if N >= NC_SOURCE && change[1] != T::ANY {
+      change[1]
+    } else {
+      before.source
+    };
+    let new_target = if N >= NC_TARGET && change[2] != T::ANY {
+      change[2]
+    } else {
+      before.target
+    };
+
+    // If source or target changed, update tree positions
+    let after = Link::new(index, new_source, new_target);
+    if new_source != before.source || new_target != before.target {
+      self.check_unique(index, new_source, new_target)?;
+      self.hooks.check(before, after)?;
+      self.log(Op::Update, index, before, after)?;
+      self.record(Undo::Update { before });
+
+      // Detach from old positions in both trees
+      self.detach_from_source_tree(index);
+      self.detach_from_target_tree(index);
+
+      // Update the link data
+      if let Some(raw) = self.repr_mut_at(index) {
+        raw.source = new_source;
+        raw.target = new_target;
+      }
+
+      // Reattach to new positions in both trees
+      self.attach_to_source_tree(index);
+      self.attach_to_target_tree(index);
+
+      self.sync_header();
+      self.hooks.notify(before, after);
+    }
+
+    Ok(handler.handle(before, after))
+  }
+
+  /// Delete a single existing link, handling its usages by the policy
+  fn delete_by_policy<H: WriteHandler<T>>(
+    &mut self,
+    index: T,
+    handler: &mut H,
+  ) -> Result<Flow, T> {
+    match self.policy.on_delete {
+      OnDelete::Allow => self.delete_one(index, handler),
+      OnDelete::Forbid => {
+        if self.usages(index).is_empty() {
+          self.delete_one(index, handler)
+        } else {
+          Err(Error::HasUsages(index))
+        }
+      }
+      // usages first, so none of them is left dangling on failure
+      OnDelete::Cascade => self.transaction(|tx| {
+        let mut flow = Flow::Continue;
+        for doomed in tx.cascade(index).into_iter().rev() {
+          flow = tx.delete_one(doomed, handler)?;
+        }
+        Ok(flow)
+      }),
+      OnDelete::Nullify => self.transaction(|tx| {
+        for usage in tx.usages(index) {
+          let null = |part: T| if part == index { T::ZERO } else { part };
+          let change = [usage.index, null(usage.source), null(usage.target)];
+          tx.update_one(usage.index, change, handler)?;
+        }
+        tx.delete_one(index, handler)
+      }),
+    }
+  }
+
+  /// Run `f` as a transaction, making its changes atomic
+  ///
+  /// Every create, update and delete done through the transaction is
+  /// recorded into an undo log. If `f` fails, panics or calls
+  /// [`Transaction::abort`], the changes are reverted in reverse order and
+  /// the store is left exactly as it was, including the free list.
+  /// Transactions can be nested, an inner one only reverts its own changes.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use doublets::{Doublets, create_heap_store};
+  ///
+  /// let mut store = create_heap_store::<usize>().unwrap();
+  /// let a = store.create_point().unwrap();
+  ///
+  /// let result = store.transaction(|tx| {
+  ///   tx.create_link(a, a)?;
+  ///   tx.delete_link(42)
+  /// });
+  /// assert!(result.is_err());
+  /// assert_eq!(store.count_all(), 1);
+  /// ```
+  pub fn transaction<R, F>(&mut self, f: F) -> Result<R, T>
   where
-    Tr: Tree<T> + SizeBalanced<T> + AdaptiveRadix<T>,
+    F: FnOnce(
+      &mut Transaction<'_, T, M, SourceStrategy, TargetStrategy>,
+    ) -> Result<R, T>,
   {
-    // Use SBT-style BST removal to maintain proper ordering for range queries
-    SizeBalanced::remove_sbt(tree, root, idx)
+    let mut tx = Transaction::new(self);
+    let result = f(&mut tx)?;
+    tx.commit();
+    Ok(result)
   }
-}
 
-/// Query/change array arity constants for method signatures
-const NC_SOURCE: usize = 2; // Change includes source
-const NC_TARGET: usize = 3; // Change includes target
+  /// Start recording changes, returns the undo log length and whether no
+  /// transaction was running before
+  pub(crate) fn begin_undo(&mut self) -> (usize, bool) {
+    match &self.undo {
+      Some(log) => (log.len(), false),
+      None => {
+        self.undo = Some(Vec::new());
+        (0, true)
+      }
+    }
+  }
 
-/// Raw link data stored in memory with tree navigation
-///
-/// Stores source, target, and tree index information for efficient
-/// searching by source and target using size-balanced trees.
-///
-/// This is the internal representation used by [`Store`]. Users should
-/// typically interact with [`Link`] instead.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-#[repr(C)]
-pub struct RawLink {
-  pub(crate) source: usize,
-  pub(crate) target: usize,
-  /// Tree node for indexing by source
-  pub(crate) source_tree: Node<usize>,
-  /// Tree node for indexing by target
-  pub(crate) target_tree: Node<usize>,
-  /// Special marker: usize::MAX if in free list, 0 otherwise
-  pub(crate) is_free: usize,
-}
+  /// Stop recording changes once the outermost transaction finishes
+  pub(crate) fn end_undo(&mut self, outermost: bool) {
+    if outermost {
+      self.undo = None;
+    }
+  }
 
-unsafe impl bytemuck::Pod for RawLink {}
-unsafe impl bytemuck::Zeroable for RawLink {}
+  /// Revert changes recorded after the undo log had `mark` entries
+  pub(crate) fn rollback_to(&mut self, mark: usize) {
+    let Some(log) = self.undo.as_mut() else {
+      return;
+    };
+    let undone = log.split_off(mark);
+    for undo in undone.into_iter().rev() {
+      self.revert(undo);
+    }
+    self.sync_header();
+  }
 
-/// Helper struct to implement Tree trait for source indexing with
-/// configurable strategy
-struct SourceTree<'a, M: RawMem<Item = RawLink>, S> {
-  mem: &'a mut M,
-  _strategy: core::marker::PhantomData<S>,
-}
+  /// Revert a single change, the ones after it must be reverted already
+  ///
+  /// Reverts are journaled as ordinary changes. They can't be refused, so
+  /// journal errors are ignored, recovery still ends up in a consistent
+  /// state.
+  fn revert(&mut self, undo: Undo<T>) {
+    match undo {
+      Undo::Create { index, fresh } => {
+        if let Some(link) = self.get(index) {
+          let _ = self.log(Op::Delete, index, link, Link::nothing());
+          self.hooks.notify(link, Link::nothing());
+        }
+        self.detach_from_source_tree(index);
+        self.detach_from_target_tree(index);
+        self.release_index(index, fresh);
+      }
+      Undo::Update { before } => {
+        if let Some(link) = self.get(before.index) {
+          let _ = self.log(Op::Update, before.index, link, before);
+          self.hooks.notify(link, before);
+        }
+        self.detach_from_source_tree(before.index);
+        self.detach_from_target_tree(before.index);
+        if let Some(raw) = self.repr_mut_at(before.index) {
+          raw.source = before.source;
+          raw.target = before.target;
+        }
+        self.attach_to_source_tree(before.index);
+        self.attach_to_target_tree(before.index);
+      }
+      Undo::Delete { before } => {
+        let _ = self.log(Op::Create, before.index, Link::nothing(), before);
+        self.hooks.notify(Link::nothing(), before);
+        // the deleted slot is still on top of the free list
+        debug_assert_eq!(self.first_free, Some(before.index));
+        let next_free = self.repr_at(before.index).map(|raw| raw.source);
+        self.first_free = next_free.filter(|next| !next.is_zero());
+        self.free_count -= 1;
+        if let Some(raw) = self.repr_mut_at(before.index) {
+          raw.source = before.source;
+          raw.target = before.target;
+          raw.is_free = T::ZERO;
+        }
+        self.attach_to_source_tree(before.index);
+        self.attach_to_target_tree(before.index);
+      }
+    }
+  }
 
-impl<'a, M: RawMem<Item = RawLink>, S> SourceTree<'a, M, S> {
-  fn new(mem: &'a mut M) -> Self {
-    Self { mem, _strategy: core::marker::PhantomData }
+  /// Record a change into the undo log of the running transaction
+  fn record(&mut self, undo: Undo<T>) {
+    if let Some(log) = &mut self.undo {
+      log.push(undo);
+    }
   }
-}
 
-impl<'a, M: RawMem<Item = RawLink>, S> Tree<usize> for SourceTree<'a, M, S> {
-  fn get(&self, idx: usize) -> Option<Node<usize>> {
-    let slice = self.mem.as_slice();
-    slice.get(idx).map(|raw| raw.source_tree)
+  /// Open a store previously written into `mem`, recovering it with
+  /// `journal`
+  ///
+  /// Same as [`Store::open`] followed by [`Store::attach_journal`].
+  ///
+  /// # Safety
+  ///
+  /// Same as for [`Store::open`].
+  pub unsafe fn open_with_journal(mem: M, journal: Journal) -> Result<Self, T> {
+    // SAFETY: guaranteed by the caller
+    let mut store = unsafe { Self::open(mem)? };
+    store.attach_journal(journal)?;
+    Ok(store)
   }
 
-  fn set(&mut self, idx: usize, node: Node<usize>) {
-    let slice = self.mem.as_mut_slice();
-    if let Some(raw) = slice.get_mut(idx) {
-      raw.source_tree = node;
+  /// Record every following change into `journal` before applying it
+  ///
+  /// Records left in the journal by a crashed session are replayed first:
+  /// each of them is applied to the links again, then the free list and
+  /// both trees are rebuilt from the links and a checkpoint is made.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::Io`] if the journal or the memory can't be accessed,
+  /// and [`Error::IncompatibleFormat`] for indices wider than 64 bits,
+  /// which records can't hold.
+  pub fn attach_journal(&mut self, mut journal: Journal) -> Result<(), T> {
+    if size_of::<T>() > size_of::<u64>() {
+      return Err(Error::IncompatibleFormat(FormatMismatch::IndexWidth {
+        found: size_of::<T>() as u8,
+        expected: size_of::<u64>() as u8,
+      }));
+    }
+    let records = journal.read::<T>().map_err(|err| Error::Io(err.kind()))?;
+    if !records.is_empty() {
+      self.recover(&records)?;
     }
+    self.journal = Some(journal);
+    self.checkpoint()
   }
 
-  fn left_mut(&mut self, idx: usize) -> Option<&mut usize> {
-    let slice = self.mem.as_mut_slice();
-    slice.get_mut(idx).and_then(|raw| raw.source_tree.left.as_mut())
+  /// Detach the journal after making a checkpoint
+  pub fn detach_journal(&mut self) -> Result<Option<Journal>, T> {
+    self.checkpoint()?;
+    Ok(self.journal.take())
   }
 
-  fn right_mut(&mut self, idx: usize) -> Option<&mut usize> {
-    let slice = self.mem.as_mut_slice();
-    slice.get_mut(idx).and_then(|raw| raw.source_tree.right.as_mut())
+  /// Flush the links to disk and empty the journal
+  ///
+  /// Does nothing but flushing if no journal is attached. Also made when
+  /// the store is dropped.
+  pub fn checkpoint(&mut self) -> Result<(), T> {
+    self.mem.sync().map_err(Self::mem_error)?;
+    if let Some(journal) = &mut self.journal {
+      journal.clear().map_err(|err| Error::Io(err.kind()))?;
+    }
+    Ok(())
   }
 
-  fn is_left_of(&self, first: usize, second: usize) -> bool {
-    let slice = self.mem.as_slice();
-    if let (Some(a), Some(b)) = (slice.get(first), slice.get(second)) {
-      // Compare by (source, target) tuple for source tree
-      (a.source, a.target) < (b.source, b.target)
+  /// Append a change to the journal, if one is attached
+  fn log(
+    &mut self,
+    op: Op,
+    index: T,
+    before: Link<T>,
+    after: Link<T>,
+  ) -> Result<(), T> {
+    self.log_all(&[Record { op, index, before, after }])
+  }
+
+  /// Append changes to the journal at once, if one is attached
+  ///
+  /// Either all of them are journaled or none. A full journal is emptied
+  /// by a checkpoint first, as the changes journaled so far are applied.
+  fn log_all(&mut self, records: &[Record<T>]) -> Result<(), T> {
+    if self.journal.as_ref().is_some_and(Journal::is_full) {
+      self.checkpoint()?;
+    }
+    let Some(journal) = &mut self.journal else {
+      return Ok(());
+    };
+    journal.append(records).map_err(|err| Error::Io(err.kind()))
+  }
+
+  /// Apply journaled changes to the links and rebuild everything else
+  fn recover(&mut self, records: &[Record<T>]) -> Result<(), T> {
+    for record in records {
+      let index = record.index.as_usize();
+      if index < header::slots::<T>() {
+        continue;
+      }
+      // keep `allocated < len` as `allocate_index` expects
+      let len = self.mem.as_slice().len();
+      if index + 1 >= len {
+        let addition = (index + 2 - len).max(len);
+        self.mem.grow(addition).map_err(|_| Error::AllocationFailed)?.zeroed();
+      }
+      self.allocated = self.allocated.max(index + 1);
+
+      let raw = &mut self.mem.as_mut_slice()[index];
+      match record.op {
+        Op::Create | Op::Update => {
+          raw.source = record.after.source;
+          raw.target = record.after.target;
+          raw.is_free = T::ZERO;
+        }
+        Op::Delete => raw.is_free = T::MAX,
+      }
+    }
+    self.rebuild();
+    Ok(())
+  }
+
+  /// Rebuild the free list and both indexes from the sources, targets and
+  /// free markers of the links
+  ///
+  /// Salvages a store whose tree nodes or free list were damaged, for
+  /// example by a crash without a journal or by a corrupted file. Tree
+  /// nodes stored in the links are discarded, every slot marked as free is
+  /// put on a new free list and every other one is indexed again as a
+  /// link, taking O(n log n) time. Use [`Store::verify`] to find out
+  /// whether a rebuild is needed.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::InvalidQuery`] inside a transaction, since the free
+  /// list it relies on to revert deletions is rebuilt in another order.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use doublets::{Doublets, create_heap_store};
+  ///
+  /// let mut store = create_heap_store::<usize>().unwrap();
+  /// let a = store.create_point().unwrap();
+  /// let b = store.create_point().unwrap();
+  /// let link = store.create_link(a, b).unwrap();
+  ///
+  /// store.rebuild_indexes().unwrap();
+  /// assert!(store.verify().is_consistent());
+  /// assert_eq!(store.search(a, b), Some(link));
+  /// ```
+  pub fn rebuild_indexes(&mut self) -> Result<(), T> {
+    if self.undo.is_some() {
+      return Err(Error::InvalidQuery);
+    }
+    self.rebuild();
+    Ok(())
+  }
+
+  /// Rebuild the free list and both trees from the links alone
+  fn rebuild(&mut self) {
+    self.first_free = None;
+    self.free_count = 0;
+
+    let slots = header::slots::<T>();
+    // walk backwards so the lowest free index is reused first
+    for i in (slots..self.allocated).rev() {
+      let raw = &mut self.mem.as_mut_slice()[i];
+      if raw.is_free == T::MAX {
+        self.free_index(T::from_usize(i));
+      } else {
+        raw.is_free = T::ZERO;
+      }
+    }
+    self.build_trees();
+    self.sync_header();
+  }
+
+  /// Build both trees from scratch out of the links sorted by their keys
+  fn build_trees(&mut self) {
+    let slots = header::slots::<T>();
+    let mut sorted: Vec<_> = (slots..self.allocated)
+      .map(T::from_usize)
+      .filter(|&index| self.exists(index))
+      .collect();
+
+    let links = self.mem.as_mut_slice();
+    for &index in &sorted {
+      let raw = &mut links[index.as_usize()];
+      raw.source_tree = Node::default();
+      raw.target_tree = Node::default();
+    }
+
+    sorted.sort_unstable_by_key(|&index| {
+      (SourceSide::key(&links[index.as_usize()]), index)
+    });
+    self.source_storage = Default::default();
+    self.source_root = SourceStrategy::build::<SourceSide>(
+      links,
+      &mut self.source_storage,
+      &sorted,
+    );
+
+    sorted.sort_unstable_by_key(|&index| {
+      (TargetSide::key(&links[index.as_usize()]), index)
+    });
+    self.target_storage = Default::default();
+    self.target_root = TargetStrategy::build::<TargetSide>(
+      links,
+      &mut self.target_storage,
+      &sorted,
+    );
+  }
+
+  /// Check the store for consistency
+  ///
+  /// Every link must be reached exactly once in both indexes, in key order
+  /// and with correct subtree sizes for in-place trees. The free list must
+  /// hold every free slot once, end with the null link and match the free
+  /// count. Sources and targets must reference existing links or be null.
+  ///
+  /// Nothing is repaired, the report lists every violation found.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use doublets::{Doublets, create_heap_store};
+  ///
+  /// let mut store = create_heap_store::<usize>().unwrap();
+  /// let a = store.create_point().unwrap();
+  /// store.create_link(a, a).unwrap();
+  ///
+  /// let report = store.verify();
+  /// assert!(report.is_consistent());
+  /// assert_eq!(report.links, 2);
+  /// ```
+  pub fn verify(&self) -> Report<T> {
+    let links = self.mem.as_slice();
+    let live = |index: T| self.exists(index);
+    let source = SourceStrategy::verify::<SourceSide>(
+      links,
+      &self.source_storage,
+      self.source_root,
+      &live,
+    );
+    let target = TargetStrategy::verify::<TargetSide>(
+      links,
+      &self.target_storage,
+      self.target_root,
+      &live,
+    );
+    let tree = |side| move |fault| Violation::Tree { side, fault };
+    let mut violations: Vec<_> = source
+      .into_iter()
+      .map(tree(Side::Source))
+      .chain(target.into_iter().map(tree(Side::Target)))
+      .collect();
+
+    self.verify_free_list(&mut violations);
+
+    let mut count = 0;
+    for link in self.query([]) {
+      count += 1;
+      for reference in [link.source, link.target] {
+        if !reference.is_zero() && !self.exists(reference) {
+          violations.push(Violation::Dangling { index: link.index, reference });
+        }
+      }
+    }
+    Report { links: count, violations }
+  }
+
+  /// Measure the memory use and the shape of both indexes
+  ///
+  /// Visits every link and every index node once. Fan-in and fan-out
+  /// count usages, as [`Doublets::count_usages`] does, ties go to the
+  /// lowest index.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use doublets::{Doublets, create_heap_store};
+  ///
+  /// let mut store = create_heap_store::<usize>().unwrap();
+  /// let a = store.create_point().unwrap();
+  /// let b = store.create_point().unwrap();
+  /// store.create_link(a, b).unwrap();
+  /// store.create_link(a, a).unwrap();
+  ///
+  /// let stats = store.stats();
+  /// assert_eq!(stats.links, 4);
+  /// assert_eq!(stats.fan_out, Some((a, 2)));
+  /// assert_eq!(stats.fan_in, Some((a, 1)));
+  /// assert_eq!(stats.source_tree.height, 3);
+  /// ```
+  pub fn stats(&self) -> Stats<T> {
+    let links = self.mem.as_slice();
+    let mut fan_out = vec![0; self.allocated];
+    let mut fan_in = vec![0; self.allocated];
+    let mut count = 0;
+    for link in self.query([]) {
+      count += 1;
+      for (part, usages) in
+        [(link.source, &mut fan_out), (link.target, &mut fan_in)]
+      {
+        if part != link.index
+          && let Some(usages) = usages.get_mut(part.as_usize())
+        {
+          *usages += 1;
+        }
+      }
+    }
+    let largest = |usages: &[usize]| {
+      let (index, &most) = usages
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| self.exists(T::from_usize(index)))
+        .min_by_key(|&(index, &usages)| (Reverse(usages), index))?;
+      (most > 0).then(|| (T::from_usize(index), most))
+    };
+
+    let source = SourceStrategy::depths::<SourceSide>(
+      links,
+      &self.source_storage,
+      self.source_root,
+    );
+    let target = TargetStrategy::depths::<TargetSide>(
+      links,
+      &self.target_storage,
+      self.target_root,
+    );
+    Stats {
+      allocated: self.allocated,
+      links: count,
+      free: self.free_count,
+      capacity: links.len(),
+      bytes: size_of_val(links),
+      source_tree: TreeStats::new(&source),
+      target_tree: TreeStats::new(&target),
+      fan_out: largest(&fan_out),
+      fan_in: largest(&fan_in),
+    }
+  }
+
+  /// Walk the free list, reporting loops, slots that are not free and
+  /// free slots left out of it
+  fn verify_free_list(&self, violations: &mut Vec<Violation<T>>) {
+    let slots = header::slots::<T>();
+    let mut listed = vec![false; self.allocated];
+    let mut count = 0;
+    let mut next = self.first_free;
+    while let Some(index) = next {
+      let i = index.as_usize();
+      let raw = self.repr_at(index).filter(|raw| {
+        (slots..self.allocated).contains(&i) && raw.is_free == T::MAX
+      });
+      let Some(raw) = raw else {
+        violations.push(Violation::FreeListBroken(index));
+        break;
+      };
+      if core::mem::replace(&mut listed[i], true) {
+        violations.push(Violation::FreeListCycle(index));
+        break;
+      }
+      count += 1;
+      next = Some(raw.source).filter(|next| !next.is_zero());
+    }
+
+    for (i, &listed) in listed.iter().enumerate().skip(slots) {
+      let free =
+        self.mem.as_slice().get(i).is_some_and(|raw| raw.is_free == T::MAX);
+      if free && !listed {
+        violations.push(Violation::Unlisted(T::from_usize(i)));
+      }
+    }
+    if count != self.free_count {
+      violations
+        .push(Violation::FreeCount { stored: self.free_count, actual: count });
+    }
+  }
+
+  /// Append links with the given sources and targets in a single pass
+  ///
+  /// Meant for large imports: memory is reserved once, the links are
+  /// written one after another past every allocated slot, and both trees
+  /// are then built from scratch, perfectly balanced, out of all links
+  /// sorted by their keys. That takes O(n log n) time for sorting and
+  /// linear time for building, instead of a rebalancing insert per link
+  /// and tree. Batches of less than a quarter of all links are inserted
+  /// into the trees instead, which is cheaper than rebuilding them over
+  /// a much larger store. Free slots are not reused.
+  ///
+  /// Returns the range of indices given to the new links, in the order of
+  /// `pairs`.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::AlreadyExists`] if the unique policy is on and a
+  /// pair duplicates another one or an existing link,
+  /// [`Error::InvalidQuery`] if a pair holds `T::ANY` and
+  /// [`Error::Overflow`] if the indices would run out. Nothing is loaded
+  /// then.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use doublets::{Doublets, Links, Link, create_heap_store};
+  ///
+  /// let mut store = create_heap_store::<usize>().unwrap();
+  /// let links = store.bulk_load([(1, 2), (2, 1), (1, 1)]).unwrap();
+  ///
+  /// assert_eq!(links, 1..4);
+  /// assert_eq!(store.get(2), Some(Link::new(2, 2, 1)));
+  /// assert_eq!(store.search(1, 1), Some(3));
+  /// ```
+  pub fn bulk_load<I>(&mut self, pairs: I) -> Result<Range<T>, T>
+  where
+    I: IntoIterator<Item = (T, T)>,
+  {
+    let pairs: Vec<_> = pairs.into_iter().collect();
+    let first = self.allocated;
+    let end = first.checked_add(pairs.len()).ok_or(Error::Overflow)?;
+    // `T::MAX` is never handed out as an index
+    let range = T::try_from_usize(end)
+      .filter(|_| end <= T::MAX.as_usize())
+      .map(|end| T::from_usize(first)..end)
+      .ok_or(Error::Overflow)?;
+    if pairs.is_empty() {
+      return Ok(range);
+    }
+    if pairs
+      .iter()
+      .any(|&(source, target)| source == T::ANY || target == T::ANY)
+    {
+      return Err(Error::InvalidQuery);
+    }
+    if self.policy.unique {
+      self.check_unique_pairs(&pairs, |i| T::from_usize(first + i))?;
+    }
+    let created = pairs.iter().enumerate().map(|(i, &(source, target))| {
+      Link::new(T::from_usize(first + i), source, target)
+    });
+    for after in created.clone() {
+      self.hooks.check(Link::nothing(), after)?;
+    }
+
+    // keep `allocated < len` as `allocate_index` expects
+    let len = self.mem.as_slice().len();
+    if end >= len {
+      let addition = end + 1 - len;
+      self.mem.grow(addition).map_err(|_| Error::AllocationFailed)?.zeroed();
+    }
+
+    let records: Vec<_> = created
+      .clone()
+      .map(|after| Record {
+        op: Op::Create,
+        index: after.index,
+        before: Link::nothing(),
+        after,
+      })
+      .collect();
+    self.log_all(&records)?;
+    for (i, &(source, target)) in pairs.iter().enumerate() {
+      self.mem.as_mut_slice()[first + i] =
+        RawLink { source, target, ..RawLink::default() };
+    }
+    for i in first..end {
+      self.record(Undo::Create { index: T::from_usize(i), fresh: true });
+    }
+    self.allocated = end;
+
+    if pairs.len() * REBUILD_SHARE >= self.count_total() {
+      self.build_trees();
     } else {
-      first < second
+      for index in first..end {
+        self.attach_to_source_tree(T::from_usize(index));
+        self.attach_to_target_tree(T::from_usize(index));
+      }
+    }
+    self.sync_header();
+    for after in created {
+      self.hooks.notify(Link::nothing(), after);
     }
+    Ok(range)
   }
 
-  fn insert(&mut self, root: Option<usize>, idx: usize) -> Option<usize> {
-    SizeBalanced::insert_sbt(self, root, idx)
+  /// Fail with [`Error::AlreadyExists`] if a pair to load duplicates
+  /// another one or an existing link
+  fn check_unique_pairs(
+    &self,
+    pairs: &[(T, T)],
+    index: impl Fn(usize) -> T,
+  ) -> Result<(), T> {
+    let mut sorted: Vec<_> = (0..pairs.len()).collect();
+    sorted.sort_unstable_by_key(|&i| (pairs[i], i));
+    for window in sorted.windows(2) {
+      let (source, target) = pairs[window[1]];
+      let null = source.is_zero() && target.is_zero();
+      if !null && pairs[window[0]] == pairs[window[1]] {
+        return Err(Error::AlreadyExists(index(window[0]), source, target));
+      }
+    }
+    pairs.iter().try_for_each(|&(source, target)| {
+      self.check_unique(T::ANY, source, target)
+    })
   }
 
-  fn remove(&mut self, root: Option<usize>, idx: usize) -> Option<usize> {
-    SizeBalanced::remove_sbt(self, root, idx)
+  /// Write every link in links notation, one `(index: source target)` per
+  /// line in index order
+  ///
+  /// The output is buffered, and read back by [`Store::import_notation`].
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::Io`] if writing fails.
+  pub fn export_notation<W: io::Write>(&self, writer: W) -> Result<(), T> {
+    notation::write(self.query([]), writer).map_err(|err| Error::Io(err.kind()))
   }
-}
 
-// Implement SizeBalanced for all strategies (required by trait bounds)
-impl<'a, M: RawMem<Item = RawLink>, S> SizeBalanced<usize>
-  for SourceTree<'a, M, S>
-{
-}
+  /// Create links read in links notation, returns their indices in the
+  /// order they are written
+  ///
+  /// Links are written as `(index: source target)`, or as
+  /// `(source target)` with [`Numbering::Renumber`], separated by any
+  /// whitespace. Exported stores are restored exactly with
+  /// [`Numbering::Preserve`] into an empty store. The whole text is parsed
+  /// before anything is created.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::Parse`] with the position of invalid notation,
+  /// [`Error::AlreadyExists`] if a preserved index is taken or the unique
+  /// policy is broken, and [`Error::Io`] if reading fails. Preserving
+  /// indices inside a transaction fails with [`Error::InvalidQuery`], as
+  /// [`Store::rebuild_indexes`] does.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use doublets::{Doublets, Link, Links, Numbering, create_heap_store};
+  ///
+  /// let mut store = create_heap_store::<usize>().unwrap();
+  /// let text = "(1: 1 1) (2: 1 3)\n(3: 2 1)";
+  /// store.import_notation(text.as_bytes(), Numbering::Preserve).unwrap();
+  /// assert_eq!(store.get(2), Some(Link::new(2, 1, 3)));
+  ///
+  /// let mut dump = Vec::new();
+  /// store.export_notation(&mut dump).unwrap();
+  /// assert_eq!(dump, b"(1: 1 1)\n(2: 1 3)\n(3: 2 1)\n");
+  /// ```
+  pub fn import_notation<R: io::Read>(
+    &mut self,
+    mut reader: R,
+    numbering: Numbering,
+  ) -> Result<Vec<T>, T> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text).map_err(|err| Error::Io(err.kind()))?;
+    let entries = notation::parse::<T>(&text).map_err(Error::Parse)?;
+
+    match numbering {
+      Numbering::Preserve => self.import_preserved(&entries),
+      Numbering::Renumber => {
+        let first = self.allocated;
+        let mut moved = BTreeMap::new();
+        for (i, entry) in entries.iter().enumerate() {
+          let index = first
+            .checked_add(i)
+            .and_then(T::try_from_usize)
+            .ok_or(Error::Overflow)?;
+          if let Some(old) = entry.index {
+            moved.insert(old, index);
+          }
+        }
+        let moved = |part: T| moved.get(&part).copied().unwrap_or(part);
+        let pairs = entries
+          .iter()
+          .map(|entry| (moved(entry.source), moved(entry.target)));
+        let range = self.bulk_load(pairs)?;
+        Ok(
+          (range.start.as_usize()..range.end.as_usize())
+            .map(T::from_usize)
+            .collect(),
+        )
+      }
+    }
+  }
 
-// Implement AdaptiveRadix for all strategies (required by trait bounds)
-impl<'a, M: RawMem<Item = RawLink>, S> AdaptiveRadix<usize>
-  for SourceTree<'a, M, S>
-{
-}
+  /// Write parsed links into the slots of their own indices
+  fn import_preserved(&mut self, entries: &[Entry<T>]) -> Result<Vec<T>, T> {
+    let mut links = Vec::with_capacity(entries.len());
+    for entry in entries {
+      let index =
+        entry.index.ok_or(Error::Parse(entry.error(Syntax::Unnumbered)))?;
+      links.push(Link::new(index, entry.source, entry.target));
+    }
+    self.place(&links)?;
+    Ok(links.iter().map(|link| link.index).collect())
+  }
 
-/// Helper struct to implement Tree trait for target indexing with
-/// configurable strategy
-struct TargetTree<'a, M: RawMem<Item = RawLink>, S> {
-  mem: &'a mut M,
-  _strategy: core::marker::PhantomData<S>,
-}
+  /// Write links into the slots of their own indices, which must be free,
+  /// then rebuild the free list and the trees
+  fn place(&mut self, links: &[Link<T>]) -> Result<(), T> {
+    if self.undo.is_some() {
+      return Err(Error::InvalidQuery);
+    }
+    let slots = header::slots::<T>();
+    let mut defined = BTreeSet::new();
+    for link in links {
+      if link.index.as_usize() < slots {
+        return Err(Error::Reserved(link.index));
+      }
+      if link.source == T::ANY || link.target == T::ANY {
+        return Err(Error::InvalidQuery);
+      }
+      if let Some(taken) = self.get(link.index) {
+        return Err(Error::AlreadyExists(
+          link.index,
+          taken.source,
+          taken.target,
+        ));
+      }
+      if !defined.insert(link.index) {
+        return Err(Error::AlreadyExists(link.index, link.source, link.target));
+      }
+    }
+    if self.policy.unique {
+      let pairs: Vec<_> =
+        links.iter().map(|link| (link.source, link.target)).collect();
+      self.check_unique_pairs(&pairs, |i| links[i].index)?;
+    }
+    for &link in links {
+      self.hooks.check(Link::nothing(), link)?;
+    }
 
-impl<'a, M: RawMem<Item = RawLink>, S> TargetTree<'a, M, S> {
-  fn new(mem: &'a mut M) -> Self {
-    Self { mem, _strategy: core::marker::PhantomData }
+    let end = links.iter().map(|link| link.index.as_usize() + 1).max();
+    let end = end.unwrap_or(0).max(self.allocated);
+    // keep `allocated < len` as `allocate_index` expects
+    let len = self.mem.as_slice().len();
+    if end >= len {
+      let addition = end + 1 - len;
+      self.mem.grow(addition).map_err(|_| Error::AllocationFailed)?.zeroed();
+    }
+
+    // skipped slots stay free, also after a recovery
+    let skipped: Vec<_> = (self.allocated..end)
+      .map(T::from_usize)
+      .filter(|index| !defined.contains(index))
+      .collect();
+    // journal everything first, so a failure leaves the links untouched
+    let deleted = skipped.iter().map(|&index| Record {
+      op: Op::Delete,
+      index,
+      before: Link::nothing(),
+      after: Link::nothing(),
+    });
+    let created = links.iter().map(|&link| Record {
+      op: Op::Create,
+      index: link.index,
+      before: Link::nothing(),
+      after: link,
+    });
+    let records: Vec<_> = deleted.chain(created).collect();
+    self.log_all(&records)?;
+
+    let raw = self.mem.as_mut_slice();
+    for index in skipped {
+      raw[index.as_usize()] = RawLink { is_free: T::MAX, ..RawLink::default() };
+    }
+    for link in links {
+      raw[link.index.as_usize()] = RawLink {
+        source: link.source,
+        target: link.target,
+        ..RawLink::default()
+      };
+    }
+    self.allocated = end;
+    self.rebuild();
+    for &link in links {
+      self.hooks.notify(Link::nothing(), link);
+    }
+    Ok(())
   }
-}
 
-impl<'a, M: RawMem<Item = RawLink>, S> Tree<usize> for TargetTree<'a, M, S> {
-  fn get(&self, idx: usize) -> Option<Node<usize>> {
-    let slice = self.mem.as_slice();
-    slice.get(idx).map(|raw| raw.target_tree)
+  /// Write a portable binary snapshot of every link
+  ///
+  /// The snapshot holds nothing but indices, sources and targets as
+  /// varints, so it can be restored by [`Store::import_from`] into a store
+  /// with another index type, memory or tree strategies. Trailing checksum
+  /// catches corruption. The output is buffered.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::Io`] if writing fails.
+  pub fn export_to<W: io::Write>(&self, writer: W) -> Result<(), T> {
+    let io = |err: io::Error| Error::Io(err.kind());
+    let count = self.count([]).as_usize();
+    let mut dumper = Dumper::new(writer, count).map_err(io)?;
+    for link in self.query([]) {
+      dumper.link(link).map_err(io)?;
+    }
+    dumper.finish().map_err(io)
   }
 
-  fn set(&mut self, idx: usize, node: Node<usize>) {
-    let slice = self.mem.as_mut_slice();
-    if let Some(raw) = slice.get_mut(idx) {
-      raw.target_tree = node;
+  /// Restore links from a snapshot written by [`Store::export_to`],
+  /// keeping their indices, returns the indices in order
+  ///
+  /// The snapshot is read and checked completely before anything is
+  /// created. Into an empty store the links are restored exactly, down to
+  /// which slots between them are free.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::IncompatibleFormat`] if the reader doesn't hold a
+  /// snapshot, or one of another version or with a wrong checksum,
+  /// [`Error::Io`] if reading fails or the snapshot is cut short,
+  /// [`Error::Overflow`] if a value doesn't fit the index type or equals
+  /// `T::ANY`, which is never stored, [`Error::Reserved`] if an index is
+  /// reserved by this store and [`Error::AlreadyExists`] if an index is
+  /// taken or the unique policy is broken. Fails with
+  /// [`Error::InvalidQuery`] inside a transaction, as
+  /// [`Store::rebuild_indexes`] does.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use doublets::{ArtStrategy, Doublets, RawLink, Store, create_heap_store};
+  /// use mem::Alloc;
+  ///
+  /// let mut store = create_heap_store::<u64>().unwrap();
+  /// let a = store.create_point().unwrap();
+  /// let b = store.create_point().unwrap();
+  /// store.create_link(a, b).unwrap();
+  ///
+  /// let mut dump = Vec::new();
+  /// store.export_to(&mut dump).unwrap();
+  ///
+  /// let mut narrow: Store<u32, Alloc<RawLink<u32>>, ArtStrategy, ArtStrategy> =
+  ///   Store::new(Alloc::new()).unwrap();
+  /// narrow.import_from(dump.as_slice()).unwrap();
+  /// assert_eq!(narrow.search(1, 2), Some(3));
+  /// assert_eq!(narrow.count_all(), 3);
+  /// ```
+  pub fn import_from<R: io::Read>(&mut self, reader: R) -> Result<Vec<T>, T> {
+    let links = dump::read::<T, R>(reader)?;
+    self.place(&links)?;
+    Ok(links.iter().map(|link| link.index).collect())
+  }
+
+  /// Move every link into a dense prefix of the memory and release the
+  /// rest of it
+  ///
+  /// Links keep their relative order. Sources and targets are rewritten to
+  /// the new indices, references to links that no longer exist become
+  /// null. Both trees are rebuilt and the free list ends up empty, so new
+  /// links are appended after the moved ones.
+  ///
+  /// Returns the remap table: `remap[old]` is the new index of the link
+  /// that was at `old`, or null if there was none.
+  ///
+  /// Every move is journaled as one batch before the first link is
+  /// touched. A crash in the middle of moving the links is recovered by
+  /// replaying the moves, leaving the slots past the moved links free, and
+  /// a crash while writing the batch leaves the links as they were. A
+  /// checkpoint is made before and after, so the journal never holds other
+  /// records with the old indices.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::InvalidQuery`] inside a transaction, since moved
+  /// links can't be reverted, and [`Error::Io`] if the memory or the
+  /// journal can't be flushed or shrunk.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use doublets::{Doublets, Link, Links, create_heap_store};
+  ///
+  /// let mut store = create_heap_store::<usize>().unwrap();
+  /// let a = store.create_point().unwrap();
+  /// let b = store.create_point().unwrap();
+  /// let c = store.create_link(a, b).unwrap();
+  /// store.delete_link(a).unwrap();
+  ///
+  /// let remap = store.compact().unwrap();
+  /// assert_eq!(remap[c], 2);
+  /// assert_eq!(store.get(2), Some(Link::new(2, 0, 1)));
+  /// ```
+  pub fn compact(&mut self) -> Result<Vec<T>, T> {
+    if self.undo.is_some() {
+      return Err(Error::InvalidQuery);
+    }
+    self.checkpoint()?;
+
+    let slots = header::slots::<T>();
+    let mut remap = vec![T::ZERO; self.allocated];
+    let mut next = slots;
+    for (old, new) in remap.iter_mut().enumerate().skip(slots) {
+      if self.exists(T::from_usize(old)) {
+        *new = T::from_usize(next);
+        next += 1;
+      }
     }
-  }
-
-  fn left_mut(&mut self, idx: usize) -> Option<&mut usize> {
-    let slice = self.mem.as_mut_slice();
-    slice.get_mut(idx).and_then(|raw| raw.target_tree.left.as_mut())
-  }
 
-  fn right_mut(&mut self, idx: usize) -> Option<&mut usize> {
-    let slice = self.mem.as_mut_slice();
-    slice.get_mut(idx).and_then(|raw| raw.target_tree.right.as_mut())
-  }
-
-  fn is_left_of(&self, first: usize, second: usize) -> bool {
-    let slice = self.mem.as_slice();
-    if let (Some(a), Some(b)) = (slice.get(first), slice.get(second)) {
-      // Compare by (target, source) tuple for target tree
-      (a.target, a.source) < (b.target, b.source)
-    } else {
-      first < second
+    // links at their new indices, read before any of them is moved
+    let moved = |part: T| remap.get(part.as_usize()).copied();
+    let links: Vec<_> = (slots..self.allocated)
+      .filter(|&old| !remap[old].is_zero())
+      .map(|old| {
+        let raw = self.mem.as_slice()[old];
+        let source = moved(raw.source).unwrap_or(T::ZERO);
+        let target = moved(raw.target).unwrap_or(T::ZERO);
+        Link::new(remap[old], source, target)
+      })
+      .collect();
+
+    let created = links.iter().map(|&link| Record {
+      op: Op::Create,
+      index: link.index,
+      before: Link::nothing(),
+      after: link,
+    });
+    let released = (next..self.allocated).map(|index| Record {
+      op: Op::Delete,
+      index: T::from_usize(index),
+      before: Link::nothing(),
+      after: Link::nothing(),
+    });
+    let records: Vec<_> = created.chain(released).collect();
+    self.log_all(&records)?;
+
+    let raw = self.mem.as_mut_slice();
+    for link in links {
+      raw[link.index.as_usize()] = RawLink {
+        source: link.source,
+        target: link.target,
+        ..RawLink::default()
+      };
     }
+    raw[next..self.allocated].fill(RawLink::default());
+    self.allocated = next;
+    self.rebuild();
+
+    // keep `allocated < len` as `allocate_index` expects
+    let len = self.mem.as_slice().len();
+    let headroom = DEFAULT_CAPACITY.max(self.allocated + 1);
+    if len > headroom {
+      self.mem.shrink(len - headroom).map_err(Self::mem_error)?;
+    }
+    self.checkpoint()?;
+    Ok(remap)
   }
 
-  fn insert(&mut self, root: Option<usize>, idx: usize) -> Option<usize> {
-    SizeBalanced::insert_sbt(self, root, idx)
-  }
-
-  fn remove(&mut self, root: Option<usize>, idx: usize) -> Option<usize> {
-    SizeBalanced::remove_sbt(self, root, idx)
+  /// Error of the memory as seen by the store
+  fn mem_error(err: mem::Error) -> Error<T> {
+    match err {
+      mem::Error::System(err) => Error::Io(err.kind()),
+      _ => Error::AllocationFailed,
+    }
   }
-}
-
-// Implement SizeBalanced for all strategies (required by trait bounds)
-impl<'a, M: RawMem<Item = RawLink>, S> SizeBalanced<usize>
-  for TargetTree<'a, M, S>
-{
-}
-
-// Implement AdaptiveRadix for all strategies (required by trait bounds)
-impl<'a, M: RawMem<Item = RawLink>, S> AdaptiveRadix<usize>
-  for TargetTree<'a, M, S>
-{
-}
-
-/// Doublets store implementation using tree-based indexing
-///
-/// Generic over tree strategies for both source and target indexing.
-///
-/// # Type Parameters
-/// * `T` - Index type (usually usize)
-/// * `M` - Memory backend (default: heap allocation)
-/// * `SourceStrategy` - Tree strategy for source indexing
-///   (SbtStrategy or ArtStrategy)
-/// * `TargetStrategy` - Tree strategy for target indexing
-///   (SbtStrategy or ArtStrategy)
-///
-/// # Examples
-///
-/// The recommended way to create a store is using [`create_heap_store`]:
-///
-/// ```
-/// use doublets::{Doublets, create_heap_store};
-///
-/// let mut store = create_heap_store::<usize>().unwrap();
-/// let a = store.create_point().unwrap();
-/// ```
-///
-/// For custom tree strategies, use `Store::new()` directly:
-///
-/// ```
-/// use doublets::{Doublets, SbtStrategy, ArtStrategy, RawLink, Store};
-/// use mem::Alloc;
-///
-/// // Create a store with SBT for source and ART for target indexing
-/// let mut store: Store<usize, Alloc<RawLink>, SbtStrategy, ArtStrategy> =
-///   Store::new(Alloc::new()).unwrap();
-/// let a = store.create_point().unwrap();
-/// ```
-pub struct Store<
-  T,
-  M = Alloc<RawLink>,
-  SourceStrategy = SbtStrategy,
-  TargetStrategy = SbtStrategy,
-> where
-  T: Index,
-  M: RawMem<Item = RawLink> + Send + Sync,
-  SourceStrategy: TreeStrategy<usize>,
-  TargetStrategy: TreeStrategy<usize>,
-{
-  mem: M,
-  allocated: usize,
-  free_count: usize,
-  first_free: Option<usize>,
-  /// Root of tree indexing links by source
-  source_root: Option<usize>,
-  /// Root of tree indexing links by target
-  target_root: Option<usize>,
-  _phantom: core::marker::PhantomData<(T, SourceStrategy, TargetStrategy)>,
-}
 
-impl<T, M, SourceStrategy, TargetStrategy>
-  Store<T, M, SourceStrategy, TargetStrategy>
-where
-  T: Index,
-  M: RawMem<Item = RawLink> + Send + Sync,
-  SourceStrategy: TreeStrategy<usize>,
-  TargetStrategy: TreeStrategy<usize>,
-{
-  /// Create a new doublets store with default capacity
-  pub fn new(mut mem: M) -> Result<Self, T> {
-    mem.grow(1024).map_err(|_| Error::AllocationFailed)?.zeroed();
+  /// Format of the data this store lays out in memory
+  const FORMAT: Format =
+    Format::new::<T>(SourceStrategy::ID, TargetStrategy::ID);
 
+  fn with_header(mem: M, header: Header) -> Result<Self, T> {
+    // counters are stored with the index width, so they always fit
+    let index = |idx: usize| match idx {
+      0 => Ok(None),
+      idx => T::try_from_usize(idx).map(Some).ok_or(Error::Overflow),
+    };
     Ok(Self {
       mem,
-      allocated: 1,
-      free_count: 0,
-      first_free: None,
-      source_root: None,
-      target_root: None,
+      allocated: header.allocated.max(header::slots::<T>()),
+      free_count: header.free_count,
+      first_free: index(header.first_free)?,
+      source_root: index(header.source_root)?,
+      target_root: index(header.target_root)?,
+      source_storage: Default::default(),
+      target_storage: Default::default(),
+      undo: None,
+      journal: None,
+      policy: Policy::default(),
+      hooks: Hooks::default(),
       _phantom: core::marker::PhantomData,
     })
   }
 
+  /// Write the bookkeeping into the reserved slots of the memory
+  fn sync_header(&mut self) {
+    let index = |idx: Option<T>| idx.map_or(0, |idx| idx.as_usize());
+    let header = Header {
+      format: Self::FORMAT,
+      allocated: self.allocated,
+      free_count: self.free_count,
+      first_free: index(self.first_free),
+      source_root: index(self.source_root),
+      target_root: index(self.target_root),
+    };
+    let slots = header::slots::<T>();
+    header
+      .write(bytemuck::cast_slice_mut(&mut self.mem.as_mut_slice()[..slots]));
+  }
+
   /// Get a raw link from memory
   #[inline]
-  fn repr_at(&self, index: usize) -> Option<&RawLink> {
+  fn repr_at(&self, index: T) -> Option<&RawLink<T>> {
     let slice = self.mem.as_slice();
-    slice.get(index)
+    slice.get(index.as_usize())
   }
 
   /// Get a mutable raw link from memory
   #[inline]
-  fn repr_mut_at(&mut self, index: usize) -> Option<&mut RawLink> {
+  fn repr_mut_at(&mut self, index: T) -> Option<&mut RawLink<T>> {
     let slice = self.mem.as_mut_slice();
-    slice.get_mut(index)
+    slice.get_mut(index.as_usize())
   }
 
   /// Check if a link exists and is not in free list
   fn exists(&self, index: T) -> bool {
     let idx = index.as_usize();
-    if index.is_zero() || idx >= self.allocated {
+    if idx < header::slots::<T>() || idx >= self.allocated {
       return false;
     }
 
-    if let Some(raw) = self.repr_at(idx) {
-      raw.is_free != usize::MAX
+    if let Some(raw) = self.repr_at(index) {
+      raw.is_free != T::MAX
     } else {
       false
     }
   }
 
   /// Allocate a new link index
+  ///
+  /// Fails with [`Error::Overflow`] once every value of the index type is
+  /// taken, so stored indices are never truncated. `T::MAX` is never handed
+  /// out, traversals use it as a wildcard.
   fn allocate_index(&mut self) -> Result<T, T> {
     if let Some(free_index) = self.first_free {
       let next_free = if let Some(raw) = self.repr_at(free_index) {
-        if raw.source == 0 { None } else { Some(raw.source) }
+        if raw.source.is_zero() { None } else { Some(raw.source) }
       } else {
         None
       };
 
       if let Some(raw) = self.repr_mut_at(free_index) {
-        raw.is_free = 0;
+        raw.is_free = T::ZERO;
       }
 
       self.first_free = next_free;
       self.free_count -= 1;
-      return Ok(T::from_usize(free_index));
+      return Ok(free_index);
     }
 
-    let index = self.allocated;
+    let index = T::try_from_usize(self.allocated)
+      .filter(|&index| index != T::MAX)
+      .ok_or(Error::Overflow)?;
     self.allocated += 1;
 
     if self.allocated >= self.mem.as_slice().len() {
@@ -366,52 +1573,65 @@ where
     }
 
     if let Some(raw) = self.repr_mut_at(index) {
-      raw.source = 0;
-      raw.target = 0;
-      raw.is_free = 0;
+      raw.source = T::ZERO;
+      raw.target = T::ZERO;
+      raw.is_free = T::ZERO;
     }
 
-    Ok(T::from_usize(index))
+    Ok(index)
   }
 
   /// Free a link index
+  ///
+  /// Free links are chained through their sources, ending with the null
+  /// link. Index 0 holds the header, so it is never a free link itself.
   fn free_index(&mut self, index: T) {
-    let idx = index.as_usize();
-    let next_free = self.first_free.unwrap_or(0);
+    let next_free = self.first_free.unwrap_or(T::ZERO);
 
-    if let Some(raw) = self.repr_mut_at(idx) {
+    if let Some(raw) = self.repr_mut_at(index) {
       raw.source = next_free;
-      raw.target = 0;
-      raw.is_free = usize::MAX;
+      raw.target = T::ZERO;
+      raw.is_free = T::MAX;
       // Clear tree nodes
       raw.source_tree = Node::default();
       raw.target_tree = Node::default();
     }
 
-    self.first_free = Some(idx);
+    self.first_free = Some(index);
     self.free_count += 1;
   }
+  /// Give back the last index handed out by `allocate_index`, `fresh` if
+  /// it was not taken from the free list
+  fn release_index(&mut self, index: T, fresh: bool) {
+    if fresh {
+      // the slot was past every allocated one
+      if let Some(raw) = self.repr_mut_at(index) {
+        *raw = RawLink::default();
+      }
+      self.allocated -= 1;
+    } else {
+      self.free_index(index);
+    }
+  }
 
   /// Attach a link to the source tree
-  fn attach_to_source_tree(&mut self, index: usize)
-  where
-    for<'a> SourceTree<'a, M, SourceStrategy>:
-      SizeBalanced<usize> + AdaptiveRadix<usize>,
-  {
-    let mut tree = SourceTree::<M, SourceStrategy>::new(&mut self.mem);
-    self.source_root =
-      SourceStrategy::insert(&mut tree, self.source_root, index);
+  fn attach_to_source_tree(&mut self, index: T) {
+    self.source_root = SourceStrategy::insert::<SourceSide>(
+      self.mem.as_mut_slice(),
+      &mut self.source_storage,
+      self.source_root,
+      index,
+    );
   }
 
   /// Detach a link from the source tree
-  fn detach_from_source_tree(&mut self, index: usize)
-  where
-    for<'a> SourceTree<'a, M, SourceStrategy>:
-      SizeBalanced<usize> + AdaptiveRadix<usize>,
-  {
-    let mut tree = SourceTree::<M, SourceStrategy>::new(&mut self.mem);
-    self.source_root =
-      SourceStrategy::remove(&mut tree, self.source_root, index);
+  fn detach_from_source_tree(&mut self, index: T) {
+    self.source_root = SourceStrategy::remove::<SourceSide>(
+      self.mem.as_mut_slice(),
+      &mut self.source_storage,
+      self.source_root,
+      index,
+    );
 
     // Clear the node's tree pointers after removal
     if let Some(raw) = self.repr_mut_at(index) {
@@ -420,25 +1640,23 @@ where
   }
 
   /// Attach a link to the target tree
-  fn attach_to_target_tree(&mut self, index: usize)
-  where
-    for<'a> TargetTree<'a, M, TargetStrategy>:
-      SizeBalanced<usize> + AdaptiveRadix<usize>,
-  {
-    let mut tree = TargetTree::<M, TargetStrategy>::new(&mut self.mem);
-    self.target_root =
-      TargetStrategy::insert(&mut tree, self.target_root, index);
+  fn attach_to_target_tree(&mut self, index: T) {
+    self.target_root = TargetStrategy::insert::<TargetSide>(
+      self.mem.as_mut_slice(),
+      &mut self.target_storage,
+      self.target_root,
+      index,
+    );
   }
 
   /// Detach a link from the target tree
-  fn detach_from_target_tree(&mut self, index: usize)
-  where
-    for<'a> TargetTree<'a, M, TargetStrategy>:
-      SizeBalanced<usize> + AdaptiveRadix<usize>,
-  {
-    let mut tree = TargetTree::<M, TargetStrategy>::new(&mut self.mem);
-    self.target_root =
-      TargetStrategy::remove(&mut tree, self.target_root, index);
+  fn detach_from_target_tree(&mut self, index: T) {
+    self.target_root = TargetStrategy::remove::<TargetSide>(
+      self.mem.as_mut_slice(),
+      &mut self.target_storage,
+      self.target_root,
+      index,
+    );
 
     // Clear the node's tree pointers after removal
     if let Some(raw) = self.repr_mut_at(index) {
@@ -446,289 +1664,122 @@ where
     }
   }
 
-  /// Search for a link with exact source and target in source tree
-  fn search_in_source_tree(
-    &self,
-    source: usize,
-    target: usize,
-  ) -> Option<usize> {
-    let mut current = self.source_root?;
-    let slice = self.mem.as_slice();
-
-    loop {
-      let raw = slice.get(current)?;
-
-      match (source, target).cmp(&(raw.source, raw.target)) {
-        core::cmp::Ordering::Equal => return Some(current),
-        core::cmp::Ordering::Less => {
-          current = raw.source_tree.left?;
-        }
-        core::cmp::Ordering::Greater => {
-          current = raw.source_tree.right?;
-        }
+  /// Rebuild the indexes that are not persisted with the links
+  fn rebuild_side_indexes(&mut self) {
+    for i in header::slots::<T>()..self.allocated {
+      let index = T::from_usize(i);
+      if !self.exists(index) {
+        continue;
+      }
+      if !SourceStrategy::IN_PLACE {
+        self.attach_to_source_tree(index);
+      }
+      if !TargetStrategy::IN_PLACE {
+        self.attach_to_target_tree(index);
       }
     }
   }
 
-  /// Traverse source tree for all links with matching source.
-  ///
-  /// Provides O(log n + k) performance where k is the number of matches.
-  fn each_by_source<H: ReadHandler<T>>(
-    &self,
-    source: usize,
-    handler: &mut H,
-  ) -> Flow {
-    self.traverse_source_tree(self.source_root, source, usize::MAX, handler)
-  }
-
-  /// Traverse target tree for all links with matching target.
-  ///
-  /// Provides O(log n + k) performance where k is the number of matches.
-  fn each_by_target<H: ReadHandler<T>>(
-    &self,
-    target: usize,
-    handler: &mut H,
-  ) -> Flow {
-    self.traverse_target_tree(self.target_root, target, usize::MAX, handler)
+  /// Count all non-free links
+  fn count_total(&self) -> usize {
+    self.allocated - self.free_count - header::slots::<T>()
   }
 
-  /// Recursively traverse source tree for links with matching source
-  ///
-  /// Internal helper for tree-based source queries.
-  fn traverse_source_tree<H: ReadHandler<T>>(
+  /// Cursor over links matching a query, see [`Links::query`]
+  fn cursor<const N: usize>(
     &self,
-    current: Option<usize>,
-    source: usize,
-    target: usize,
-    handler: &mut H,
-  ) -> Flow {
-    let idx = match current {
-      Some(i) => i,
-      None => return Flow::Continue,
-    };
+    query: [T; N],
+  ) -> Cursor<'_, T, SourceStrategy, TargetStrategy> {
+    let links = self.mem.as_slice();
+    let index = if N >= 1 { query[0] } else { T::ANY };
+    let source = if N >= 2 { query[1] } else { T::ANY };
+    let target = if N >= 3 { query[2] } else { T::ANY };
 
-    let slice = self.mem.as_slice();
-    let raw = match slice.get(idx) {
-      Some(r) => r,
-      None => return Flow::Continue,
-    };
+    if index != T::ANY {
+      let link = self.get(index).filter(|link| {
+        (source == T::ANY || source == link.source)
+          && (target == T::ANY || target == link.target)
+      });
+      return Cursor::One(link);
+    }
 
-    // When searching by source with wildcard target
-    if target == usize::MAX {
-      // Do an in-order traversal, visiting only nodes where source matches
-      // Since tree is ordered by (source, target), matching nodes are
-      // contiguous in in-order traversal
-
-      // If current node's source < search source, go right
-      if raw.source < source {
-        return self.traverse_source_tree(
-          raw.source_tree.right,
-          source,
-          target,
-          handler,
-        );
-      }
-
-      // If current node's source > search source, go left
-      if raw.source > source {
-        return self.traverse_source_tree(
-          raw.source_tree.left,
-          source,
-          target,
-          handler,
-        );
-      }
-
-      // Current node's source == search source, traverse both subtrees
-      if self.traverse_source_tree(
-        raw.source_tree.left,
+    match (source != T::ANY, target != T::ANY) {
+      (true, true) => Cursor::Source(SourceStrategy::cursor::<SourceSide>(
+        links,
+        &self.source_storage,
+        self.source_root,
         source,
-        target,
-        handler,
-      ) == Flow::Break
-      {
-        return Flow::Break;
-      }
-
-      // Check current node
-      let link = Link::new(
-        T::from_usize(idx),
-        T::from_usize(raw.source),
-        T::from_usize(raw.target),
-      );
-      if handler.handle(link) == Flow::Break {
-        return Flow::Break;
-      }
-
-      // Continue to right subtree
-      return self.traverse_source_tree(
-        raw.source_tree.right,
+        Some(target),
+      )),
+      (true, false) => Cursor::Source(SourceStrategy::cursor::<SourceSide>(
+        links,
+        &self.source_storage,
+        self.source_root,
         source,
+        None,
+      )),
+      (false, true) => Cursor::Target(TargetStrategy::cursor::<TargetSide>(
+        links,
+        &self.target_storage,
+        self.target_root,
         target,
-        handler,
-      );
-    } else {
-      // Exact (source, target) search - can prune efficiently
-      // Traverse left subtree if it might contain matches
-      if (source, target) < (raw.source, raw.target)
-        && self.traverse_source_tree(
-          raw.source_tree.left,
-          source,
-          target,
-          handler,
-        ) == Flow::Break
-      {
-        return Flow::Break;
-      }
-
-      // Check current node
-      if raw.source == source && raw.target == target {
-        let link = Link::new(
-          T::from_usize(idx),
-          T::from_usize(raw.source),
-          T::from_usize(raw.target),
-        );
-        if handler.handle(link) == Flow::Break {
-          return Flow::Break;
-        }
-      }
-
-      // Traverse right subtree if it might contain matches
-      if (source, target) > (raw.source, raw.target)
-        && self.traverse_source_tree(
-          raw.source_tree.right,
-          source,
-          target,
-          handler,
-        ) == Flow::Break
-      {
-        return Flow::Break;
-      }
+        None,
+      )),
+      (false, false) => Cursor::All(header::slots::<T>()..self.allocated),
     }
-
-    Flow::Continue
   }
+}
 
-  /// Recursively traverse target tree for links with matching target
-  ///
-  /// Internal helper for tree-based target queries.
-  fn traverse_target_tree<H: ReadHandler<T>>(
-    &self,
-    current: Option<usize>,
-    target: usize,
-    source: usize,
-    handler: &mut H,
-  ) -> Flow {
-    let idx = match current {
-      Some(i) => i,
-      None => return Flow::Continue,
-    };
-
-    let slice = self.mem.as_slice();
-    let raw = match slice.get(idx) {
-      Some(r) => r,
-      None => return Flow::Continue,
-    };
-
-    // When searching by target with wildcard source
-    if source == usize::MAX {
-      // Do an in-order traversal, visiting only nodes where target matches
-      // Since tree is ordered by (target, source), matching nodes are
-      // contiguous in in-order traversal
-
-      // If current node's target < search target, go right
-      if raw.target < target {
-        return self.traverse_target_tree(
-          raw.target_tree.right,
-          target,
-          source,
-          handler,
-        );
-      }
-
-      // If current node's target > search target, go left
-      if raw.target > target {
-        return self.traverse_target_tree(
-          raw.target_tree.left,
-          target,
-          source,
-          handler,
-        );
-      }
-
-      // Current node's target == search target, traverse both subtrees
-      if self.traverse_target_tree(
-        raw.target_tree.left,
-        target,
-        source,
-        handler,
-      ) == Flow::Break
-      {
-        return Flow::Break;
-      }
-
-      // Check current node
-      let link = Link::new(
-        T::from_usize(idx),
-        T::from_usize(raw.source),
-        T::from_usize(raw.target),
-      );
-      if handler.handle(link) == Flow::Break {
-        return Flow::Break;
-      }
-
-      // Continue to right subtree
-      return self.traverse_target_tree(
-        raw.target_tree.right,
-        target,
-        source,
-        handler,
-      );
-    } else {
-      // Exact (target, source) search - can prune efficiently
-      // Traverse left subtree if it might contain matches
-      if (target, source) < (raw.target, raw.source)
-        && self.traverse_target_tree(
-          raw.target_tree.left,
-          target,
-          source,
-          handler,
-        ) == Flow::Break
-      {
-        return Flow::Break;
-      }
-
-      // Check current node
-      if raw.target == target && raw.source == source {
-        let link = Link::new(
-          T::from_usize(idx),
-          T::from_usize(raw.source),
-          T::from_usize(raw.target),
-        );
-        if handler.handle(link) == Flow::Break {
-          return Flow::Break;
-        }
-      }
-
-      // Traverse right subtree if it might contain matches
-      if (target, source) > (raw.target, raw.source)
-        && self.traverse_target_tree(
-          raw.target_tree.right,
-          target,
-          source,
-          handler,
-        ) == Flow::Break
-      {
-        return Flow::Break;
-      }
-    }
+/// Position of a [`Query`] inside the store
+enum Cursor<'a, T, S, R>
+where
+  T: Index + 'a,
+  S: TreeStrategy<T> + 'a,
+  R: TreeStrategy<T> + 'a,
+{
+  /// At most one link found by its index
+  One(Option<Link<T>>),
+  /// Scan over every slot
+  All(Range<usize>),
+  Source(S::Cursor<'a, SourceSide>),
+  Target(R::Cursor<'a, TargetSide>),
+}
 
-    Flow::Continue
-  }
+/// Lazy iterator over links matching a query
+///
+/// Created by [`Links::query`] and [`Doublets::iter`](crate::Doublets::iter).
+/// Queries by source or target walk the corresponding tree with an explicit
+/// stack, so no links are collected up front.
+pub struct Query<'a, T, S, R>
+where
+  T: Index + 'a,
+  S: TreeStrategy<T> + 'a,
+  R: TreeStrategy<T> + 'a,
+{
+  links: &'a [RawLink<T>],
+  cursor: Cursor<'a, T, S, R>,
+}
 
-  /// Count all non-free links
-  fn count_total(&self) -> usize {
-    self.allocated - self.free_count - 1
+impl<'a, T, S, R> Iterator for Query<'a, T, S, R>
+where
+  T: Index + 'a,
+  S: TreeStrategy<T> + 'a,
+  R: TreeStrategy<T> + 'a,
+{
+  type Item = Link<T>;
+
+  fn next(&mut self) -> Option<Link<T>> {
+    let index = match &mut self.cursor {
+      Cursor::One(link) => return link.take(),
+      Cursor::All(range) => range
+        .by_ref()
+        .map(T::from_usize)
+        .find(|index| self.links[index.as_usize()].is_free != T::MAX)?,
+      Cursor::Source(cursor) => cursor.next()?,
+      Cursor::Target(cursor) => cursor.next()?,
+    };
+    let raw = &self.links[index.as_usize()];
+    Some(Link::new(index, raw.source, raw.target))
   }
 }
 
@@ -736,9 +1787,9 @@ impl<T, M, SourceStrategy, TargetStrategy> Links<T>
   for Store<T, M, SourceStrategy, TargetStrategy>
 where
   T: Index,
-  M: RawMem<Item = RawLink> + Send + Sync,
-  SourceStrategy: TreeStrategy<usize>,
-  TargetStrategy: TreeStrategy<usize>,
+  M: RawMem<Item = RawLink<T>> + Send + Sync,
+  SourceStrategy: TreeStrategy<T>,
+  TargetStrategy: TreeStrategy<T>,
 {
   fn count<const N: usize>(&self, query: [T; N]) -> T {
     match N {
@@ -754,45 +1805,100 @@ where
         }
       }
       _ => {
-        let mut count = 0;
-        self.each(query, &mut |_| {
-          count += 1;
-          Flow::Continue
-        });
+        let index = query[0];
+        let source = query[1];
+        let target = if N >= 3 { query[2] } else { T::ANY };
+        let links = self.mem.as_slice();
+
+        // Rank-based counting in the trees, O(log n) regardless of matches
+        let count = if index != T::ANY {
+          self.query(query).count()
+        } else if source != T::ANY {
+          let target = (target != T::ANY).then_some(target);
+          SourceStrategy::count::<SourceSide>(
+            links,
+            &self.source_storage,
+            self.source_root,
+            source,
+            target,
+          )
+        } else if target != T::ANY {
+          TargetStrategy::count::<TargetSide>(
+            links,
+            &self.target_storage,
+            self.target_root,
+            target,
+            None,
+          )
+        } else {
+          self.count_total()
+        };
         T::from_usize(count)
       }
     }
   }
 
+  type Query<'a>
+    = Query<'a, T, SourceStrategy, TargetStrategy>
+  where
+    Self: 'a;
+
+  fn query<const N: usize>(
+    &self,
+    query: [T; N],
+  ) -> Query<'_, T, SourceStrategy, TargetStrategy> {
+    Query { links: self.mem.as_slice(), cursor: self.cursor(query) }
+  }
+
   fn create<const N: usize, H: WriteHandler<T>>(
     &mut self,
     query: [T; N],
     handler: &mut H,
   ) -> Result<Flow, T> {
+    let pair = match N {
+      0 => None,
+      1 => Some((query[0], query[0])),
+      _ => Some((query[0], query[1])),
+    };
+    if let Some((source, target)) = pair {
+      // the wildcard is never stored, so queries can't confuse it with a link
+      if source == T::ANY || target == T::ANY {
+        return Err(Error::InvalidQuery);
+      }
+      self.check_unique(T::ANY, source, target)?;
+    }
+
+    let fresh = self.first_free.is_none();
     let index = self.allocate_index()?;
+    // a point references itself, so it is unique by construction
+    let (source, target) = pair.unwrap_or((index, index));
     let before = Link::nothing();
 
-    let (source, target) = match N {
-      0 => (T::ZERO, T::ZERO),
-      1 => (query[0], query[0]),
-      _ => (query[0], query[1]),
-    };
-
-    let idx = index.as_usize();
+    let after = Link::new(index, source, target);
+    let allowed = self.hooks.check(before, after);
+    if let Err(err) =
+      allowed.and_then(|()| self.log(Op::Create, index, before, after))
+    {
+      self.release_index(index, fresh);
+      return Err(err);
+    }
+    self.record(Undo::Create { index, fresh });
 
-    if let Some(raw) = self.repr_mut_at(idx) {
-      raw.source = source.as_usize();
-      raw.target = target.as_usize();
-      raw.is_free = 0;
+    if let Some(raw) = self.repr_mut_at(index) {
+      raw.source = source;
+      raw.target = target;
+      raw.is_free = T::ZERO;
       raw.source_tree = Node::default();
       raw.target_tree = Node::default();
     }
 
     // Attach to both trees for efficient searching
-    self.attach_to_source_tree(idx);
-    self.attach_to_target_tree(idx);
+    self.attach_to_source_tree(index);
+    self.attach_to_target_tree(index);
+
+    self.sync_header();
+    self.hooks.notify(before, after);
 
-    let after = Link::new(index, source, target);
     Ok(handler.handle(before, after))
   }
 
@@ -803,13 +1909,13 @@ where
   ) -> Flow {
     if N == 0 {
       // Enumerate all links
-      for i in 1..self.allocated {
+      for i in header::slots::<T>()..self.allocated {
         let index = T::from_usize(i);
         if self.exists(index)
-          && let Some(raw) = self.repr_at(i)
+          && let Some(raw) = self.repr_at(index)
         {
-          let source = T::from_usize(raw.source);
-          let target = T::from_usize(raw.target);
+          let source = raw.source;
+          let target = raw.target;
           let link = Link::new(index, source, target);
           if handler.handle(link) == Flow::Break {
             return Flow::Break;
@@ -825,10 +1931,10 @@ where
       if index_query == T::ANY {
         return self.each([], handler);
       } else if self.exists(index_query)
-        && let Some(raw) = self.repr_at(index_query.as_usize())
+        && let Some(raw) = self.repr_at(index_query)
       {
-        let source = T::from_usize(raw.source);
-        let target = T::from_usize(raw.target);
+        let source = raw.source;
+        let target = raw.target;
         return handler.handle(Link::new(index_query, source, target));
       }
       return Flow::Continue;
@@ -840,27 +1946,15 @@ where
     // Use tree-based search when possible for better performance
     if index_query == T::ANY {
       // Query by source and/or target
-      if source != T::ANY && target != T::ANY {
-        // Exact (source, target) search - use tree
-        if let Some(idx) =
-          self.search_in_source_tree(source.as_usize(), target.as_usize())
-          && self.exists(T::from_usize(idx))
-        {
-          let raw = self.repr_at(idx).unwrap();
-          let link = Link::new(
-            T::from_usize(idx),
-            T::from_usize(raw.source),
-            T::from_usize(raw.target),
-          );
-          return handler.handle(link);
+      if source != T::ANY || target != T::ANY {
+        // Query by source and/or target - iterative tree traversal
+        // O(log n + k) with a stack bounded by the tree height
+        for link in self.query(query) {
+          if handler.handle(link) == Flow::Break {
+            return Flow::Break;
+          }
         }
         return Flow::Continue;
-      } else if source != T::ANY {
-        // Query by source only - use tree traversal O(log n + k)
-        return self.each_by_source(source.as_usize(), handler);
-      } else if target != T::ANY {
-        // Query by target only - use tree traversal O(log n + k)
-        return self.each_by_target(target.as_usize(), handler);
       } else {
         // No constraints - enumerate all
         return self.each([], handler);
@@ -872,13 +1966,13 @@ where
       return Flow::Continue;
     }
 
-    let raw = match self.repr_at(index_query.as_usize()) {
+    let raw = match self.repr_at(index_query) {
       Some(r) => r,
       None => return Flow::Continue,
     };
 
-    let raw_source = T::from_usize(raw.source);
-    let raw_target = T::from_usize(raw.target);
+    let raw_source = raw.source;
+    let raw_target = raw.target;
 
     let matches = (source == T::ANY || source == raw_source)
       && (target == T::ANY || target == raw_target);
@@ -902,36 +1996,25 @@ where
     }
 
     let index = query[0];
-    if !self.exists(index) {
-      return Err(Error::NotExists(index));
-    }
-
-    let before = self.get(index).ok_or(Error::NotExists(index))?;
-
-    let new_source = if N2 >= NC_SOURCE { change[1] } else { before.source };
-    let new_target = if N2 >= NC_TARGET { change[2] } else { before.target };
-
-    let idx = index.as_usize();
-
-    // If source or target changed, update tree positions
-    if new_source != before.source || new_target != before.target {
-      // Detach from old positions in both trees
-      self.detach_from_source_tree(idx);
-      self.detach_from_target_tree(idx);
-
-      // Update the link data
-      if let Some(raw) = self.repr_mut_at(idx) {
-        raw.source = new_source.as_usize();
-        raw.target = new_target.as_usize();
+    if index != T::ANY {
+      if !self.exists(index) {
+        return Err(Error::NotExists(index));
       }
-
-      // Reattach to new positions in both trees
-      self.attach_to_source_tree(idx);
-      self.attach_to_target_tree(idx);
+      if self.query(query).next().is_none() {
+        return Ok(Flow::Continue);
+      }
+      return self.update_one(index, change, handler);
     }
 
-    let after = Link::new(index, new_source, new_target);
-    Ok(handler.handle(before, after))
+    let matches = self.matching(query);
+    self.transaction(|tx| {
+      for index in matches {
+        if tx.update_one(index, change, handler)? == Flow::Break {
+          return Ok(Flow::Break);
+        }
+      }
+      Ok(Flow::Continue)
+    })
   }
 
   fn delete<const N: usize, H: WriteHandler<T>>(
@@ -944,21 +2027,28 @@ where
     }
 
     let index = query[0];
-    if !self.exists(index) {
-      return Err(Error::NotExists(index));
+    if index != T::ANY {
+      if !self.exists(index) {
+        return Err(Error::NotExists(index));
+      }
+      if self.query(query).next().is_none() {
+        return Ok(Flow::Continue);
+      }
+      return self.delete_by_policy(index, handler);
     }
 
-    let before = self.get(index).ok_or(Error::NotExists(index))?;
-
-    // Detach from both trees before freeing
-    let idx = index.as_usize();
-    self.detach_from_source_tree(idx);
-    self.detach_from_target_tree(idx);
-
-    self.free_index(index);
-
-    let after = Link::nothing();
-    Ok(handler.handle(before, after))
+    let matches = self.matching(query);
+    self.transaction(|tx| {
+      for index in matches {
+        // could be deleted by the policy along with another match
+        if tx.exists(index)
+          && tx.delete_by_policy(index, handler)? == Flow::Break
+        {
+          return Ok(Flow::Break);
+        }
+      }
+      Ok(Flow::Continue)
+    })
   }
 
   fn get(&self, index: T) -> Option<Link<T>> {
@@ -966,13 +2056,28 @@ where
       return None;
     }
 
-    let raw = self.repr_at(index.as_usize())?;
-    let source = T::from_usize(raw.source);
-    let target = T::from_usize(raw.target);
+    let raw = self.repr_at(index)?;
+    let source = raw.source;
+    let target = raw.target;
     Some(Link::new(index, source, target))
   }
 }
 
+impl<T, M, SourceStrategy, TargetStrategy> Drop
+  for Store<T, M, SourceStrategy, TargetStrategy>
+where
+  T: Index,
+  M: RawMem<Item = RawLink<T>> + Send + Sync,
+  SourceStrategy: TreeStrategy<T>,
+  TargetStrategy: TreeStrategy<T>,
+{
+  fn drop(&mut self) {
+    if self.journal.is_some() {
+      let _ = self.checkpoint();
+    }
+  }
+}
+
 /// Create a doublets store with heap allocation using SBT
 /// (Size-Balanced Tree) for both source and target trees.
 ///
@@ -987,8 +2092,7 @@ where
 /// let b = store.create_point().unwrap();
 /// let c = store.create_link(a, b).unwrap();
 /// ```
-pub fn create_heap_store<T>()
--> Result<Store<T, Alloc<RawLink>, SbtStrategy, SbtStrategy>, T>
+pub fn create_heap_store<T>() -> Result<Store<T>, T>
 where
   T: Index,
 {
@@ -0,0 +1,687 @@
+use crate::{Index, RawLink, TreeFault};
+
+use {
+  core::{cmp::Ordering, marker::PhantomData},
+  trees::{AdaptiveRadix, Node, RadixIter, SizeBalanced, Tree},
+};
+
+/// One of the two orders a [`Store`](crate::Store) indexes links by
+///
+/// Strategies use it to reach the ordering key of a link and the tree node
+/// reserved for the index inside the link.
+pub trait TreeSide<T: Index> {
+  /// Key the links are ordered by, `(source, target)` or `(target, source)`
+  fn key(link: &RawLink<T>) -> (T, T);
+
+  fn node(link: &RawLink<T>) -> &Node<T>;
+
+  fn node_mut(link: &mut RawLink<T>) -> &mut Node<T>;
+}
+
+/// Links ordered by `(source, target)`
+pub(crate) struct SourceSide;
+
+/// Links ordered by `(target, source)`
+pub(crate) struct TargetSide;
+
+impl<T: Index> TreeSide<T> for SourceSide {
+  #[inline]
+  fn key(link: &RawLink<T>) -> (T, T) {
+    (link.source, link.target)
+  }
+
+  #[inline]
+  fn node(link: &RawLink<T>) -> &Node<T> {
+    &link.source_tree
+  }
+
+  #[inline]
+  fn node_mut(link: &mut RawLink<T>) -> &mut Node<T> {
+    &mut link.source_tree
+  }
+}
+
+impl<T: Index> TreeSide<T> for TargetSide {
+  #[inline]
+  fn key(link: &RawLink<T>) -> (T, T) {
+    (link.target, link.source)
+  }
+
+  #[inline]
+  fn node(link: &RawLink<T>) -> &Node<T> {
+    &link.target_tree
+  }
+
+  #[inline]
+  fn node_mut(link: &mut RawLink<T>) -> &mut Node<T> {
+    &mut link.target_tree
+  }
+}
+
+/// Index of links by one of the [`TreeSide`] orders.
+///
+/// An index either lives in the tree nodes reserved inside every link and
+/// is persisted with them, or keeps its own storage next to the links.
+/// Such storage is rebuilt from the links when a store is opened.
+pub trait TreeStrategy<T: Index>: Send + Sync {
+  /// Identifier of the strategy recorded in the store header
+  ///
+  /// Trees built by different strategies are not interchangeable, so
+  /// every strategy must use its own id.
+  const ID: u8;
+
+  /// Whether the whole index lives inside the links
+  const IN_PLACE: bool;
+
+  /// Storage kept next to the links, `()` for in-place indexes
+  type Storage: Default + Send + Sync;
+
+  /// Insert a link into the index, returns the new root
+  fn insert<S: TreeSide<T>>(
+    links: &mut [RawLink<T>],
+    storage: &mut Self::Storage,
+    root: Option<T>,
+    idx: T,
+  ) -> Option<T>;
+
+  /// Remove a link from the index, returns the new root
+  fn remove<S: TreeSide<T>>(
+    links: &mut [RawLink<T>],
+    storage: &mut Self::Storage,
+    root: Option<T>,
+    idx: T,
+  ) -> Option<T>;
+
+  /// Lazy cursor over links whose key starts with `first` and `second`
+  type Cursor<'a, S: TreeSide<T>>: Iterator<Item = T>
+  where
+    T: 'a,
+    Self: 'a;
+
+  /// Open a cursor over links whose key starts with `first`, followed by
+  /// `second` unless it is `None`, in key order
+  ///
+  /// Positioning takes O(log n), every following step amortized O(1).
+  fn cursor<'a, S: TreeSide<T>>(
+    links: &'a [RawLink<T>],
+    storage: &'a Self::Storage,
+    root: Option<T>,
+    first: T,
+    second: Option<T>,
+  ) -> Self::Cursor<'a, S>;
+
+  /// Count links whose key starts with `first`, followed by `second`
+  /// unless it is `None`
+  ///
+  /// Takes O(log n) time instead of visiting every match.
+  fn count<S: TreeSide<T>>(
+    links: &[RawLink<T>],
+    storage: &Self::Storage,
+    root: Option<T>,
+    first: T,
+    second: Option<T>,
+  ) -> usize;
+
+  /// Build the index from scratch out of `sorted` links, returns the root
+  ///
+  /// Links are ordered by their key on the side and then by index, the
+  /// storage is empty and the tree nodes of the links are reset. Inserts
+  /// the links one by one unless the strategy can do better.
+  fn build<S: TreeSide<T>>(
+    links: &mut [RawLink<T>],
+    storage: &mut Self::Storage,
+    sorted: &[T],
+  ) -> Option<T> {
+    sorted
+      .iter()
+      .fold(None, |root, &idx| Self::insert::<S>(links, storage, root, idx))
+  }
+
+  /// Check the index against the links
+  ///
+  /// `live` tells whether a slot holds a link, every such link must be
+  /// reached exactly once and nothing else may be reached.
+  fn verify<S: TreeSide<T>>(
+    links: &[RawLink<T>],
+    storage: &Self::Storage,
+    root: Option<T>,
+    live: &dyn Fn(T) -> bool,
+  ) -> Vec<TreeFault<T>>;
+
+  /// Depth of every link in the index, the number of nodes above it, in
+  /// no particular order
+  fn depths<S: TreeSide<T>>(
+    links: &[RawLink<T>],
+    storage: &Self::Storage,
+    root: Option<T>,
+  ) -> Vec<usize>;
+}
+
+/// Compare the key of a link with a key prefix
+fn cmp_prefix<T: Index>(
+  (first, second): (T, T),
+  prefix: (T, Option<T>),
+) -> Ordering {
+  match prefix {
+    (prefix, None) => first.cmp(&prefix),
+    (prefix, Some(next)) => (first, second).cmp(&(prefix, next)),
+  }
+}
+
+/// Report live links that were never reached
+fn missing<T: Index>(
+  seen: &[bool],
+  live: &dyn Fn(T) -> bool,
+  faults: &mut Vec<TreeFault<T>>,
+) {
+  let slots = seen
+    .iter()
+    .enumerate()
+    .map_while(|(i, &seen)| T::try_from_usize(i).map(|idx| (idx, seen)));
+  for (idx, seen) in slots {
+    if !seen && live(idx) {
+      faults.push(TreeFault::Missing(idx));
+    }
+  }
+}
+
+/// Tree over the nodes of one side of the links
+struct LinkTree<'a, T, S> {
+  links: &'a mut [RawLink<T>],
+  _side: PhantomData<S>,
+}
+
+impl<'a, T: Index, S: TreeSide<T>> LinkTree<'a, T, S> {
+  fn new(links: &'a mut [RawLink<T>]) -> Self {
+    Self { links, _side: PhantomData }
+  }
+
+  fn is_left_of_in(links: &[RawLink<T>], first: T, second: T) -> bool {
+    let (a, b) = (links.get(first.as_usize()), links.get(second.as_usize()));
+    // equal keys are ordered by index, so duplicates can be found again
+    match (a, b) {
+      (Some(a), Some(b)) => (S::key(a), first) < (S::key(b), second),
+      _ => false,
+    }
+  }
+}
+
+impl<T: Index, S: TreeSide<T>> Tree<T> for LinkTree<'_, T, S> {
+  fn get(&self, idx: T) -> Option<Node<T>> {
+    self.links.get(idx.as_usize()).map(|link| *S::node(link))
+  }
+
+  fn set(&mut self, idx: T, node: Node<T>) {
+    if let Some(link) = self.links.get_mut(idx.as_usize()) {
+      *S::node_mut(link) = node;
+    }
+  }
+
+  fn left_mut(&mut self, idx: T) -> Option<&mut T> {
+    let link = self.links.get_mut(idx.as_usize())?;
+    let left = &mut S::node_mut(link).left;
+    (!left.is_null()).then_some(left)
+  }
+
+  fn right_mut(&mut self, idx: T) -> Option<&mut T> {
+    let link = self.links.get_mut(idx.as_usize())?;
+    let right = &mut S::node_mut(link).right;
+    (!right.is_null()).then_some(right)
+  }
+
+  fn is_left_of(&self, first: T, second: T) -> bool {
+    Self::is_left_of_in(self.links, first, second)
+  }
+
+  fn insert(&mut self, root: Option<T>, idx: T) -> Option<T> {
+    SizeBalanced::insert_sbt(self, root, idx)
+  }
+
+  fn remove(&mut self, root: Option<T>, idx: T) -> Option<T> {
+    SizeBalanced::remove_sbt(self, root, idx)
+  }
+}
+
+impl<T: Index, S: TreeSide<T>> SizeBalanced<T> for LinkTree<'_, T, S> {}
+
+/// Read-only tree over the nodes of one side of the links
+///
+/// Serves the order statistics of [`SizeBalanced`] to queries, which only
+/// borrow the links. Changing it is a bug.
+struct LinkView<'a, T, S> {
+  links: &'a [RawLink<T>],
+  _side: PhantomData<S>,
+}
+
+impl<'a, T: Index, S: TreeSide<T>> LinkView<'a, T, S> {
+  fn new(links: &'a [RawLink<T>]) -> Self {
+    Self { links, _side: PhantomData }
+  }
+}
+
+impl<T: Index, S: TreeSide<T>> Tree<T> for LinkView<'_, T, S> {
+  fn get(&self, idx: T) -> Option<Node<T>> {
+    self.links.get(idx.as_usize()).map(|link| *S::node(link))
+  }
+
+  fn set(&mut self, _: T, _: Node<T>) {
+    unreachable!("link views are read-only")
+  }
+
+  fn left_mut(&mut self, _: T) -> Option<&mut T> {
+    unreachable!("link views are read-only")
+  }
+
+  fn right_mut(&mut self, _: T) -> Option<&mut T> {
+    unreachable!("link views are read-only")
+  }
+
+  fn is_left_of(&self, first: T, second: T) -> bool {
+    LinkTree::<T, S>::is_left_of_in(self.links, first, second)
+  }
+
+  fn insert(&mut self, _: Option<T>, _: T) -> Option<T> {
+    unreachable!("link views are read-only")
+  }
+
+  fn remove(&mut self, _: Option<T>, _: T) -> Option<T> {
+    unreachable!("link views are read-only")
+  }
+}
+
+impl<T: Index, S: TreeSide<T>> SizeBalanced<T> for LinkView<'_, T, S> {}
+
+/// Size-Balanced Tree strategy marker
+///
+/// Keeps the tree inside the links, so it is persisted with them.
+pub struct SbtStrategy;
+
+/// In-order cursor over a size-balanced tree inside the links
+///
+/// Keeps the path to the next matching node on an explicit stack instead of
+/// recursing, so its memory is bounded by the tree height.
+pub struct SbtCursor<'a, T, S> {
+  links: &'a [RawLink<T>],
+  stack: Vec<T>,
+  prefix: (T, Option<T>),
+  _side: PhantomData<S>,
+}
+
+impl<'a, T: Index, S: TreeSide<T>> SbtCursor<'a, T, S> {
+  fn new(
+    links: &'a [RawLink<T>],
+    root: Option<T>,
+    prefix: (T, Option<T>),
+  ) -> Self {
+    let mut cursor =
+      Self { links, stack: Vec::new(), prefix, _side: PhantomData };
+    cursor.descend(root);
+    cursor
+  }
+
+  /// Push the path to the leftmost node of `current` not before the prefix
+  ///
+  /// The stack never outgrows the links, so a corrupted tree can't make
+  /// it grow without bound.
+  fn descend(&mut self, mut current: Option<T>) {
+    while let Some(idx) = current
+      && self.stack.len() < self.links.len()
+    {
+      let Some(link) = self.links.get(idx.as_usize()) else {
+        break;
+      };
+      let node = S::node(link);
+      if cmp_prefix(S::key(link), self.prefix) == Ordering::Less {
+        current = node.right();
+      } else {
+        self.stack.push(idx);
+        current = node.left();
+      }
+    }
+  }
+}
+
+impl<T: Index, S: TreeSide<T>> Iterator for SbtCursor<'_, T, S> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<T> {
+    let idx = self.stack.pop()?;
+    let link = &self.links[idx.as_usize()];
+    if cmp_prefix(S::key(link), self.prefix) != Ordering::Equal {
+      // every following node is past the prefix as well
+      self.stack.clear();
+      return None;
+    }
+    self.descend(S::node(link).right());
+    Some(idx)
+  }
+}
+
+impl SbtStrategy {
+  /// Perfectly balanced tree over `sorted` links, returns its root
+  ///
+  /// Recursion depth is logarithmic in the number of links.
+  fn balance<T: Index, S: TreeSide<T>>(
+    links: &mut [RawLink<T>],
+    sorted: &[T],
+  ) -> Option<T> {
+    let mid = sorted.len() / 2;
+    let &idx = sorted.get(mid)?;
+    let left = Self::balance::<T, S>(links, &sorted[..mid]);
+    let right = Self::balance::<T, S>(links, &sorted[mid + 1..]);
+    let size = T::from_usize(sorted.len());
+    *S::node_mut(&mut links[idx.as_usize()]) = Node {
+      size,
+      left: left.unwrap_or_else(T::null),
+      right: right.unwrap_or_else(T::null),
+    };
+    Some(idx)
+  }
+
+  /// Number of links ordered before the prefix, or not after it when
+  /// `inclusive` is set
+  fn rank<T: Index, S: TreeSide<T>>(
+    links: &[RawLink<T>],
+    root: Option<T>,
+    prefix: (T, Option<T>),
+    inclusive: bool,
+  ) -> usize {
+    LinkView::<T, S>::new(links).rank_by(root, |idx| {
+      // a missing link has no children either, which ends the walk
+      links.get(idx.as_usize()).is_some_and(|link| {
+        match cmp_prefix(S::key(link), prefix) {
+          Ordering::Less => true,
+          Ordering::Equal => inclusive,
+          Ordering::Greater => false,
+        }
+      })
+    })
+  }
+}
+
+impl<T: Index> TreeStrategy<T> for SbtStrategy {
+  const ID: u8 = 1;
+  const IN_PLACE: bool = true;
+
+  type Storage = ();
+
+  fn insert<S: TreeSide<T>>(
+    links: &mut [RawLink<T>],
+    _: &mut (),
+    root: Option<T>,
+    idx: T,
+  ) -> Option<T> {
+    LinkTree::<T, S>::new(links).insert_sbt(root, idx)
+  }
+
+  fn remove<S: TreeSide<T>>(
+    links: &mut [RawLink<T>],
+    _: &mut (),
+    root: Option<T>,
+    idx: T,
+  ) -> Option<T> {
+    LinkTree::<T, S>::new(links).remove_sbt(root, idx)
+  }
+
+  /// Links the sorted nodes into a balanced tree in linear time
+  fn build<S: TreeSide<T>>(
+    links: &mut [RawLink<T>],
+    _: &mut (),
+    sorted: &[T],
+  ) -> Option<T> {
+    Self::balance::<T, S>(links, sorted)
+  }
+
+  fn verify<S: TreeSide<T>>(
+    links: &[RawLink<T>],
+    _: &(),
+    root: Option<T>,
+    live: &dyn Fn(T) -> bool,
+  ) -> Vec<TreeFault<T>> {
+    let mut faults = Vec::new();
+    let mut seen = vec![false; links.len()];
+    let key = |idx: T| (S::key(&links[idx.as_usize()]), idx);
+
+    // nodes in pre-order, each one visited with the nodes bounding its key
+    let mut order = Vec::new();
+    let mut stack = Vec::from_iter(root.map(|root| (root, None, None)));
+    while let Some((idx, low, high)) = stack.pop() {
+      let Some(link) = links.get(idx.as_usize()) else {
+        faults.push(TreeFault::Stray(idx));
+        continue;
+      };
+      // also stops at cycles
+      if core::mem::replace(&mut seen[idx.as_usize()], true) {
+        faults.push(TreeFault::Duplicated(idx));
+        continue;
+      }
+      if !live(idx) {
+        faults.push(TreeFault::Stray(idx));
+      }
+      if low.is_some_and(|low| key(low) >= key(idx))
+        || high.is_some_and(|high| key(high) <= key(idx))
+      {
+        faults.push(TreeFault::Misplaced(idx));
+      }
+      order.push(idx);
+
+      let node = S::node(link);
+      stack.extend(node.right().map(|right| (right, Some(idx), high)));
+      stack.extend(node.left().map(|left| (left, low, Some(idx))));
+    }
+
+    // children come after their parents in pre-order
+    let mut sizes = vec![0; links.len()];
+    for &idx in order.iter().rev() {
+      let node = S::node(&links[idx.as_usize()]);
+      let size = |child: Option<T>| {
+        child.and_then(|child| sizes.get(child.as_usize())).map_or(0, |&s| s)
+      };
+      let actual = 1 + size(node.left()) + size(node.right());
+      sizes[idx.as_usize()] = actual;
+      let stored = node.size.as_usize();
+      if stored != actual {
+        faults.push(TreeFault::Size { index: idx, stored, actual });
+      }
+    }
+
+    missing(&seen, live, &mut faults);
+    faults
+  }
+
+  fn depths<S: TreeSide<T>>(
+    links: &[RawLink<T>],
+    _: &(),
+    root: Option<T>,
+  ) -> Vec<usize> {
+    let mut depths = Vec::new();
+    let mut seen = vec![false; links.len()];
+    let mut stack = Vec::from_iter(root.map(|root| (root, 0)));
+    while let Some((idx, depth)) = stack.pop() {
+      // stops at cycles and out of bounds nodes of a corrupted tree
+      let Some(link) = links.get(idx.as_usize()) else { continue };
+      if core::mem::replace(&mut seen[idx.as_usize()], true) {
+        continue;
+      }
+      depths.push(depth);
+      let node = S::node(link);
+      stack.extend(node.left().map(|left| (left, depth + 1)));
+      stack.extend(node.right().map(|right| (right, depth + 1)));
+    }
+    depths
+  }
+
+  type Cursor<'a, S: TreeSide<T>>
+    = SbtCursor<'a, T, S>
+  where
+    T: 'a;
+
+  fn cursor<'a, S: TreeSide<T>>(
+    links: &'a [RawLink<T>],
+    _: &'a (),
+    root: Option<T>,
+    first: T,
+    second: Option<T>,
+  ) -> SbtCursor<'a, T, S> {
+    SbtCursor::new(links, root, (first, second))
+  }
+
+  fn count<S: TreeSide<T>>(
+    links: &[RawLink<T>],
+    _: &(),
+    root: Option<T>,
+    first: T,
+    second: Option<T>,
+  ) -> usize {
+    let prefix = (first, second);
+    Self::rank::<T, S>(links, root, prefix, true)
+      - Self::rank::<T, S>(links, root, prefix, false)
+  }
+}
+
+/// Adaptive Radix Tree strategy marker.
+///
+/// Keeps an [`AdaptiveRadix`] trie next to the links, keyed by the
+/// big-endian bytes of the link key followed by the link index, so keys are
+/// unique and sorted like `(first, second, index)` tuples. Lookups take
+/// time proportional to the key length instead of the number of links.
+///
+/// The tree nodes inside the links stay unused. The trie is not persisted
+/// and is rebuilt when a store is opened.
+pub struct ArtStrategy;
+
+/// Widest supported index is `u128`
+const MAX_KEY: usize = 3 * size_of::<u128>();
+
+/// Bytes of index values in big-endian order, which sort like the values
+struct ArtKey {
+  bytes: [u8; MAX_KEY],
+  len: usize,
+}
+
+impl ArtKey {
+  fn new<T: Index>(parts: &[T]) -> Self {
+    let width = size_of::<T>();
+    let mut bytes = [0; MAX_KEY];
+    for (chunk, part) in bytes.chunks_exact_mut(width).zip(parts) {
+      chunk.copy_from_slice(bytemuck::bytes_of(part));
+      if cfg!(target_endian = "little") {
+        chunk.reverse();
+      }
+    }
+    Self { bytes, len: size_of_val(parts) }
+  }
+
+  fn prefix<T: Index>(first: T, second: Option<T>) -> Self {
+    match second {
+      Some(second) => Self::new(&[first, second]),
+      None => Self::new(&[first]),
+    }
+  }
+
+  fn of<T: Index, S: TreeSide<T>>(links: &[RawLink<T>], idx: T) -> Self {
+    let (first, second) = S::key(&links[idx.as_usize()]);
+    Self::new(&[first, second, idx])
+  }
+
+  fn as_bytes(&self) -> &[u8] {
+    &self.bytes[..self.len]
+  }
+}
+
+impl<T: Index> TreeStrategy<T> for ArtStrategy {
+  const ID: u8 = 2;
+  const IN_PLACE: bool = false;
+
+  type Storage = AdaptiveRadix<T>;
+
+  fn insert<S: TreeSide<T>>(
+    links: &mut [RawLink<T>],
+    art: &mut AdaptiveRadix<T>,
+    root: Option<T>,
+    idx: T,
+  ) -> Option<T> {
+    art.insert(ArtKey::of::<T, S>(links, idx).as_bytes(), idx);
+    root
+  }
+
+  fn remove<S: TreeSide<T>>(
+    links: &mut [RawLink<T>],
+    art: &mut AdaptiveRadix<T>,
+    root: Option<T>,
+    idx: T,
+  ) -> Option<T> {
+    art.remove(ArtKey::of::<T, S>(links, idx).as_bytes());
+    root
+  }
+
+  fn verify<S: TreeSide<T>>(
+    links: &[RawLink<T>],
+    art: &AdaptiveRadix<T>,
+    _: Option<T>,
+    live: &dyn Fn(T) -> bool,
+  ) -> Vec<TreeFault<T>> {
+    let mut faults = Vec::new();
+    let mut seen = vec![false; links.len()];
+    for (key, &idx) in art {
+      if idx.as_usize() >= links.len() {
+        faults.push(TreeFault::Stray(idx));
+      } else if core::mem::replace(&mut seen[idx.as_usize()], true) {
+        faults.push(TreeFault::Duplicated(idx));
+      } else if !live(idx) {
+        faults.push(TreeFault::Stray(idx));
+      } else if key != ArtKey::of::<T, S>(links, idx).as_bytes() {
+        faults.push(TreeFault::Misplaced(idx));
+      }
+    }
+    missing(&seen, live, &mut faults);
+    faults
+  }
+
+  fn depths<S: TreeSide<T>>(
+    _: &[RawLink<T>],
+    art: &AdaptiveRadix<T>,
+    _: Option<T>,
+  ) -> Vec<usize> {
+    art.depths().collect()
+  }
+
+  type Cursor<'a, S: TreeSide<T>>
+    = ArtCursor<'a, T>
+  where
+    T: 'a;
+
+  fn cursor<'a, S: TreeSide<T>>(
+    _: &'a [RawLink<T>],
+    art: &'a AdaptiveRadix<T>,
+    _: Option<T>,
+    first: T,
+    second: Option<T>,
+  ) -> ArtCursor<'a, T> {
+    let key = ArtKey::prefix(first, second);
+    ArtCursor { iter: art.prefix(key.as_bytes()) }
+  }
+
+  fn count<S: TreeSide<T>>(
+    _: &[RawLink<T>],
+    art: &AdaptiveRadix<T>,
+    _: Option<T>,
+    first: T,
+    second: Option<T>,
+  ) -> usize {
+    art.count_prefix(ArtKey::prefix(first, second).as_bytes())
+  }
+}
+
+/// Cursor over a prefix of an [`ArtStrategy`] trie
+pub struct ArtCursor<'a, T> {
+  iter: RadixIter<'a, T>,
+}
+
+impl<T: Index> Iterator for ArtCursor<'_, T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<T> {
+    self.iter.next().map(|(_, &idx)| idx)
+  }
+}
@@ -0,0 +1,139 @@
+use crate::{Index, Link, Result};
+
+/// Listener notified with the `(before, after)` states of a changed link
+pub type Listener<T> = Box<dyn FnMut(Link<T>, Link<T>) + Send + Sync>;
+
+/// Trigger asked with the `(before, after)` states of a link about to
+/// change, an error vetoes the change
+pub type Trigger<T> =
+  Box<dyn FnMut(Link<T>, Link<T>) -> Result<(), T> + Send + Sync>;
+
+/// Handle of a registered listener or trigger, see
+/// [`Store::unsubscribe`](crate::Store::unsubscribe)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Subscription(u64);
+
+/// Changes a listener or trigger is called for
+///
+/// A change matches when its event is enabled and the link matches the
+/// source and target pattern, where `T::ANY` matches anything. An update
+/// matches if the link does so before or after it. The default filter
+/// matches every change.
+///
+/// # Examples
+///
+/// ```
+/// use doublets::{Filter, Index};
+///
+/// // creates and deletes of links coming from link 1
+/// let filter =
+///   Filter { source: 1, update: false, ..Filter::<usize>::default() };
+/// assert_eq!(filter.target, usize::ANY);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Filter<T: Index> {
+  pub create: bool,
+  pub update: bool,
+  pub delete: bool,
+  pub source: T,
+  pub target: T,
+}
+
+impl<T: Index> Default for Filter<T> {
+  fn default() -> Self {
+    Self {
+      create: true,
+      update: true,
+      delete: true,
+      source: T::ANY,
+      target: T::ANY,
+    }
+  }
+}
+
+impl<T: Index> Filter<T> {
+  /// Every change of links with the given source and target pattern
+  pub fn pattern(source: T, target: T) -> Self {
+    Self { source, target, ..Self::default() }
+  }
+
+  fn matches(&self, before: Link<T>, after: Link<T>) -> bool {
+    let fits = |link: Link<T>| {
+      (self.source == T::ANY || self.source == link.source)
+        && (self.target == T::ANY || self.target == link.target)
+    };
+    if before.is_null() {
+      self.create && fits(after)
+    } else if after.is_null() {
+      self.delete && fits(before)
+    } else {
+      self.update && (fits(before) || fits(after))
+    }
+  }
+}
+
+/// Listeners and triggers registered on a store
+pub(crate) struct Hooks<T: Index> {
+  next: u64,
+  listeners: Vec<(Subscription, Filter<T>, Listener<T>)>,
+  triggers: Vec<(Subscription, Filter<T>, Trigger<T>)>,
+}
+
+impl<T: Index> Default for Hooks<T> {
+  fn default() -> Self {
+    Self { next: 0, listeners: Vec::new(), triggers: Vec::new() }
+  }
+}
+
+impl<T: Index> Hooks<T> {
+  fn subscription(&mut self) -> Subscription {
+    self.next += 1;
+    Subscription(self.next)
+  }
+
+  pub fn listen(
+    &mut self,
+    filter: Filter<T>,
+    listener: Listener<T>,
+  ) -> Subscription {
+    let subscription = self.subscription();
+    self.listeners.push((subscription, filter, listener));
+    subscription
+  }
+
+  pub fn trigger(
+    &mut self,
+    filter: Filter<T>,
+    trigger: Trigger<T>,
+  ) -> Subscription {
+    let subscription = self.subscription();
+    self.triggers.push((subscription, filter, trigger));
+    subscription
+  }
+
+  pub fn remove(&mut self, subscription: Subscription) -> bool {
+    let len = self.listeners.len() + self.triggers.len();
+    self.listeners.retain(|(registered, ..)| *registered != subscription);
+    self.triggers.retain(|(registered, ..)| *registered != subscription);
+    len != self.listeners.len() + self.triggers.len()
+  }
+
+  /// Ask matching triggers in registration order, the first error wins
+  pub fn check(&mut self, before: Link<T>, after: Link<T>) -> Result<(), T> {
+    for (_, filter, trigger) in &mut self.triggers {
+      if filter.matches(before, after) {
+        trigger(before, after)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Notify matching listeners in registration order
+  pub fn notify(&mut self, before: Link<T>, after: Link<T>) {
+    for (_, filter, listener) in &mut self.listeners {
+      if filter.matches(before, after) {
+        listener(before, after);
+      }
+    }
+  }
+}
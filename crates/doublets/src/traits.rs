@@ -7,19 +7,33 @@ pub trait Links<T: Index>: Send + Sync {
   /// Count links matching a query
   ///
   /// Query format: [index?, source?, target?]
-  /// Use T::ANY for wildcards
+  /// Use T::ANY for wildcards, T::ZERO matches null references
   fn count<const N: usize>(&self, query: [T; N]) -> T;
 
   /// Create a new link
   ///
   /// Query format: [source?, target?] or []
-  /// Empty query creates a point link
+  /// Empty query creates a point link, whose source and target are its
+  /// own index
+  /// T::ANY is not a valid source or target, [`Error::InvalidQuery`]
+  /// is returned for it
   fn create<const N: usize, H: WriteHandler<T>>(
     &mut self,
     query: [T; N],
     handler: &mut H,
   ) -> Result<Flow, T>;
 
+  /// Lazy iterator returned by [`Links::query`]
+  type Query<'a>: Iterator<Item = Link<T>>
+  where
+    Self: 'a;
+
+  /// Lazily iterate over links matching a query
+  ///
+  /// Query format is the same as for [`Links::each`]. Nothing is collected
+  /// up front, so adapters like `take` or `find` stop the traversal early.
+  fn query<const N: usize>(&self, query: [T; N]) -> Self::Query<'_>;
+
   /// Iterate over links matching a query
   fn each<const N: usize, H: ReadHandler<T>>(
     &self,
@@ -29,8 +43,11 @@ pub trait Links<T: Index>: Send + Sync {
 
   /// Update links matching a query
   ///
-  /// Query identifies which links to update
-  /// Change specifies new values
+  /// Query identifies which links to update, with the same format as for
+  /// [`Links::each`]. Change specifies new values as
+  /// [index?, source?, target?], T::ANY keeps the current value.
+  /// The handler is called for every updated link, returning
+  /// [`Flow::Break`] stops before the next one.
   fn update<const N1: usize, const N2: usize, H: WriteHandler<T>>(
     &mut self,
     query: [T; N1],
@@ -39,6 +56,9 @@ pub trait Links<T: Index>: Send + Sync {
   ) -> Result<Flow, T>;
 
   /// Delete links matching a query
+  ///
+  /// The handler is called for every deleted link, returning
+  /// [`Flow::Break`] stops before the next match.
   fn delete<const N: usize, H: WriteHandler<T>>(
     &mut self,
     query: [T; N],
@@ -85,16 +105,17 @@ pub trait Doublets<T: Index>: Links<T> {
       index = after.index;
       Flow::Continue
     })?;
-    self.update(
-      [index],
-      [index, index, index],
-      &mut |_before: Link<T>, _after: Link<T>| Flow::Continue,
-    )?;
     Ok(index)
   }
 
   /// Update a specific link
+  ///
+  /// Returns [`Error::InvalidQuery`] if `source` or `target` is `T::ANY`,
+  /// which is never stored in a link.
   fn update_link(&mut self, index: T, source: T, target: T) -> Result<T, T> {
+    if source == T::ANY || target == T::ANY {
+      return Err(Error::InvalidQuery);
+    }
     let mut result = T::ZERO;
     self.update(
       [index],
@@ -150,7 +171,8 @@ pub trait Doublets<T: Index>: Links<T> {
       usage_target = usage_target.checked_sub_one().unwrap_or(usage_target);
     }
 
-    Ok(T::from_usize(usage_source.as_usize() + usage_target.as_usize()))
+    let usages = usage_source.as_usize() + usage_target.as_usize();
+    T::try_from_usize(usages).ok_or(Error::Overflow)
   }
 
   /// Check if a link has any usages
@@ -198,18 +220,12 @@ pub trait Doublets<T: Index>: Links<T> {
 
   /// Collect all links into a vector
   fn collect_all(&self) -> Vec<Link<T>> {
-    let count = self.count_all().as_usize();
-    let mut result = Vec::with_capacity(count);
-    self.each([], &mut |link| {
-      result.push(link);
-      Flow::Continue
-    });
-    result
+    self.iter().collect()
   }
 
   /// Iterate over all links
-  fn iter(&self) -> impl Iterator<Item = Link<T>> {
-    self.collect_all().into_iter()
+  fn iter(&self) -> Self::Query<'_> {
+    self.query([])
   }
 }
 
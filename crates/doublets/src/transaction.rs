@@ -0,0 +1,108 @@
+use {
+  crate::{Index, Link, RawLink, Store, TreeStrategy},
+  core::ops::{Deref, DerefMut},
+  mem::RawMem,
+};
+
+/// Change recorded in the undo log of a transaction
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Undo<T: Index> {
+  /// Link was created, from a fresh slot or from the free list
+  Create { index: T, fresh: bool },
+  /// Link had the given state before it was updated
+  Update { before: Link<T> },
+  /// Link had the given state before it was deleted
+  Delete { before: Link<T> },
+}
+
+/// Atomic set of changes to a [`Store`]
+///
+/// Created by [`Store::transaction`]. Dereferences to the store, so all
+/// [`Doublets`](crate::Doublets) operations are available. Every change is
+/// recorded into an undo log and reverted unless the transaction commits.
+pub struct Transaction<'a, T, M, S, R>
+where
+  T: Index,
+  M: RawMem<Item = RawLink<T>> + Send + Sync,
+  S: TreeStrategy<T>,
+  R: TreeStrategy<T>,
+{
+  store: &'a mut Store<T, M, S, R>,
+  /// Length of the undo log when the transaction began
+  mark: usize,
+  /// Whether the transaction is not nested into another one
+  outermost: bool,
+  committed: bool,
+  aborted: bool,
+}
+
+impl<'a, T, M, S, R> Transaction<'a, T, M, S, R>
+where
+  T: Index,
+  M: RawMem<Item = RawLink<T>> + Send + Sync,
+  S: TreeStrategy<T>,
+  R: TreeStrategy<T>,
+{
+  pub(crate) fn new(store: &'a mut Store<T, M, S, R>) -> Self {
+    let (mark, outermost) = store.begin_undo();
+    Self { store, mark, outermost, committed: false, aborted: false }
+  }
+
+  /// Revert every change of this transaction once it finishes, even if
+  /// the closure succeeds
+  pub fn abort(&mut self) {
+    self.aborted = true;
+  }
+
+  /// Whether [`Transaction::abort`] was called
+  pub fn is_aborted(&self) -> bool {
+    self.aborted
+  }
+
+  /// Keep the changes, unless the transaction was aborted
+  pub(crate) fn commit(mut self) {
+    self.committed = !self.aborted;
+  }
+}
+
+impl<T, M, S, R> Deref for Transaction<'_, T, M, S, R>
+where
+  T: Index,
+  M: RawMem<Item = RawLink<T>> + Send + Sync,
+  S: TreeStrategy<T>,
+  R: TreeStrategy<T>,
+{
+  type Target = Store<T, M, S, R>;
+
+  fn deref(&self) -> &Self::Target {
+    self.store
+  }
+}
+
+impl<T, M, S, R> DerefMut for Transaction<'_, T, M, S, R>
+where
+  T: Index,
+  M: RawMem<Item = RawLink<T>> + Send + Sync,
+  S: TreeStrategy<T>,
+  R: TreeStrategy<T>,
+{
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    self.store
+  }
+}
+
+impl<T, M, S, R> Drop for Transaction<'_, T, M, S, R>
+where
+  T: Index,
+  M: RawMem<Item = RawLink<T>> + Send + Sync,
+  S: TreeStrategy<T>,
+  R: TreeStrategy<T>,
+{
+  // also reverts the changes when the closure panics
+  fn drop(&mut self) {
+    if !self.committed {
+      self.store.rollback_to(self.mark);
+    }
+    self.store.end_undo(self.outermost);
+  }
+}
@@ -0,0 +1,293 @@
+use {
+  crate::{Doublets, Flow, Index, Link, Links},
+  std::collections::{BTreeMap, BTreeSet, VecDeque},
+};
+
+/// Which links a walk follows from a link
+///
+/// Every link `(index: source target)` is an edge from its source to its
+/// target. Loops of a link to itself, like points, are never followed, and
+/// neither are null or missing references.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+  /// Links having it as their source, leading to their targets
+  Outgoing,
+  /// Links having it as their target, leading to their sources
+  Incoming,
+  /// Both, as if links had no direction
+  Both,
+}
+
+/// Link reached by a walk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step<T: Index> {
+  /// Index of the reached link
+  pub index: T,
+  /// Link followed to reach it, `None` for the start
+  pub via: Option<Link<T>>,
+  /// Number of links followed from the start
+  pub depth: usize,
+}
+
+/// Call `f` with every link followed from `node` and the link it leads to
+fn edges<T: Index, D: Links<T> + ?Sized>(
+  links: &D,
+  node: T,
+  direction: Direction,
+  mut f: impl FnMut(Link<T>, T),
+) {
+  let mut follow = |link: Link<T>, next: T| {
+    if next != node && !next.is_zero() && links.get(next).is_some() {
+      f(link, next);
+    }
+    Flow::Continue
+  };
+  if direction != Direction::Incoming {
+    links.each([T::ANY, node, T::ANY], &mut |link: Link<T>| {
+      follow(link, link.target)
+    });
+  }
+  if direction != Direction::Outgoing {
+    links.each([T::ANY, T::ANY, node], &mut |link: Link<T>| {
+      follow(link, link.source)
+    });
+  }
+}
+
+/// Breadth-first walk, see [`Traversal::bfs`]
+pub struct Bfs<'a, T: Index, D: Links<T> + ?Sized> {
+  links: &'a D,
+  direction: Direction,
+  queue: VecDeque<Step<T>>,
+  seen: BTreeSet<T>,
+}
+
+impl<'a, T: Index, D: Links<T> + ?Sized> Bfs<'a, T, D> {
+  fn new(links: &'a D, start: T, direction: Direction) -> Self {
+    let mut walk =
+      Self { links, direction, queue: VecDeque::new(), seen: BTreeSet::new() };
+    if links.get(start).is_some() {
+      walk.seen.insert(start);
+      walk.queue.push_back(Step { index: start, via: None, depth: 0 });
+    }
+    walk
+  }
+}
+
+impl<T: Index, D: Links<T> + ?Sized> Iterator for Bfs<'_, T, D> {
+  type Item = Step<T>;
+
+  fn next(&mut self) -> Option<Step<T>> {
+    let step = self.queue.pop_front()?;
+    let Self { queue, seen, .. } = self;
+    edges(self.links, step.index, self.direction, |link, next| {
+      if seen.insert(next) {
+        let depth = step.depth + 1;
+        queue.push_back(Step { index: next, via: Some(link), depth });
+      }
+    });
+    Some(step)
+  }
+}
+
+/// Depth-first walk in pre-order, see [`Traversal::dfs`]
+pub struct Dfs<'a, T: Index, D: Links<T> + ?Sized> {
+  links: &'a D,
+  direction: Direction,
+  stack: Vec<Step<T>>,
+  seen: BTreeSet<T>,
+}
+
+impl<'a, T: Index, D: Links<T> + ?Sized> Dfs<'a, T, D> {
+  fn new(links: &'a D, start: T, direction: Direction) -> Self {
+    let mut stack = Vec::new();
+    if links.get(start).is_some() {
+      stack.push(Step { index: start, via: None, depth: 0 });
+    }
+    Self { links, direction, stack, seen: BTreeSet::new() }
+  }
+}
+
+impl<T: Index, D: Links<T> + ?Sized> Iterator for Dfs<'_, T, D> {
+  type Item = Step<T>;
+
+  fn next(&mut self) -> Option<Step<T>> {
+    loop {
+      let step = self.stack.pop()?;
+      if !self.seen.insert(step.index) {
+        continue;
+      }
+      // pushed in reverse, so the first found link is walked first
+      let mark = self.stack.len();
+      let Self { stack, seen, .. } = self;
+      edges(self.links, step.index, self.direction, |link, next| {
+        if !seen.contains(&next) {
+          let depth = step.depth + 1;
+          stack.push(Step { index: next, via: Some(link), depth });
+        }
+      });
+      self.stack[mark..].reverse();
+      return Some(step);
+    }
+  }
+}
+
+/// Connected components of a store, see [`Traversal::components`]
+pub struct Components<'a, T: Index, D: Links<T> + ?Sized + 'a> {
+  links: &'a D,
+  all: D::Query<'a>,
+  seen: BTreeSet<T>,
+}
+
+impl<T: Index, D: Links<T> + ?Sized> Iterator for Components<'_, T, D> {
+  type Item = Vec<T>;
+
+  fn next(&mut self) -> Option<Vec<T>> {
+    let start =
+      self.all.by_ref().find(|link| !self.seen.contains(&link.index))?;
+    let mut component: Vec<_> =
+      Bfs::new(self.links, start.index, Direction::Both)
+        .map(|step| step.index)
+        .collect();
+    self.seen.extend(component.iter().copied());
+    component.sort_unstable();
+    Some(component)
+  }
+}
+
+/// Graph algorithms over the links of a store
+///
+/// Links are visited through [`Links::each`] one at a time, so nothing
+/// but the links already reached is kept in memory.
+///
+/// # Examples
+///
+/// ```
+/// use doublets::{Direction, Doublets, Traversal, create_heap_store};
+///
+/// let mut store = create_heap_store::<usize>().unwrap();
+/// let a = store.create_point().unwrap();
+/// let b = store.create_point().unwrap();
+/// let c = store.create_point().unwrap();
+/// let ab = store.create_link(a, b).unwrap();
+/// let bc = store.create_link(b, c).unwrap();
+///
+/// let reached: Vec<_> =
+///   store.bfs(a, Direction::Outgoing).map(|step| step.index).collect();
+/// assert_eq!(reached, [a, b, c]);
+///
+/// let path = store.shortest_path(a, c, Direction::Outgoing).unwrap();
+/// assert_eq!(path.iter().map(|link| link.index).collect::<Vec<_>>(), [ab, bc]);
+/// assert_eq!(store.shortest_path(c, a, Direction::Outgoing), None);
+/// ```
+pub trait Traversal<T: Index>: Doublets<T> {
+  /// Walk links reachable from `start` breadth-first, starting with it
+  ///
+  /// Nothing is walked if `start` does not exist.
+  fn bfs(&self, start: T, direction: Direction) -> Bfs<'_, T, Self> {
+    Bfs::new(self, start, direction)
+  }
+
+  /// Walk links reachable from `start` depth-first in pre-order, starting
+  /// with it
+  ///
+  /// Nothing is walked if `start` does not exist.
+  fn dfs(&self, start: T, direction: Direction) -> Dfs<'_, T, Self> {
+    Dfs::new(self, start, direction)
+  }
+
+  /// Find a cycle reachable from `start`, returns its links in walking
+  /// order
+  ///
+  /// With [`Direction::Both`] a link is not walked back the way it was
+  /// followed, so a cycle needs at least two links.
+  fn find_cycle(&self, start: T, direction: Direction) -> Option<Vec<Link<T>>> {
+    struct Frame<T: Index> {
+      index: T,
+      via: Option<Link<T>>,
+      edges: Vec<(Link<T>, T)>,
+    }
+    let frame = |index: T, via: Option<Link<T>>| {
+      let mut found = Vec::new();
+      edges(self, index, direction, |link, next| found.push((link, next)));
+      // popped from the back, in the order they were found
+      found.reverse();
+      Frame { index, via, edges: found }
+    };
+
+    self.get(start)?;
+    // whether a link is on the current path, or done with otherwise
+    let mut on_path = BTreeMap::from([(start, true)]);
+    let mut path = vec![frame(start, None)];
+    while let Some(top) = path.last_mut() {
+      let Some((link, next)) = top.edges.pop() else {
+        on_path.insert(top.index, false);
+        path.pop();
+        continue;
+      };
+      if top.via.is_some_and(|via| via.index == link.index) {
+        continue;
+      }
+      match on_path.get(&next) {
+        Some(true) => {
+          let at = path.iter().position(|frame| frame.index == next)?;
+          let mut cycle: Vec<_> =
+            path[at + 1..].iter().filter_map(|frame| frame.via).collect();
+          cycle.push(link);
+          return Some(cycle);
+        }
+        Some(false) => {}
+        None => {
+          on_path.insert(next, true);
+          path.push(frame(next, Some(link)));
+        }
+      }
+    }
+    None
+  }
+
+  /// Find a path with the fewest links from `from` to `to`, returns its
+  /// links in walking order
+  ///
+  /// The path from a link to itself is empty. Returns `None` if `to` is
+  /// not reachable.
+  fn shortest_path(
+    &self,
+    from: T,
+    to: T,
+    direction: Direction,
+  ) -> Option<Vec<Link<T>>> {
+    let mut parents = BTreeMap::new();
+    for step in self.bfs(from, direction) {
+      if let Some(via) = step.via {
+        parents.insert(step.index, via);
+      }
+      if step.index != to {
+        continue;
+      }
+      let mut path = Vec::new();
+      let mut current = to;
+      while let Some(&via) = parents.get(&current) {
+        path.push(via);
+        current = if via.source == current { via.target } else { via.source };
+      }
+      path.reverse();
+      return Some(path);
+    }
+    None
+  }
+
+  /// Iterate over the connected components, ignoring the direction of
+  /// links
+  ///
+  /// Every component is a sorted list of link indices, yielded when its
+  /// first link is met iterating the store. A link is an edge between its
+  /// source and target, so it is alone in its component unless other
+  /// links reference it. Links already labelled are remembered, the rest
+  /// is walked lazily.
+  fn components(&self) -> Components<'_, T, Self> {
+    Components { links: self, all: self.query([]), seen: BTreeSet::new() }
+  }
+}
+
+impl<T: Index, D: Doublets<T> + ?Sized> Traversal<T> for D {}
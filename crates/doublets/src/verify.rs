@@ -0,0 +1,73 @@
+use {
+  crate::Index,
+  core::fmt::{self, Display, Formatter},
+  thiserror::Error,
+};
+
+/// One of the two indexes of a [`Store`](crate::Store)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+  /// Links ordered by `(source, target)`
+  Source,
+  /// Links ordered by `(target, source)`
+  Target,
+}
+
+impl Display for Side {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Side::Source => f.write_str("source"),
+      Side::Target => f.write_str("target"),
+    }
+  }
+}
+
+/// Problem found in a single index by
+/// [`TreeStrategy::verify`](crate::TreeStrategy::verify)
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum TreeFault<T: Index> {
+  #[error("link {0:?} is missing")]
+  Missing(T),
+  #[error("link {0:?} is reached more than once")]
+  Duplicated(T),
+  #[error("slot {0:?} is reached but holds no link")]
+  Stray(T),
+  #[error("link {0:?} is out of key order")]
+  Misplaced(T),
+  #[error("subtree of link {index:?} stores size {stored}, has {actual}")]
+  Size { index: T, stored: usize, actual: usize },
+}
+
+/// Inconsistency found by [`Store::verify`](crate::Store::verify)
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum Violation<T: Index> {
+  #[error("{side} index: {fault}")]
+  Tree { side: Side, fault: TreeFault<T> },
+  #[error("free list loops back to {0:?}")]
+  FreeListCycle(T),
+  #[error("free list goes through {0:?}, which is not a free slot")]
+  FreeListBroken(T),
+  #[error("free slot {0:?} is not on the free list")]
+  Unlisted(T),
+  #[error("free count is {stored}, the free list has {actual}")]
+  FreeCount { stored: usize, actual: usize },
+  /// Left behind by [`OnDelete::Allow`](crate::OnDelete::Allow) as well
+  #[error("link {index:?} references missing link {reference:?}")]
+  Dangling { index: T, reference: T },
+}
+
+/// Result of checking a store for consistency
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report<T: Index> {
+  /// Number of links checked
+  pub links: usize,
+  /// Every inconsistency found, in the order of the checks
+  pub violations: Vec<Violation<T>>,
+}
+
+impl<T: Index> Report<T> {
+  /// Whether no violations were found
+  pub fn is_consistent(&self) -> bool {
+    self.violations.is_empty()
+  }
+}
@@ -1,4 +1,4 @@
-use doublets::{Doublets, Flow, Link, Links, Result, create_heap_store};
+use doublets::{Doublets, Flow, Index, Link, Links, Result, create_heap_store};
 
 #[test]
 fn test_create_point() -> Result<(), usize> {
@@ -153,7 +153,7 @@ fn test_each_with_query() -> Result<(), usize> {
 
   let mut found_links = Vec::new();
 
-  store.each([0, a, 0], &mut |link| {
+  store.each([usize::ANY, a, usize::ANY], &mut |link| {
     found_links.push(link);
     Flow::Continue
   });
@@ -176,3 +176,102 @@ fn test_get_or_create() -> Result<(), usize> {
   assert_eq!(store.count_all(), 3);
   Ok(())
 }
+
+#[test]
+fn test_narrow_index_layout() {
+  use doublets::RawLink;
+
+  assert_eq!(size_of::<RawLink<u32>>() * 2, size_of::<RawLink<u64>>());
+  // nine indices and no padding, so links are plain data
+  assert_eq!(size_of::<RawLink<u8>>(), 9);
+  assert_eq!(size_of::<RawLink<u32>>(), 9 * 4);
+}
+
+#[test]
+fn test_first_index_per_width() {
+  fn first<T: Index>() -> T {
+    create_heap_store::<T>().unwrap().create_point().unwrap()
+  }
+
+  assert_eq!(first::<u8>(), 3);
+  assert_eq!(first::<u16>(), 2);
+  assert_eq!(first::<u32>(), 1);
+  assert_eq!(first::<u64>(), 1);
+  assert_eq!(first::<usize>(), 1);
+}
+
+#[test]
+fn test_narrow_index_store() -> Result<(), u32> {
+  let mut store = create_heap_store::<u32>()?;
+
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let c = store.create_link(a, b)?;
+
+  assert_eq!(store.get(c), Some(Link::new(c, a, b)));
+  assert_eq!(store.search(a, b), Some(c));
+  assert_eq!(store.count_usages(a)?, 1);
+  Ok(())
+}
+
+#[test]
+fn test_index_overflow() -> Result<(), u8> {
+  let mut store = create_heap_store::<u8>()?;
+
+  let mut last = 0;
+  while let Ok(index) = store.create_point() {
+    last = index;
+  }
+  assert!(last < u8::MAX);
+  assert_eq!(store.create_point(), Err(doublets::Error::Overflow));
+
+  // freed indices are still reusable
+  store.delete_link(last)?;
+  assert_eq!(store.create_point()?, last);
+  Ok(())
+}
+
+#[test]
+fn test_null_references() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let any = usize::ANY;
+
+  let a = store.create_point()?;
+  let to_a = store.create_link(0, a)?;
+  let from_a = store.create_link(a, 0)?;
+  let null = store.create_link(0, 0)?;
+
+  // 0 is the null link, not a wildcard
+  assert_eq!(store.count([any, 0, any]), 2);
+  assert_eq!(store.count([any, any, 0]), 2);
+  assert_eq!(store.count([any, 0, 0]), 1);
+  assert_eq!(store.count([any, any, any]), 4);
+  assert_eq!(store.search(0, a), Some(to_a));
+  assert_eq!(store.search(a, 0), Some(from_a));
+
+  let mut found = Vec::new();
+  store.each([any, 0, any], &mut |link: Link<usize>| {
+    found.push(link.index);
+    Flow::Continue
+  });
+  assert_eq!(found, [null, to_a]);
+  assert_eq!(store.count([null, 0, 0]), 1);
+  assert_eq!(store.count([to_a, 0, 0]), 0);
+  Ok(())
+}
+
+#[test]
+fn test_any_is_never_stored() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let any = usize::ANY;
+  let a = store.create_point()?;
+
+  let invalid = doublets::Error::InvalidQuery;
+  assert_eq!(store.create_link(any, a), Err(invalid.clone()));
+  assert_eq!(store.create_link(a, any), Err(invalid.clone()));
+  assert_eq!(store.update_link(a, a, any), Err(invalid.clone()));
+  assert_eq!(store.bulk_load([(a, a), (any, a)]), Err(invalid));
+  assert_eq!(store.count_all(), 1);
+  assert_eq!(store.get(a), Some(Link::new(a, a, a)));
+  Ok(())
+}
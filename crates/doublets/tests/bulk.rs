@@ -0,0 +1,132 @@
+// Tests for pattern-based bulk update and delete
+//
+// Queries with a wildcard index apply the change to every matching link,
+// calling the handler for each of them until it breaks.
+
+use doublets::{
+  Doublets, Error, Flow, Index, Link, Links, Policy, Result, create_heap_store,
+};
+
+const ANY: usize = usize::ANY;
+
+#[test]
+fn test_update_by_pattern() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let c = store.create_point()?;
+  let ab = store.create_link(a, b)?;
+  let ac = store.create_link(a, c)?;
+  let cb = store.create_link(c, b)?;
+
+  let mut changed = Vec::new();
+  store.update([ANY, a, ANY], [ANY, b, ANY], &mut |before, after| {
+    changed.push((before, after));
+    Flow::Continue
+  })?;
+  assert_eq!(
+    changed,
+    [
+      (Link::new(a, a, a), Link::new(a, b, a)),
+      (Link::new(ab, a, b), Link::new(ab, b, b)),
+      (Link::new(ac, a, c), Link::new(ac, b, c)),
+    ]
+  );
+  assert_eq!(store.count([ANY, a, ANY]), 0);
+  assert_eq!(store.count([ANY, b, ANY]), 4);
+
+  // only the target changes
+  store.update([ANY, ANY, b], [ANY, ANY, c], &mut |_, _| Flow::Continue)?;
+  assert_eq!(store.get(cb), Some(Link::new(cb, c, c)));
+  assert_eq!(store.count([ANY, ANY, b]), 0);
+  Ok(())
+}
+
+#[test]
+fn test_delete_by_pattern() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+  let x = store.create_point()?;
+  for _ in 0..10 {
+    store.create_link(a, x)?;
+  }
+  let kept = store.create_link(x, a)?;
+
+  let mut deleted = 0;
+  store.delete([ANY, ANY, x], &mut |_, after: Link<usize>| {
+    assert!(after.is_null());
+    deleted += 1;
+    Flow::Continue
+  })?;
+  assert_eq!(deleted, 11);
+  assert_eq!(store.count_all(), 2);
+  assert_eq!(store.search(x, a), Some(kept));
+
+  // no matches is not an error
+  assert_eq!(
+    store.delete([ANY, ANY, x], &mut |_, _| Flow::Continue)?,
+    Flow::Continue
+  );
+  Ok(())
+}
+
+#[test]
+fn test_bulk_break() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  for _ in 0..10 {
+    store.create_link(a, b)?;
+  }
+
+  let mut handled = 0;
+  let flow = store.delete([ANY, a, b], &mut |_, _| {
+    handled += 1;
+    handled < 3
+  })?;
+  assert_eq!(flow, Flow::Break);
+  assert_eq!(handled, 3);
+  assert_eq!(store.count([ANY, a, b]), 7);
+  Ok(())
+}
+
+#[test]
+fn test_bulk_is_atomic() -> Result<(), usize> {
+  let policy = Policy { unique: true, ..Policy::default() };
+  let mut store = create_heap_store::<usize>()?.with_policy(policy);
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let c = store.create_point()?;
+  store.create_link(a, b)?;
+  store.create_link(c, b)?;
+  let before = store.collect_all();
+
+  // the second link would duplicate the first one
+  let result =
+    store.update([ANY, ANY, b], [ANY, a, b], &mut |_, _| Flow::Continue);
+  assert!(matches!(result, Err(Error::AlreadyExists(..))));
+  assert_eq!(store.collect_all(), before);
+  Ok(())
+}
+
+#[test]
+fn test_single_index_with_pattern() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let ab = store.create_link(a, b)?;
+
+  // the link doesn't match the pattern, nothing happens
+  store.update([ab, b, ANY], [ab, b, b], &mut |_, _| Flow::Continue)?;
+  assert_eq!(store.get(ab), Some(Link::new(ab, a, b)));
+  store.delete([ab, ANY, a], &mut |_, _| Flow::Continue)?;
+  assert!(store.get(ab).is_some());
+
+  // links can't be moved to another index
+  assert_eq!(
+    store.update([ab], [a, b, b], &mut |_, _| Flow::Continue),
+    Err(Error::InvalidQuery)
+  );
+  assert_eq!(store.delete_link(100), Err(Error::NotExists(100)));
+  Ok(())
+}
@@ -0,0 +1,151 @@
+// Tests for loading links in bulk
+//
+// Loaded links are appended after every allocated slot and both trees are
+// rebuilt balanced, or inserted into for small batches, so the store must
+// answer queries exactly as if the links were created one by one.
+
+use doublets::{
+  ArtStrategy, Doublets, Error, Index, Link, Links, Policy, RawLink, Result,
+  SbtStrategy, Store, TreeStrategy, create_heap_store,
+};
+use mem::Alloc;
+
+const ANY: usize = usize::ANY;
+
+fn pairs(count: usize) -> impl Iterator<Item = (usize, usize)> {
+  (0..count).map(|i| (i * 7 % 97 + 1, i * 13 % 89 + 1))
+}
+
+fn test_load<S, R>() -> Result<(), usize>
+where
+  S: TreeStrategy<usize>,
+  R: TreeStrategy<usize>,
+{
+  let mut loaded =
+    Store::<usize, Alloc<RawLink<usize>>, S, R>::new(Alloc::new())?;
+  let mut created =
+    Store::<usize, Alloc<RawLink<usize>>, S, R>::new(Alloc::new())?;
+
+  let range = loaded.bulk_load(pairs(5000))?;
+  assert_eq!(range, 1..5001);
+  for (source, target) in pairs(5000) {
+    created.create_link(source, target)?;
+  }
+
+  assert_eq!(loaded.verify().violations, []);
+  assert_eq!(loaded.collect_all(), created.collect_all());
+  for i in 1..=100 {
+    for query in [[ANY, i, ANY], [ANY, ANY, i], [ANY, i, i * 3 % 89 + 1]] {
+      assert_eq!(
+        loaded.query(query).collect::<Vec<_>>(),
+        created.query(query).collect::<Vec<_>>()
+      );
+      assert_eq!(loaded.count(query), created.count(query));
+    }
+  }
+  Ok(())
+}
+
+#[test]
+fn test_load_sbt() -> Result<(), usize> {
+  test_load::<SbtStrategy, SbtStrategy>()
+}
+
+#[test]
+fn test_load_art() -> Result<(), usize> {
+  test_load::<ArtStrategy, ArtStrategy>()
+}
+
+#[test]
+fn test_load_small_batches() -> Result<(), usize> {
+  let mut loaded = create_heap_store::<usize>()?;
+  let mut created = create_heap_store::<usize>()?;
+  let pairs: Vec<_> = pairs(2000).collect();
+
+  // the first batch builds the trees, the following ones are inserted
+  loaded.bulk_load(pairs[..1000].iter().copied())?;
+  for batch in pairs[1000..].chunks(50) {
+    loaded.bulk_load(batch.iter().copied())?;
+  }
+  for &(source, target) in &pairs {
+    created.create_link(source, target)?;
+  }
+
+  assert_eq!(loaded.verify().violations, []);
+  assert_eq!(loaded.collect_all(), created.collect_all());
+  for i in 1..=100 {
+    assert_eq!(loaded.count([ANY, i, ANY]), created.count([ANY, i, ANY]));
+    assert_eq!(loaded.count([ANY, ANY, i]), created.count([ANY, ANY, i]));
+  }
+  Ok(())
+}
+
+#[test]
+fn test_load_into_existing() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let c = store.create_point()?;
+  store.delete_link(b)?;
+
+  let range = store.bulk_load([(a, c), (c, a), (a, c)])?;
+  assert_eq!(range, 4..7);
+  assert_eq!(store.verify().violations, []);
+  assert_eq!(store.count([ANY, a, c]), 2);
+  assert_eq!(store.get(5), Some(Link::new(5, c, a)));
+
+  // the free slot is left for the next link
+  assert_eq!(store.create_point()?, b);
+  assert_eq!(store.bulk_load([])?, 7..7);
+  Ok(())
+}
+
+#[test]
+fn test_load_unique() -> Result<(), usize> {
+  let policy = Policy { unique: true, ..Policy::default() };
+  let mut store = create_heap_store::<usize>()?.with_policy(policy);
+  let a = store.create_point()?;
+  let ab = store.create_link(a, 5)?;
+  let before = store.collect_all();
+
+  assert_eq!(
+    store.bulk_load([(1, 2), (3, 4), (1, 2)]),
+    Err(Error::AlreadyExists(3, 1, 2))
+  );
+  assert_eq!(
+    store.bulk_load([(1, 2), (a, 5)]),
+    Err(Error::AlreadyExists(ab, a, 5))
+  );
+  assert_eq!(store.collect_all(), before);
+
+  // null links are exempt
+  store.bulk_load([(0, 0), (0, 0)])?;
+  assert_eq!(store.count_all(), 4);
+  Ok(())
+}
+
+#[test]
+fn test_load_reverted() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+
+  let result = store.transaction(|tx| {
+    tx.bulk_load(pairs(100))?;
+    tx.delete_link(1000)
+  });
+  assert_eq!(result, Err(Error::NotExists(1000)));
+  assert_eq!(store.collect_all(), [Link::new(a, a, a)]);
+  assert!(store.verify().is_consistent());
+  assert_eq!(store.create_point()?, 2);
+  Ok(())
+}
+
+#[test]
+fn test_load_overflow() -> Result<(), u8> {
+  let mut store = create_heap_store::<u8>()?;
+  // u8 headers take three slots and `u8::MAX` is never an index
+  assert_eq!(store.bulk_load((0..253).map(|_| (0, 0))), Err(Error::Overflow));
+  assert_eq!(store.bulk_load((0..252).map(|_| (0, 0)))?, 3..255);
+  assert_eq!(store.create_point(), Err(Error::Overflow));
+  Ok(())
+}
@@ -0,0 +1,48 @@
+use {
+  doublets::{
+    Index, Journal, RawLink, Result, SbtStrategy, Store, TreeStrategy,
+  },
+  mem::FileMapped,
+  std::path::Path,
+};
+
+/// Store backed by a file mapping
+pub type FileStore<T = usize, S = SbtStrategy, R = SbtStrategy> =
+  Store<T, FileMapped<RawLink<T>>, S, R>;
+
+/// Open the store written into the file at `path`, creating an empty one
+/// if there is no file yet
+#[allow(dead_code)]
+pub fn open(path: &Path) -> Result<FileStore, usize> {
+  open_as(path)
+}
+
+/// Same as [`open`] with other index type and tree strategies
+#[allow(dead_code)]
+pub fn open_as<T, S, R>(path: &Path) -> Result<FileStore<T, S, R>, T>
+where
+  T: Index,
+  S: TreeStrategy<T>,
+  R: TreeStrategy<T>,
+{
+  let mem = FileMapped::from_path(path).expect("file should be mapped");
+  // SAFETY: file always represents initialized bytes
+  unsafe { Store::open(mem) }
+}
+
+/// Same as [`open_as`], recovering the store with `journal` and recording
+/// every following change into it
+#[allow(dead_code)]
+pub fn open_journaled<T, S, R>(
+  path: &Path,
+  journal: Journal,
+) -> Result<FileStore<T, S, R>, T>
+where
+  T: Index,
+  S: TreeStrategy<T>,
+  R: TreeStrategy<T>,
+{
+  let mem = FileMapped::from_path(path).expect("file should be mapped");
+  // SAFETY: file always represents initialized bytes
+  unsafe { Store::open_with_journal(mem, journal) }
+}
@@ -0,0 +1,236 @@
+// Tests for compacting the link arena
+//
+// Compaction moves live links into a dense prefix, rewrites references to
+// them and releases the tail of the memory.
+
+mod common;
+
+use {
+  common::{FileStore, open, open_journaled},
+  doublets::{
+    ArtStrategy, Doublets, Error, Index, Journal, Link, Links, RawLink, Result,
+    SbtStrategy, Store, SyncPolicy, TreeStrategy, create_heap_store,
+  },
+  mem::{Alloc, FileMapped, Page, RawMem},
+  std::{fs, io},
+};
+
+const ANY: usize = usize::ANY;
+
+fn test_compact_keeps_links<S, R>() -> Result<(), usize>
+where
+  S: TreeStrategy<usize>,
+  R: TreeStrategy<usize>,
+{
+  let mut store =
+    Store::<usize, Alloc<RawLink<usize>>, S, R>::new(Alloc::new())?;
+  let points: Vec<_> =
+    (0..50).map(|_| store.create_point()).collect::<Result<_, _>>()?;
+  for i in 0..500 {
+    store.create_link(points[i % 50], points[i * 7 % 50])?;
+  }
+  for link in (1..550).filter(|link| link % 3 != 0) {
+    if store.get(link).is_some_and(|link| link.source != link.index) {
+      store.delete_link(link)?;
+    }
+  }
+  let before = store.collect_all();
+
+  let remap = store.compact()?;
+  let moved = |index: usize| remap[index];
+  let expected: Vec<_> = before
+    .iter()
+    .map(|link| {
+      Link::new(moved(link.index), moved(link.source), moved(link.target))
+    })
+    .collect();
+  assert_eq!(store.collect_all(), expected);
+
+  // indices are dense and keep their order
+  for (i, link) in expected.iter().enumerate() {
+    assert_eq!(link.index, i + 1);
+  }
+  for link in &expected {
+    assert!(store.search(link.source, link.target).is_some());
+    let by_source = store.query([ANY, link.source, ANY]).count();
+    assert_eq!(store.count([ANY, link.source, ANY]), by_source);
+    let by_target = store.query([ANY, ANY, link.target]).count();
+    assert_eq!(store.count([ANY, ANY, link.target]), by_target);
+  }
+
+  // new links are appended after the moved ones
+  assert_eq!(store.create_point()?, expected.len() + 1);
+  Ok(())
+}
+
+#[test]
+fn test_compact_sbt() -> Result<(), usize> {
+  test_compact_keeps_links::<SbtStrategy, SbtStrategy>()
+}
+
+#[test]
+fn test_compact_art() -> Result<(), usize> {
+  test_compact_keeps_links::<ArtStrategy, ArtStrategy>()
+}
+
+#[test]
+fn test_compact_nullifies_dangling() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let ab = store.create_link(a, b)?;
+  store.delete_link(a)?;
+
+  let remap = store.compact()?;
+  assert_eq!(remap[a], 0);
+  assert_eq!(remap[b], 1);
+  assert_eq!(remap[ab], 2);
+  assert_eq!(store.collect_all(), [Link::new(1, 1, 1), Link::new(2, 0, 1)]);
+  assert_eq!(store.count([ANY, 0, ANY]), 1);
+  Ok(())
+}
+
+#[test]
+fn test_compact_shrinks_file() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+  let size = || fs::metadata(&path).unwrap().len();
+
+  let mut store = open(&path)?;
+  let links: Vec<_> =
+    (0..10_000).map(|_| store.create_point()).collect::<Result<_, _>>()?;
+  let peak = size();
+  for &link in &links[10..] {
+    store.delete_link(link)?;
+  }
+  store.compact()?;
+  assert!(size() < peak / 4);
+  drop(store);
+
+  let store = open(&path)?;
+  assert_eq!(store.count_all(), 10);
+  assert_eq!(store.get(10), Some(Link::new(10, 10, 10)));
+  Ok(())
+}
+
+#[test]
+fn test_compact_inside_transaction() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  store.create_point()?;
+  let result = store.transaction(|tx| tx.compact());
+  assert_eq!(result, Err(Error::InvalidQuery));
+  Ok(())
+}
+
+/// File memory that can't shrink, which interrupts a compaction after the
+/// links are moved but before the journal is emptied
+struct NoShrink(FileMapped<RawLink<usize>>);
+
+impl RawMem for NoShrink {
+  type Item = RawLink<usize>;
+
+  fn as_slice(&self) -> &[Self::Item] {
+    self.0.as_slice()
+  }
+
+  fn as_mut_slice(&mut self) -> &mut [Self::Item] {
+    self.0.as_mut_slice()
+  }
+
+  fn grow(&mut self, cap: usize) -> mem::Result<Page<'_, Self::Item>> {
+    self.0.grow(cap)
+  }
+
+  fn shrink(&mut self, _: usize) -> mem::Result<()> {
+    Err(io::Error::other("crash").into())
+  }
+
+  fn sync(&mut self) -> mem::Result<()> {
+    self.0.sync()
+  }
+}
+
+#[test]
+fn test_crash_during_compaction() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let links = dir.path().join("db.links");
+  let journal = || {
+    Journal::from_path(dir.path().join("db.journal"), SyncPolicy::Always)
+      .unwrap()
+  };
+  let mem = || FileMapped::from_path(&links).unwrap();
+
+  fn fill(store: &mut impl Doublets<usize>) -> Result<(), usize> {
+    for _ in 0..5000 {
+      store.create_point()?;
+    }
+    for i in 0..500 {
+      store.create_link(i * 9 + 1, i * 7 + 2)?;
+    }
+    for link in (1..5000).filter(|link| link % 10 != 0) {
+      store.delete_link(link)?;
+    }
+    Ok(())
+  }
+  let mut heap = create_heap_store::<usize>()?;
+  fill(&mut heap)?;
+  heap.compact()?;
+
+  // SAFETY: file always represents initialized bytes
+  let mut store: Store<usize, NoShrink> =
+    unsafe { Store::open_with_journal(NoShrink(mem()), journal())? };
+  fill(&mut store)?;
+  store.checkpoint()?;
+  let snapshot = fs::read(&links).unwrap();
+  assert!(store.compact().is_err());
+  std::mem::forget(store);
+
+  // only the first page of moved links reached the disk
+  let mut moved = fs::read(&links).unwrap();
+  moved[4096..].copy_from_slice(&snapshot[4096..]);
+  fs::write(&links, moved).unwrap();
+
+  let store: FileStore = open_journaled(&links, journal())?;
+  assert_eq!(store.collect_all(), heap.collect_all());
+  assert!(store.verify().is_consistent());
+  Ok(())
+}
+
+#[test]
+fn test_crash_while_journaling_compaction() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let links = dir.path().join("db.links");
+  let journal_path = dir.path().join("db.journal");
+  let journal =
+    || Journal::from_path(&journal_path, SyncPolicy::Always).unwrap();
+  let mem = || FileMapped::from_path(&links).unwrap();
+
+  // SAFETY: file always represents initialized bytes
+  let mut store: Store<usize, NoShrink> =
+    unsafe { Store::open_with_journal(NoShrink(mem()), journal())? };
+  for _ in 0..5001 {
+    store.create_point()?;
+  }
+  store.create_link(4999, 5000)?;
+  for link in 1..4999 {
+    store.delete_link(link)?;
+  }
+  store.checkpoint()?;
+  let before = store.collect_all();
+  let snapshot = fs::read(&links).unwrap();
+  assert!(store.compact().is_err());
+  std::mem::forget(store);
+
+  // only the first record of the moves was written, the links never
+  // changed
+  let record = 1 + 5 * 8 + 4;
+  assert!(fs::metadata(&journal_path).unwrap().len() > record);
+  let file = fs::OpenOptions::new().write(true).open(&journal_path).unwrap();
+  file.set_len(record).unwrap();
+  fs::write(&links, snapshot).unwrap();
+
+  let store: FileStore = open_journaled(&links, journal())?;
+  assert_eq!(store.collect_all(), before);
+  assert!(store.verify().is_consistent());
+  Ok(())
+}
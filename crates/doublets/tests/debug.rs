@@ -1,5 +1,5 @@
 use {
-  doublets::{Doublets, Flow, Link, Links, Result, create_heap_store},
+  doublets::{Doublets, Flow, Index, Link, Links, Result, create_heap_store},
   std::collections::HashSet,
 };
 
@@ -41,13 +41,13 @@ fn debug_each_with_query() -> Result<(), usize> {
   });
 
   println!(
-    "\nSearching for all links with source = {} (query [0, {}, 0]):",
+    "\nSearching for all links with source = {} (query [ANY, {}, ANY]):",
     a, a
   );
   let mut found_links = Vec::new();
   let mut call_count = 0;
 
-  store.each([0, a, 0], &mut |link| {
+  store.each([usize::ANY, a, usize::ANY], &mut |link| {
     call_count += 1;
     println!("  Call #{}: Found: {:?}", call_count, link);
     found_links.push(link);
@@ -164,7 +164,7 @@ fn test_tree_traversal_with_delete() -> Result<(), usize> {
 
   // Query by source = a: should find a (point), link_ab, link_ac
   let mut found: HashSet<usize> = HashSet::new();
-  store.each([0, a, 0], &mut |link: Link<usize>| {
+  store.each([usize::ANY, a, usize::ANY], &mut |link: Link<usize>| {
     found.insert(link.index);
     Flow::Continue
   });
@@ -180,7 +180,7 @@ fn test_tree_traversal_with_delete() -> Result<(), usize> {
 
   // Query by source = a: should find a (point), link_ac only
   found.clear();
-  store.each([0, a, 0], &mut |link: Link<usize>| {
+  store.each([usize::ANY, a, usize::ANY], &mut |link: Link<usize>| {
     found.insert(link.index);
     Flow::Continue
   });
@@ -192,7 +192,7 @@ fn test_tree_traversal_with_delete() -> Result<(), usize> {
 
   // Query by target = a: should find a (point), link_ba, link_ca
   found.clear();
-  store.each([0, 0, a], &mut |link: Link<usize>| {
+  store.each([usize::ANY, usize::ANY, a], &mut |link: Link<usize>| {
     found.insert(link.index);
     Flow::Continue
   });
@@ -208,7 +208,7 @@ fn test_tree_traversal_with_delete() -> Result<(), usize> {
 
   // Query by target = a: should find a (point), link_ba only
   found.clear();
-  store.each([0, 0, a], &mut |link: Link<usize>| {
+  store.each([usize::ANY, usize::ANY, a], &mut |link: Link<usize>| {
     found.insert(link.index);
     Flow::Continue
   });
@@ -245,7 +245,9 @@ fn test_tree_traversal_stress() -> Result<(), usize> {
   // Verify querying by source works for first point
   let first_point = points[0];
   let mut found: HashSet<usize> = HashSet::new();
-  store.each([0, first_point, 0], &mut |link: Link<usize>| {
+  store.each([usize::ANY, first_point, usize::ANY], &mut |link: Link<
+    usize,
+  >| {
     found.insert(link.index);
     Flow::Continue
   });
@@ -263,7 +265,9 @@ fn test_tree_traversal_stress() -> Result<(), usize> {
 
   // Now should only find the point
   found.clear();
-  store.each([0, first_point, 0], &mut |link: Link<usize>| {
+  store.each([usize::ANY, first_point, usize::ANY], &mut |link: Link<
+    usize,
+  >| {
     found.insert(link.index);
     Flow::Continue
   });
@@ -291,7 +295,7 @@ fn test_tree_vs_linear_consistency() -> Result<(), usize> {
 
   // Collect results using current implementation (linear scan)
   let mut linear_results: HashSet<usize> = HashSet::new();
-  store.each([0, a, 0], &mut |link: Link<usize>| {
+  store.each([usize::ANY, a, usize::ANY], &mut |link: Link<usize>| {
     linear_results.insert(link.index);
     Flow::Continue
   });
@@ -308,7 +312,7 @@ fn test_tree_vs_linear_consistency() -> Result<(), usize> {
   store.delete_link(link1)?;
 
   let mut after_delete: HashSet<usize> = HashSet::new();
-  store.each([0, a, 0], &mut |link: Link<usize>| {
+  store.each([usize::ANY, a, usize::ANY], &mut |link: Link<usize>| {
     after_delete.insert(link.index);
     Flow::Continue
   });
@@ -327,7 +331,7 @@ fn test_tree_vs_linear_consistency() -> Result<(), usize> {
 
   // Query again - should reflect new state
   let mut final_results: HashSet<usize> = HashSet::new();
-  store.each([0, a, 0], &mut |link: Link<usize>| {
+  store.each([usize::ANY, a, usize::ANY], &mut |link: Link<usize>| {
     final_results.insert(link.index);
     Flow::Continue
   });
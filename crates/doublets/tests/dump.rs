@@ -0,0 +1,164 @@
+// Tests for binary snapshots of stores
+//
+// Snapshots hold only indices, sources and targets, so they must restore
+// the same links into stores of any index type and tree strategies, and
+// reject damaged or foreign data.
+
+use {
+  doublets::{
+    ArtStrategy, Doublets, Error, FormatMismatch, Link, Policy, RawLink,
+    Result, SbtStrategy, Store, create_heap_store,
+  },
+  mem::Alloc,
+};
+
+fn dump(store: &Store<u64>) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  store.export_to(&mut bytes).unwrap();
+  bytes
+}
+
+fn sample() -> Result<Store<u64>, u64> {
+  let mut store = create_heap_store::<u64>()?;
+  let points: Vec<_> =
+    (0..30).map(|_| store.create_point()).collect::<Result<_, _>>()?;
+  for i in 0..300 {
+    store.create_link(points[i % 30], points[i * 11 % 30])?;
+  }
+  for link in (40..330).step_by(7) {
+    store.delete_link(link)?;
+  }
+  Ok(store)
+}
+
+#[test]
+fn test_format() -> Result<(), u64> {
+  let mut store = create_heap_store::<u64>()?;
+  store.create_point()?;
+  store.create_link(1, 200)?;
+
+  let bytes = dump(&store);
+  let (body, checksum) = bytes.split_at(bytes.len() - 4);
+  assert_eq!(&body[..8], b"DBLTDUMP");
+  // version, count, then deltas, sources and targets, 200 takes two bytes
+  assert_eq!(&body[8..], [1, 0, 2, 1, 1, 1, 1, 1, 0xc8, 0x01]);
+  assert_eq!(checksum.len(), 4);
+  Ok(())
+}
+
+#[test]
+fn test_round_trip() -> Result<(), u64> {
+  let store = sample()?;
+  let bytes = dump(&store);
+
+  let mut restored = create_heap_store::<u64>()?;
+  let indices = restored.import_from(bytes.as_slice())?;
+  assert_eq!(indices.len(), store.count_all() as usize);
+  assert_eq!(restored.collect_all(), store.collect_all());
+  assert_eq!(restored.verify().violations, []);
+  assert_eq!(dump(&restored), bytes);
+  Ok(())
+}
+
+#[test]
+fn test_other_index_and_strategies() -> Result<(), u32> {
+  let store = sample().unwrap();
+  let bytes = dump(&store);
+
+  let mut narrow =
+    Store::<u32, Alloc<RawLink<u32>>, ArtStrategy, SbtStrategy>::new(
+      Alloc::new(),
+    )?;
+  narrow.import_from(bytes.as_slice())?;
+  let narrowed = |link: Link<u64>| {
+    Link::new(link.index as u32, link.source as u32, link.target as u32)
+  };
+  let expected: Vec<_> = store.iter().map(narrowed).collect();
+  assert_eq!(narrow.collect_all(), expected);
+  assert!(narrow.verify().is_consistent());
+  for &link in &expected[..20] {
+    assert!(narrow.search(link.source, link.target).is_some());
+  }
+  Ok(())
+}
+
+#[test]
+fn test_values_out_of_range() -> Result<(), u8> {
+  let mut store = create_heap_store::<u64>().unwrap();
+  let a = store.create_point().unwrap();
+  store.create_link(a, 1000).unwrap();
+
+  let mut narrow = create_heap_store::<u8>()?;
+  assert_eq!(narrow.import_from(dump(&store).as_slice()), Err(Error::Overflow));
+  assert_eq!(narrow.count_all(), 0);
+
+  // u8 stores reserve more leading slots for their header
+  let mut store = create_heap_store::<u64>().unwrap();
+  store.create_point().unwrap();
+  let result = narrow.import_from(dump(&store).as_slice());
+  assert_eq!(result, Err(Error::Reserved(1)));
+  Ok(())
+}
+
+#[test]
+fn test_any_is_out_of_range() -> Result<(), u8> {
+  // 255 fits into u8 but is its wildcard, which is never stored
+  let mut store = create_heap_store::<u64>().unwrap();
+  let a = store.create_point().unwrap();
+  store.create_link(a, u8::MAX.into()).unwrap();
+
+  let mut narrow = create_heap_store::<u8>()?;
+  assert_eq!(narrow.import_from(dump(&store).as_slice()), Err(Error::Overflow));
+  assert_eq!(narrow.count_all(), 0);
+  Ok(())
+}
+
+#[test]
+fn test_damaged() -> Result<(), u64> {
+  let bytes = dump(&sample()?);
+  let mut store = create_heap_store::<u64>()?;
+
+  let mut flipped = bytes.clone();
+  flipped[40] ^= 0x01;
+  assert_eq!(
+    store.import_from(flipped.as_slice()),
+    Err(Error::IncompatibleFormat(FormatMismatch::Checksum))
+  );
+  assert_eq!(
+    store.import_from(&bytes[..bytes.len() - 1]),
+    Err(Error::Io(std::io::ErrorKind::UnexpectedEof))
+  );
+  assert_eq!(
+    store.import_from(&b"DOUBLETS"[..]),
+    Err(Error::IncompatibleFormat(FormatMismatch::Magic))
+  );
+  let mut newer = bytes.clone();
+  newer[8] = 9;
+  assert_eq!(
+    store.import_from(newer.as_slice()),
+    Err(Error::IncompatibleFormat(FormatMismatch::Version {
+      found: 9,
+      expected: 1
+    }))
+  );
+  assert_eq!(store.count_all(), 0);
+  Ok(())
+}
+
+#[test]
+fn test_import_into_taken() -> Result<(), u64> {
+  let bytes = dump(&sample()?);
+  let mut store = create_heap_store::<u64>()?;
+  store.create_point()?;
+  assert_eq!(
+    store.import_from(bytes.as_slice()),
+    Err(Error::AlreadyExists(1, 1, 1))
+  );
+
+  let policy = Policy { unique: true, ..Policy::default() };
+  let mut store = create_heap_store::<u64>()?.with_policy(policy);
+  let result = store.import_from(bytes.as_slice());
+  assert!(matches!(result, Err(Error::AlreadyExists(..))));
+  assert_eq!(store.count_all(), 0);
+  Ok(())
+}
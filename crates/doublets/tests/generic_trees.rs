@@ -2,10 +2,10 @@
 // which allows for different tree implementations (SBT by default)
 //
 // The Tree trait provides default implementations that use SizeBalanced (SBT),
-// but implementations can override insert/remove to use other tree strategies
-// like AdaptiveRadix (ART) by implementing the AdaptiveRadix trait.
+// other indexes like the AdaptiveRadix (ART) trie are plugged in through
+// the TreeStrategy trait.
 
-use doublets::{Doublets, Flow, Link, Links, Result, create_heap_store};
+use doublets::{Doublets, Flow, Index, Link, Links, Result, create_heap_store};
 
 /// Test that the default tree backend (SBT) works correctly with all operations
 #[test]
@@ -70,7 +70,7 @@ fn test_tree_backed_query_by_source() -> Result<(), usize> {
   let _d = store.create_link(a, a)?;
 
   let mut found_links = Vec::new();
-  store.each([0, a, 0], &mut |link| {
+  store.each([usize::ANY, a, usize::ANY], &mut |link| {
     found_links.push(link);
     Flow::Continue
   });
@@ -90,7 +90,7 @@ fn test_tree_backed_query_by_target() -> Result<(), usize> {
   let _d = store.create_link(b, b)?;
 
   let mut found_links = Vec::new();
-  store.each([0, 0, b], &mut |link| {
+  store.each([usize::ANY, usize::ANY, b], &mut |link| {
     found_links.push(link);
     Flow::Continue
   });
@@ -179,8 +179,8 @@ fn test_tree_backend_scalability() -> Result<(), usize> {
 /// 3. The generic interface allows for different tree backends
 ///
 /// To use a different tree backend (e.g., ART), one would:
-/// 1. Implement AdaptiveRadix trait for SourceTree/TargetTree
-/// 2. Override Tree::insert and Tree::remove to call ART methods
+/// 1. Implement TreeStrategy with the backend as its storage
+/// 2. Choose the strategy in the Store type parameters
 /// 3. The rest of the doublets store code remains unchanged
 #[test]
 fn test_generic_tree_trait_usage() -> Result<(), usize> {
@@ -0,0 +1,132 @@
+use doublets::{Doublets, Index, Join, Store, Term, create_heap_store};
+
+/// Points 1 to 8 and links between them picked by a fixed formula
+fn store() -> Store<usize> {
+  let mut store = create_heap_store::<usize>().unwrap();
+  for _ in 1..=8 {
+    store.create_point().unwrap();
+  }
+  for i in 0..40 {
+    store.create_link(i * 5 % 8 + 1, i * 3 % 7 + 1).unwrap();
+  }
+  store
+}
+
+#[test]
+fn test_single_pattern() {
+  let store = store();
+  let mut join = Join::new();
+  let (x, y) = (join.var(), join.var());
+  join.pattern(x, 1, y);
+
+  let mut found: Vec<_> =
+    join.run(&store).map(|binding| (binding[x], binding[y])).collect();
+  found.sort_unstable();
+  let expected: Vec<_> = store
+    .iter()
+    .filter(|link| link.source == 1)
+    .map(|link| (link.index, link.target))
+    .collect();
+  assert_eq!(found, expected);
+}
+
+#[test]
+fn test_paths_match_nested_loops() {
+  let store = store();
+  let links = store.collect_all();
+  let mut join = Join::new();
+  let (first, second) = (join.var(), join.var());
+  let (start, middle, end) = (join.var(), join.var(), join.var());
+  join.pattern(first, start, middle).pattern(second, middle, end);
+
+  let mut found: Vec<_> =
+    join.run(&store).map(|binding| binding.values().to_vec()).collect();
+  found.sort_unstable();
+
+  let mut expected = Vec::new();
+  for a in &links {
+    for b in links.iter().filter(|b| b.source == a.target) {
+      expected.push(vec![a.index, b.index, a.source, a.target, b.target]);
+    }
+  }
+  expected.sort_unstable();
+  assert!(!expected.is_empty());
+  assert_eq!(found, expected);
+}
+
+#[test]
+fn test_pattern_order_does_not_matter() {
+  let store = store();
+  let run = |patterns: &[[Term<usize>; 3]], vars: usize| {
+    let mut join = Join::new();
+    for _ in 0..vars {
+      join.var();
+    }
+    for &[index, source, target] in patterns {
+      join.pattern(index, source, target);
+    }
+    let mut found: Vec<_> =
+      join.run(&store).map(|binding| binding.values().to_vec()).collect();
+    found.sort_unstable();
+    found
+  };
+
+  let mut join = Join::<usize>::new();
+  let (x, y) = (join.var(), join.var());
+  let patterns = [
+    [Term::Var(x), Term::Value(3), Term::Var(y)],
+    [Term::Any, Term::Var(y), Term::Value(2)],
+    [Term::Var(y), Term::Any, Term::Any],
+  ];
+  let reversed = [patterns[2], patterns[1], patterns[0]];
+  assert_eq!(run(&patterns, 2), run(&reversed, 2));
+  assert!(!run(&patterns, 2).is_empty());
+}
+
+#[test]
+fn test_repeated_variable() {
+  let mut store = store();
+  let loop_ = store.create_link(4, 4).unwrap();
+  let mut join = Join::new();
+  let (x, y) = (join.var(), join.var());
+  join.pattern(x, y, y);
+
+  let mut found: Vec<_> = join
+    .run(&store)
+    .map(|binding| (binding[x], binding[y]))
+    .filter(|&(x, _)| x > 8)
+    .collect();
+  let expected: Vec<_> = store
+    .iter()
+    .filter(|link| link.index > 8 && link.source == link.target)
+    .map(|link| (link.index, link.source))
+    .collect();
+  found.sort_unstable();
+  assert!(expected.contains(&(loop_, 4)));
+  assert_eq!(found, expected);
+
+  let mut points = Join::new();
+  let x = points.var();
+  points.pattern(x, x, x);
+  assert_eq!(points.run(&store).count(), 8);
+}
+
+#[test]
+fn test_edge_cases() {
+  let store = store();
+
+  let mut empty = Join::<usize>::new();
+  let unused = empty.var();
+  let bindings: Vec<_> = empty.run(&store).collect();
+  assert_eq!(bindings.len(), 1);
+  assert_eq!(bindings[0][unused], usize::ANY);
+
+  let mut missing = Join::new();
+  let x = missing.var();
+  missing.pattern(x, 1, 2).pattern(x, 3, Term::Any);
+  assert_eq!(missing.run(&store).count(), 0);
+
+  let mut fixed = Join::new();
+  fixed.pattern(1, 1, 1);
+  assert_eq!(fixed.run(&store).count(), 1);
+}
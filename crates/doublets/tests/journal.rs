@@ -0,0 +1,215 @@
+// Tests for the write-ahead journal of file-backed stores
+//
+// A crash is simulated by forgetting the store, so no checkpoint is made,
+// and by restoring an older copy of the links file, as if the latest
+// changes never reached the disk. Reopening with the journal must replay
+// them and rebuild consistent trees.
+
+mod common;
+
+use {
+  common::{FileStore, open_journaled},
+  doublets::{
+    ArtStrategy, Doublets, Index, Journal, Link, Links, Result, SbtStrategy,
+    SyncPolicy, TreeStrategy,
+  },
+  std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+  },
+};
+
+struct Paths {
+  _dir: tempfile::TempDir,
+  links: PathBuf,
+  journal: PathBuf,
+}
+
+fn paths() -> Paths {
+  let dir = tempfile::tempdir().unwrap();
+  let links = dir.path().join("db.links");
+  let journal = dir.path().join("db.journal");
+  Paths { _dir: dir, links, journal }
+}
+
+fn open<S, R>(paths: &Paths) -> Result<FileStore<usize, S, R>, usize>
+where
+  S: TreeStrategy<usize>,
+  R: TreeStrategy<usize>,
+{
+  let journal = Journal::from_path(&paths.journal, SyncPolicy::Always)
+    .expect("journal should be opened");
+  open_journaled(&paths.links, journal)
+}
+
+fn journal_len(path: &Path) -> u64 {
+  fs::metadata(path).unwrap().len()
+}
+
+fn test_replays_lost_changes<S, R>() -> Result<(), usize>
+where
+  S: TreeStrategy<usize>,
+  R: TreeStrategy<usize>,
+{
+  let paths = paths();
+  {
+    let mut store = open::<S, R>(&paths)?;
+    for _ in 0..4 {
+      store.create_point()?;
+    }
+  }
+  // dropping the store makes a checkpoint
+  assert_eq!(journal_len(&paths.journal), 0);
+  let snapshot = fs::read(&paths.links).unwrap();
+
+  let mut store = open::<S, R>(&paths)?;
+  let link = store.create_link(1, 2)?;
+  store.update_link(3, 1, 4)?;
+  store.delete_link(2)?;
+  // reuses the slot of the deleted link
+  assert_eq!(store.create_link(4, 4)?, 2);
+  std::mem::forget(store);
+  assert!(journal_len(&paths.journal) > 0);
+
+  // none of the changes reached the links file
+  fs::write(&paths.links, snapshot).unwrap();
+
+  let store = open::<S, R>(&paths)?;
+  assert_eq!(journal_len(&paths.journal), 0);
+  assert_eq!(store.count_all(), 5);
+  assert_eq!(store.get(link), Some(Link::new(link, 1, 2)));
+  assert_eq!(store.get(3), Some(Link::new(3, 1, 4)));
+  assert_eq!(store.get(2), Some(Link::new(2, 4, 4)));
+  assert_eq!(store.search(1, 2), Some(link));
+  assert_eq!(store.count([usize::ANY, 1, usize::ANY]), 3);
+  assert_eq!(store.count([usize::ANY, usize::ANY, 4]), 3);
+  assert_eq!(store.query([usize::ANY, 4, usize::ANY]).count(), 2);
+  Ok(())
+}
+
+#[test]
+fn test_replays_lost_changes_sbt() -> Result<(), usize> {
+  test_replays_lost_changes::<SbtStrategy, SbtStrategy>()
+}
+
+#[test]
+fn test_replays_lost_changes_art() -> Result<(), usize> {
+  test_replays_lost_changes::<ArtStrategy, SbtStrategy>()
+}
+
+#[test]
+fn test_discards_torn_record() -> Result<(), usize> {
+  let paths = paths();
+  let mut store = open::<SbtStrategy, SbtStrategy>(&paths)?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  std::mem::forget(store);
+
+  // half of a record that was being written during the crash
+  let len = journal_len(&paths.journal);
+  let mut journal =
+    OpenOptions::new().append(true).open(&paths.journal).unwrap();
+  journal.write_all(&[2; 20]).unwrap();
+
+  let mut store = open::<SbtStrategy, SbtStrategy>(&paths)?;
+  assert!(len > 0);
+  assert_eq!(store.count_all(), 2);
+  assert_eq!(store.create_link(a, b)?, 3);
+  Ok(())
+}
+
+#[test]
+fn test_discards_torn_batch() -> Result<(), usize> {
+  let paths = paths();
+  let (a, b) = {
+    let mut store = open::<SbtStrategy, SbtStrategy>(&paths)?;
+    (store.create_point()?, store.create_point()?)
+  };
+  let snapshot = fs::read(&paths.links).unwrap();
+
+  let mut store = open::<SbtStrategy, SbtStrategy>(&paths)?;
+  store.bulk_load([(a, b), (b, a), (a, a), (b, b), (a, b)])?;
+  std::mem::forget(store);
+
+  // the crash hit before the last record of the batch was written
+  let len = journal_len(&paths.journal);
+  let record = 1 + 5 * 8 + 4;
+  assert_eq!(len, 5 * record);
+  let journal = OpenOptions::new().write(true).open(&paths.journal).unwrap();
+  journal.set_len(len - record).unwrap();
+  fs::write(&paths.links, snapshot).unwrap();
+
+  let mut store = open::<SbtStrategy, SbtStrategy>(&paths)?;
+  assert_eq!(journal_len(&paths.journal), 0);
+  assert_eq!(store.count_all(), 2);
+  assert_eq!(store.create_link(a, b)?, 3);
+  Ok(())
+}
+
+#[test]
+fn test_reverted_transaction_is_journaled() -> Result<(), usize> {
+  let paths = paths();
+  let mut store = open::<SbtStrategy, SbtStrategy>(&paths)?;
+  let a = store.create_point()?;
+  let result = store.transaction(|tx| {
+    let b = tx.create_point()?;
+    tx.create_link(a, b)?;
+    tx.delete_link(a)
+  });
+  assert!(result.is_ok());
+  let result = store.transaction(|tx| {
+    tx.create_point()?;
+    tx.delete_link(100)
+  });
+  assert!(result.is_err());
+  std::mem::forget(store);
+
+  let store = open::<SbtStrategy, SbtStrategy>(&paths)?;
+  assert_eq!(store.count_all(), 2);
+  assert_eq!(store.get(a), None);
+  assert_eq!(store.search(a, 2), Some(3));
+  Ok(())
+}
+
+#[test]
+fn test_checkpoint_when_full() -> Result<(), usize> {
+  let paths = paths();
+  let open = || {
+    let journal = Journal::from_path(&paths.journal, SyncPolicy::Always)
+      .unwrap()
+      .with_limit(3);
+    open_journaled::<usize, SbtStrategy, SbtStrategy>(&paths.links, journal)
+  };
+
+  let mut store = open()?;
+  let mut lens = Vec::new();
+  for _ in 0..10 {
+    store.create_point()?;
+    lens.push(journal_len(&paths.journal));
+  }
+  std::mem::forget(store);
+  // a record is an opcode, five 64-bit values and a checksum
+  let record = 1 + 5 * 8 + 4;
+  assert!(lens.iter().all(|&len| len <= 3 * record));
+  assert!(lens.windows(2).any(|pair| pair[1] < pair[0]));
+
+  let store = open()?;
+  assert_eq!(store.count_all(), 10);
+  Ok(())
+}
+
+#[test]
+fn test_reject_wide_indices() {
+  let dir = tempfile::tempdir().unwrap();
+  let journal =
+    Journal::from_path(dir.path().join("db.journal"), SyncPolicy::Never)
+      .unwrap();
+  let mut store = doublets::create_heap_store::<u128>().unwrap();
+  assert_eq!(
+    store.attach_journal(journal),
+    Err(doublets::Error::IncompatibleFormat(
+      doublets::FormatMismatch::IndexWidth { found: 16, expected: 8 }
+    ))
+  );
+}
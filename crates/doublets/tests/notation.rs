@@ -0,0 +1,148 @@
+// Tests for importing and exporting links notation
+//
+// Exported stores must come back exactly when their indices are preserved,
+// and invalid text must be rejected with the position of the problem.
+
+use doublets::{
+  Doublets, Error, Index, Link, Links, Numbering, ParseError, Result, Store,
+  Syntax, create_heap_store,
+};
+
+const ANY: usize = usize::ANY;
+
+fn export(store: &Store<usize>) -> String {
+  let mut dump = Vec::new();
+  store.export_notation(&mut dump).unwrap();
+  String::from_utf8(dump).unwrap()
+}
+
+fn syntax_error(line: usize, column: usize, syntax: Syntax) -> Error<usize> {
+  Error::Parse(ParseError { line, column, syntax })
+}
+
+#[test]
+fn test_display() {
+  assert_eq!(Link::new(3, 1, 2).to_string(), "(3: 1 2)");
+}
+
+#[test]
+fn test_round_trip() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let points: Vec<_> =
+    (0..20).map(|_| store.create_point()).collect::<Result<_, _>>()?;
+  for i in 0..60 {
+    store.create_link(points[i % 20], points[i * 3 % 20])?;
+  }
+  for link in [3, 17, 40, 41, 79] {
+    store.delete_link(link)?;
+  }
+  let dump = export(&store);
+  assert_eq!(dump.lines().count(), 75);
+
+  let mut restored = create_heap_store::<usize>()?;
+  let indices =
+    restored.import_notation(dump.as_bytes(), Numbering::Preserve)?;
+  assert_eq!(indices.len(), 75);
+  assert_eq!(restored.collect_all(), store.collect_all());
+  // dangling references to the deleted points are kept
+  assert_eq!(restored.verify(), store.verify());
+  assert_eq!(export(&restored), dump);
+
+  // free slots are restored as well, the order of reuse may differ
+  let mut reused: Vec<_> =
+    (0..5).map(|_| store.create_point()).collect::<Result<_, _>>()?;
+  let mut restored_reused: Vec<_> =
+    (0..5).map(|_| restored.create_point()).collect::<Result<_, _>>()?;
+  reused.sort_unstable();
+  restored_reused.sort_unstable();
+  assert_eq!(restored_reused, reused);
+  assert_eq!(restored.create_point()?, store.create_point()?);
+  Ok(())
+}
+
+#[test]
+fn test_renumber() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+
+  // forward references, a reference to an existing link and a link
+  // without an index
+  let text = "(10: 11 1)\n  (11: 10 10)\t(1 11)";
+  let indices = store.import_notation(text.as_bytes(), Numbering::Renumber)?;
+  assert_eq!(indices, [2, 3, 4]);
+  assert_eq!(
+    store.collect_all(),
+    [
+      Link::new(a, a, a),
+      Link::new(2, 3, a),
+      Link::new(3, 2, 2),
+      Link::new(4, a, 3),
+    ]
+  );
+  assert_eq!(store.count([ANY, 2, ANY]), 1);
+  Ok(())
+}
+
+#[test]
+fn test_preserve_taken_index() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+  let text = format!("(5: 1 1) ({a}: 2 2)");
+  assert_eq!(
+    store.import_notation(text.as_bytes(), Numbering::Preserve),
+    Err(Error::AlreadyExists(a, a, a))
+  );
+  assert_eq!(store.count_all(), 1);
+  assert_eq!(
+    store.import_notation(&b"(7: 1 1)\n(1 2)"[..], Numbering::Preserve),
+    Err(syntax_error(2, 1, Syntax::Unnumbered))
+  );
+  Ok(())
+}
+
+#[test]
+fn test_parse_errors() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let mut import = |text: &str| {
+    store.import_notation(text.as_bytes(), Numbering::Renumber).map(|_| ())
+  };
+
+  assert_eq!(
+    import("(1: 2 3)\n(4: 5 x)"),
+    Err(syntax_error(2, 7, Syntax::Expected("a number")))
+  );
+  assert_eq!(
+    import("(1: 2 3"),
+    Err(syntax_error(1, 8, Syntax::Expected("`)`")))
+  );
+  assert_eq!(import(" 1 2"), Err(syntax_error(1, 2, Syntax::Expected("`(`"))));
+  assert_eq!(import("( 0: 1 1)"), Err(syntax_error(1, 3, Syntax::Reserved)));
+  assert_eq!(
+    import("(9: 1 1)\n\n (9: 2 2)"),
+    Err(syntax_error(3, 2, Syntax::Redefined))
+  );
+  assert_eq!(
+    import("(1 99999999999999999999999)"),
+    Err(syntax_error(1, 4, Syntax::OutOfRange))
+  );
+  assert_eq!(store.count_all(), 0);
+  Ok(())
+}
+
+#[test]
+fn test_out_of_range_for_index_type() -> Result<(), u8> {
+  let mut store = create_heap_store::<u8>()?;
+  let result = store.import_notation(&b"(3: 300 1)"[..], Numbering::Preserve);
+  assert_eq!(
+    result,
+    Err(Error::Parse(ParseError {
+      line: 1,
+      column: 5,
+      syntax: Syntax::OutOfRange
+    }))
+  );
+  // `u8::MAX` is the wildcard, not a link
+  let result = store.import_notation(&b"(3 255)"[..], Numbering::Renumber);
+  assert!(matches!(result, Err(Error::Parse(_))));
+  Ok(())
+}
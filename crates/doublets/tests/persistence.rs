@@ -0,0 +1,241 @@
+// Tests for reopening stores backed by file mappings
+//
+// The store keeps its format, counters and tree roots in the reserved
+// slot 0, so everything written through one `FileMapped` must be visible
+// after reopening the same file, and files of other formats are rejected.
+
+mod common;
+
+use {
+  common::{FileStore, open, open_as},
+  doublets::{
+    ArtStrategy, Doublets, Error, FormatMismatch, Index, Link, Links, Result,
+    SbtStrategy, Store,
+  },
+  mem::FileMapped,
+  std::{
+    fs::OpenOptions,
+    io::{Seek, SeekFrom, Write},
+    path::Path,
+  },
+};
+
+#[test]
+fn test_open_empty_file() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let mut store = open(&dir.path().join("db.links"))?;
+
+  assert_eq!(store.count_all(), 0);
+  assert_eq!(store.create_point()?, 1);
+  Ok(())
+}
+
+#[test]
+fn test_reopen_keeps_links() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+
+  let (a, b, c) = {
+    let mut store = open(&path)?;
+    let a = store.create_point()?;
+    let b = store.create_point()?;
+    let c = store.create_link(a, b)?;
+    (a, b, c)
+  };
+
+  let mut store = open(&path)?;
+  assert_eq!(store.count_all(), 3);
+  assert_eq!(store.get(c), Some(Link::new(c, a, b)));
+  assert_eq!(store.search(a, b), Some(c));
+  assert_eq!(store.count([usize::ANY, a, usize::ANY]), 2);
+
+  // new links continue after the stored ones
+  let d = store.create_link(b, a)?;
+  assert_eq!(d, 4);
+  assert_eq!(store.search(b, a), Some(d));
+  Ok(())
+}
+
+#[test]
+fn test_reopen_keeps_free_list() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+
+  {
+    let mut store = open(&path)?;
+    for _ in 0..5 {
+      store.create_point()?;
+    }
+    store.delete_link(2)?;
+    store.delete_link(4)?;
+  }
+
+  let mut store = open(&path)?;
+  assert_eq!(store.count_all(), 3);
+  assert!(store.get(2).is_none());
+  assert_eq!(store.create_point()?, 4);
+  assert_eq!(store.create_point()?, 2);
+  assert_eq!(store.create_point()?, 6);
+  Ok(())
+}
+
+#[test]
+fn test_reopen_grown_store() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+
+  {
+    let mut store = open(&path)?;
+    let root = store.create_point()?;
+    for _ in 0..3000 {
+      let point = store.create_point()?;
+      store.create_link(root, point)?;
+    }
+  }
+
+  let store = open(&path)?;
+  assert_eq!(store.count_all(), 6001);
+  assert_eq!(store.count([usize::ANY, 1, usize::ANY]), 3001);
+  assert_eq!(store.search(1, 6000), Some(6001));
+  Ok(())
+}
+
+#[test]
+fn test_reopen_rebuilds_art_index() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+
+  {
+    let mut store = open_as::<usize, ArtStrategy, ArtStrategy>(&path)?;
+    let root = store.create_point()?;
+    for _ in 0..100 {
+      let point = store.create_point()?;
+      store.create_link(root, point)?;
+    }
+    store.delete_link(3)?;
+  }
+
+  let mut store = open_as::<usize, ArtStrategy, ArtStrategy>(&path)?;
+  assert_eq!(store.count([usize::ANY, 1, usize::ANY]), 100);
+  assert_eq!(store.count([usize::ANY, usize::ANY, 2]), 1);
+  assert_eq!(store.search(1, 200), Some(201));
+  assert_eq!(store.search(1, 2), None);
+
+  let c = store.create_link(1, 2)?;
+  assert_eq!(store.search(1, 2), Some(c));
+  Ok(())
+}
+
+#[test]
+fn test_create_discards_file() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+
+  {
+    let mut store = open(&path)?;
+    store.create_point()?;
+    store.create_point()?;
+  }
+
+  let mem = FileMapped::from_path(&path).expect("file should be mapped");
+  let store: FileStore = Store::create(mem)?;
+  assert_eq!(store.count_all(), 0);
+  drop(store);
+
+  assert_eq!(open(&path)?.count_all(), 0);
+  Ok(())
+}
+
+fn overwrite(path: &Path, offset: u64, bytes: &[u8]) {
+  let mut file = OpenOptions::new().write(true).open(path).unwrap();
+  file.seek(SeekFrom::Start(offset)).unwrap();
+  file.write_all(bytes).unwrap();
+}
+
+fn stored_file() -> (tempfile::TempDir, std::path::PathBuf) {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+  open(&path).unwrap().create_point().unwrap();
+  (dir, path)
+}
+
+#[test]
+fn test_reject_other_strategy() {
+  let (_dir, path) = stored_file();
+
+  let result = open_as::<usize, ArtStrategy, SbtStrategy>(&path);
+  assert_eq!(
+    result.err(),
+    Some(Error::IncompatibleFormat(FormatMismatch::SourceStrategy {
+      found: 1,
+      expected: 2,
+    }))
+  );
+
+  let result = open_as::<usize, SbtStrategy, ArtStrategy>(&path);
+  assert!(matches!(
+    result,
+    Err(Error::IncompatibleFormat(FormatMismatch::TargetStrategy { .. }))
+  ));
+}
+
+#[test]
+fn test_reject_other_index_width() {
+  let (_dir, path) = stored_file();
+
+  let result = open_as::<u32, SbtStrategy, SbtStrategy>(&path);
+  assert_eq!(
+    result.err(),
+    Some(Error::IncompatibleFormat(FormatMismatch::IndexWidth {
+      found: 8,
+      expected: 4,
+    }))
+  );
+}
+
+#[test]
+fn test_reject_other_version() {
+  let (_dir, path) = stored_file();
+
+  let mut header = std::fs::read(&path).unwrap()[..56].to_vec();
+  header[4] = 3;
+  header[12..16].fill(0);
+  // FNV-1a over the header with zeroed checksum field
+  let checksum = header.iter().fold(0x811c_9dc5u32, |hash, &byte| {
+    (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+  });
+  header[12..16].copy_from_slice(&checksum.to_le_bytes());
+  overwrite(&path, 0, &header);
+
+  assert_eq!(
+    open(&path).err(),
+    Some(Error::IncompatibleFormat(FormatMismatch::Version {
+      found: 3,
+      expected: 2,
+    }))
+  );
+}
+
+#[test]
+fn test_reject_corrupted_header() {
+  let (_dir, path) = stored_file();
+
+  // allocated counter
+  overwrite(&path, 16, &[0xFF]);
+  assert_eq!(
+    open(&path).err(),
+    Some(Error::IncompatibleFormat(FormatMismatch::Checksum))
+  );
+}
+
+#[test]
+fn test_reject_foreign_file() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("foreign.bin");
+  std::fs::write(&path, b"definitely not a doublets store").unwrap();
+
+  assert_eq!(
+    open(&path).err(),
+    Some(Error::IncompatibleFormat(FormatMismatch::Magic))
+  );
+}
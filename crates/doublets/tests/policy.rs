@@ -0,0 +1,143 @@
+// Tests for store policies
+//
+// By default the store allows duplicates and dangling references, the
+// policies turn them into `AlreadyExists` and `HasUsages` errors or clean
+// up the usages of deleted links.
+
+use doublets::{
+  Doublets, Error, Flow, Index, Link, Links, OnDelete, Policy, Result, Store,
+  create_heap_store,
+};
+
+fn store_with(policy: Policy) -> Result<Store<usize>, usize> {
+  Ok(create_heap_store::<usize>()?.with_policy(policy))
+}
+
+#[test]
+fn test_default_policy_allows_everything() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  assert_eq!(store.policy(), Policy::default());
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  store.create_link(a, b)?;
+  store.create_link(a, b)?;
+  store.delete_link(a)?;
+  assert_eq!(store.count([usize::ANY, a, b]), 2);
+  Ok(())
+}
+
+#[test]
+fn test_unique() -> Result<(), usize> {
+  let mut store = store_with(Policy { unique: true, ..Policy::default() })?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let ab = store.create_link(a, b)?;
+  let ba = store.create_link(b, a)?;
+
+  assert_eq!(store.create_link(a, b), Err(Error::AlreadyExists(ab, a, b)));
+  assert_eq!(store.update_link(ba, a, b), Err(Error::AlreadyExists(ab, a, b)));
+  assert_eq!(store.get(ba), Some(Link::new(ba, b, a)));
+  // a point is its own doublet
+  assert_eq!(store.create_link(a, a), Err(Error::AlreadyExists(a, a, a)));
+
+  // updating a link into its own state is fine
+  assert_eq!(store.update_link(ab, a, b)?, ab);
+  assert_eq!(store.get_or_create(a, b)?, ab);
+  assert_eq!(store.count_all(), 4);
+  Ok(())
+}
+
+#[test]
+fn test_forbid() -> Result<(), usize> {
+  let mut store =
+    store_with(Policy { on_delete: OnDelete::Forbid, ..Policy::default() })?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let ab = store.create_link(a, b)?;
+
+  assert_eq!(store.delete_link(a), Err(Error::HasUsages(a)));
+  assert_eq!(store.delete_link(b), Err(Error::HasUsages(b)));
+  store.delete_link(ab)?;
+  // references to itself are not usages
+  store.delete_link(a)?;
+  assert_eq!(store.count_all(), 1);
+  Ok(())
+}
+
+#[test]
+fn test_cascade() -> Result<(), usize> {
+  let mut store =
+    store_with(Policy { on_delete: OnDelete::Cascade, ..Policy::default() })?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let ab = store.create_link(a, b)?;
+  let nested = store.create_link(ab, b)?;
+  let ba = store.create_link(b, a)?;
+  // cycle through usages
+  let cycle = store.create_link(0, 0)?;
+  store.update_link(cycle, ba, cycle)?;
+  store.update_link(ba, b, cycle)?;
+
+  let mut deleted = Vec::new();
+  store.delete([a], &mut |before: Link<usize>, _| {
+    deleted.push(before.index);
+    Flow::Continue
+  })?;
+  assert_eq!(deleted, [nested, ab, a]);
+  assert_eq!(store.count_all(), 3);
+
+  store.delete_link(b)?;
+  assert_eq!(store.count_all(), 0);
+  Ok(())
+}
+
+#[test]
+fn test_nullify() -> Result<(), usize> {
+  let mut store =
+    store_with(Policy { on_delete: OnDelete::Nullify, ..Policy::default() })?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let ab = store.create_link(a, b)?;
+  let aa = store.create_link(a, a)?;
+
+  store.delete_link(a)?;
+  assert_eq!(store.get(ab), Some(Link::new(ab, 0, b)));
+  assert_eq!(store.get(aa), Some(Link::new(aa, 0, 0)));
+  assert_eq!(store.count([usize::ANY, a, usize::ANY]), 0);
+  Ok(())
+}
+
+#[test]
+fn test_nullify_into_null_links() -> Result<(), usize> {
+  let policy = Policy { unique: true, on_delete: OnDelete::Nullify };
+  let mut store = store_with(policy)?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let ab = store.create_link(a, b)?;
+  let ba = store.create_link(b, a)?;
+
+  // null links are no duplicates, or deleting `b` would be refused
+  store.delete_link(a)?;
+  store.delete_link(b)?;
+  assert_eq!(store.get(ab), Some(Link::new(ab, 0, 0)));
+  assert_eq!(store.get(ba), Some(Link::new(ba, 0, 0)));
+  Ok(())
+}
+
+#[test]
+fn test_nullify_is_atomic() -> Result<(), usize> {
+  let policy = Policy { unique: true, on_delete: OnDelete::Nullify };
+  let mut store = store_with(policy)?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let c = store.create_point()?;
+  store.create_link(a, c)?;
+  let bc = store.create_link(b, c)?;
+  store.update_link(bc, 0, c)?;
+  let before = store.collect_all();
+
+  // nullifying (a, c) would duplicate (0, c)
+  assert_eq!(store.delete_link(a), Err(Error::AlreadyExists(bc, 0, c)));
+  assert_eq!(store.collect_all(), before);
+  Ok(())
+}
@@ -97,10 +97,10 @@ proptest! {
           if !created_links.is_empty() {
             let source = created_links[source_idx % created_links.len()];
             let target = created_links[target_idx % created_links.len()];
-            if let Ok(idx) = store.get_or_create(source, target) {
-              if !created_links.contains(&idx) {
-                created_links.push(idx);
-              }
+            if let Ok(idx) = store.get_or_create(source, target)
+              && !created_links.contains(&idx)
+            {
+              created_links.push(idx);
             }
           }
         }
@@ -0,0 +1,87 @@
+// Tests for the lazy query iterator
+//
+// Queries borrow the store and walk its trees on demand, so adapters like
+// `take`, `zip` and `find` stop early instead of collecting every link.
+
+use doublets::{Doublets, Flow, Index, Link, Links, Result, create_heap_store};
+
+#[test]
+fn test_query_adapters() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let hub = store.create_point()?;
+  let points: Vec<_> =
+    (0..10).map(|_| store.create_point()).collect::<Result<_, _>>()?;
+  for &point in &points {
+    store.create_link(hub, point)?;
+  }
+
+  let first: Vec<_> = store
+    .query([usize::ANY, hub, usize::ANY])
+    .take(3)
+    .map(|link| link.target)
+    .collect();
+  assert_eq!(first, [hub, points[0], points[1]]);
+
+  let odd = store
+    .query([usize::ANY, hub, usize::ANY])
+    .filter(|link| link.target % 2 == 1);
+  assert!(odd.map(|link| link.target).eq(
+    std::iter::once(hub).chain(points.iter().copied()).filter(|t| t % 2 == 1)
+  ));
+
+  for (link, &point) in
+    store.query([usize::ANY, hub, usize::ANY]).skip(1).zip(&points)
+  {
+    assert_eq!(link, Link::new(link.index, hub, point));
+  }
+
+  assert_eq!(store.iter().count(), store.count_all());
+  assert_eq!(store.collect_all(), store.iter().collect::<Vec<_>>());
+  Ok(())
+}
+
+#[test]
+fn test_query_early_exit() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let hub = store.create_point()?;
+  for _ in 0..100_000 {
+    let point = store.create_point()?;
+    store.create_link(hub, point)?;
+  }
+
+  let mut query = store.query([usize::ANY, hub, usize::ANY]);
+  assert_eq!(query.next().map(|link| link.index), Some(hub));
+  let link = query.find(|link| link.target == 5000).unwrap();
+  assert_eq!(link.source, hub);
+
+  assert_eq!(store.iter().nth(200_000).map(|link| link.index), Some(200_001));
+  assert_eq!(store.query([usize::ANY, usize::ANY, 5000]).count(), 2);
+  Ok(())
+}
+
+#[test]
+fn test_high_fan_out_small_stack() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let hub = store.create_point()?;
+  for _ in 0..100_000 {
+    let point = store.create_point()?;
+    store.create_link(hub, point)?;
+    store.create_link(point, hub)?;
+  }
+
+  // traversal stack is bounded by the tree height, not by the fan-out
+  let traverse = std::thread::Builder::new()
+    .stack_size(32 * 1024)
+    .spawn(move || {
+      let mut visited = 0;
+      store.each([usize::ANY, hub, usize::ANY], &mut |_| {
+        visited += 1;
+        Flow::Continue
+      });
+      let by_target = store.query([usize::ANY, usize::ANY, hub]).count();
+      (visited, by_target, store.count([usize::ANY, hub, usize::ANY]))
+    })
+    .unwrap();
+  assert_eq!(traverse.join().unwrap(), (100_001, 100_001, 100_001));
+  Ok(())
+}
@@ -0,0 +1,110 @@
+use {
+  doublets::{
+    Doublets, Flow, Index, Link, Links, SharedStore, create_heap_store,
+  },
+  std::thread,
+};
+
+const WRITERS: usize = 4;
+const READERS: usize = 4;
+const LINKS: usize = 500;
+
+#[test]
+fn test_concurrent_search_and_create() {
+  let shared = SharedStore::new(create_heap_store::<usize>().unwrap());
+  let point = shared.create_point().unwrap();
+
+  thread::scope(|scope| {
+    for writer in 0..WRITERS {
+      let shared = &shared;
+      scope.spawn(move || {
+        for i in 0..LINKS {
+          let target = writer * LINKS + i + 2;
+          shared.create_link(point, target).unwrap();
+        }
+      });
+    }
+    for _ in 0..READERS {
+      let shared = &shared;
+      scope.spawn(move || {
+        for i in 0..LINKS * WRITERS {
+          // Every link a reader sees must be complete and indexed
+          if let Some(index) = shared.search(point, i + 2) {
+            let link = shared.get(index).unwrap();
+            assert_eq!((link.source, link.target), (point, i + 2));
+          }
+        }
+      });
+    }
+  });
+
+  let store = shared.into_inner();
+  assert_eq!(store.count_all(), WRITERS * LINKS + 1);
+  assert_eq!(store.count([usize::ANY, point, usize::ANY]), WRITERS * LINKS + 1);
+  assert!(store.verify().is_consistent());
+}
+
+#[test]
+fn test_get_or_create_is_atomic() {
+  let shared = SharedStore::new(create_heap_store::<usize>().unwrap());
+  let point = shared.create_point().unwrap();
+
+  let indices: Vec<_> = thread::scope(|scope| {
+    let handles: Vec<_> = (0..8)
+      .map(|_| scope.spawn(|| shared.get_or_create(point, 1).unwrap()))
+      .collect();
+    handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+  });
+
+  assert!(indices.iter().all(|&index| index == indices[0]));
+  assert_eq!(shared.count([usize::ANY, point, 1]), 1);
+}
+
+#[test]
+fn test_guards() {
+  let mut shared = SharedStore::from(create_heap_store::<usize>().unwrap());
+  let a = shared.create_point().unwrap();
+
+  shared
+    .write()
+    .transaction(|store| {
+      store.create_link(a, a)?;
+      store.create_link(a, a)
+    })
+    .unwrap();
+
+  {
+    let (first, second) = (shared.read(), shared.read());
+    assert_eq!(first.count_all(), second.count_all());
+  }
+
+  let mut seen = Vec::new();
+  shared.each([usize::ANY, a, a], &mut |link: Link<usize>| {
+    seen.push(link.index);
+    Flow::Continue
+  });
+  assert_eq!(seen, [1, 2, 3]);
+  assert_eq!(shared.collect([2]), [Link::new(2, a, a)]);
+
+  shared.get_mut().delete_link(3).unwrap();
+  assert_eq!(shared.delete_link(2), Ok(2));
+  assert_eq!(shared.count([]), 1);
+}
+
+#[test]
+fn test_poisoned_writer() {
+  let shared = SharedStore::new(create_heap_store::<usize>().unwrap());
+
+  thread::scope(|scope| {
+    let result = scope
+      .spawn(|| {
+        let _guard = shared.write();
+        panic!("writer failed");
+      })
+      .join();
+    assert!(result.is_err());
+  });
+
+  let read = thread::scope(|scope| scope.spawn(|| shared.count([])).join());
+  assert!(read.is_err());
+}
@@ -0,0 +1,75 @@
+use {
+  doublets::{
+    ArtStrategy, Doublets, RawLink, SbtStrategy, Store, create_heap_store,
+  },
+  mem::Alloc,
+};
+
+#[test]
+fn test_empty_store() {
+  let store = create_heap_store::<usize>().unwrap();
+  let stats = store.stats();
+
+  assert_eq!(stats.links, 0);
+  assert_eq!(stats.free, 0);
+  assert_eq!(stats.source_tree.height, 0);
+  assert_eq!(stats.target_tree.average_depth, 0.0);
+  assert_eq!((stats.fan_out, stats.fan_in), (None, None));
+  assert_eq!(stats.bytes, stats.capacity * size_of::<RawLink<usize>>());
+}
+
+#[test]
+fn test_slots_and_fans() {
+  let mut store = create_heap_store::<usize>().unwrap();
+  let hub = store.create_point().unwrap();
+  let leaves: Vec<_> = (0..10).map(|_| store.create_point().unwrap()).collect();
+  for &leaf in &leaves {
+    store.create_link(hub, leaf).unwrap();
+  }
+  for &leaf in &leaves[..3] {
+    store.create_link(leaf, leaves[9]).unwrap();
+  }
+  store.delete_link(leaves[0]).unwrap();
+  store.delete_link(leaves[1]).unwrap();
+
+  let stats = store.stats();
+  assert_eq!(stats.links, 22);
+  assert_eq!(stats.free, 2);
+  assert_eq!(stats.allocated, 25);
+  assert_eq!(stats.fan_out, Some((hub, 10)));
+  assert_eq!(stats.fan_in, Some((leaves[9], 4)));
+
+  store.create_point().unwrap();
+  assert_eq!(store.stats().free, 1);
+  assert_eq!(store.stats().allocated, 25);
+}
+
+#[test]
+fn test_tree_shape() {
+  let mut store = create_heap_store::<usize>().unwrap();
+  let links = store.bulk_load((1..=1023).map(|i| (i, i))).unwrap();
+  assert_eq!(links.len(), 1023);
+
+  // a perfect tree has ten levels
+  let stats = store.stats();
+  assert_eq!(stats.source_tree.height, 10);
+  assert_eq!(stats.target_tree.height, 10);
+  let depths: usize = (0..10).map(|depth| depth << depth).sum();
+  assert_eq!(stats.source_tree.average_depth, depths as f64 / 1023.0);
+}
+
+#[test]
+fn test_radix_trees() {
+  type Mem = Alloc<RawLink<u32>>;
+  let mut store: Store<u32, Mem, SbtStrategy, ArtStrategy> =
+    Store::new(Alloc::new()).unwrap();
+  for _ in 0..100 {
+    store.create_point().unwrap();
+  }
+
+  let stats = store.stats();
+  assert_eq!(stats.links, 100);
+  assert!(stats.target_tree.height >= 2);
+  assert!(stats.target_tree.average_depth > 0.0);
+  assert!(stats.source_tree.height <= 2 * 7);
+}
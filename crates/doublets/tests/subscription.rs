@@ -0,0 +1,166 @@
+use {
+  doublets::{
+    Doublets, Error, Filter, Index, Link, Links, OnDelete, Policy,
+    create_heap_store,
+  },
+  std::sync::{Arc, Mutex},
+};
+
+type Changes = Arc<Mutex<Vec<(Link<usize>, Link<usize>)>>>;
+
+fn record(changes: &Changes) -> impl FnMut(Link<usize>, Link<usize>) + use<> {
+  let changes = Arc::clone(changes);
+  move |before, after| changes.lock().unwrap().push((before, after))
+}
+
+fn take(changes: &Changes) -> Vec<(Link<usize>, Link<usize>)> {
+  std::mem::take(&mut *changes.lock().unwrap())
+}
+
+#[test]
+fn test_listeners_receive_changes() {
+  let mut store = create_heap_store::<usize>().unwrap();
+  let changes = Changes::default();
+  store.subscribe(Filter::default(), record(&changes));
+
+  let a = store.create_link(0, 0).unwrap();
+  store.update_link(a, a, a).unwrap();
+  store.delete_link(a).unwrap();
+
+  let (nothing, null, point) =
+    (Link::nothing(), Link::new(a, 0, 0), Link::point(a));
+  assert_eq!(
+    take(&changes),
+    [(nothing, null), (null, point), (point, nothing)]
+  );
+
+  // unchanged links are not reported
+  let b = store.create_point().unwrap();
+  take(&changes);
+  store.update_link(b, b, b).unwrap();
+  assert!(take(&changes).is_empty());
+}
+
+#[test]
+fn test_filters() {
+  let mut store = create_heap_store::<usize>().unwrap();
+  let a = store.create_point().unwrap();
+  let b = store.create_point().unwrap();
+
+  let (from_a, deletes) = (Changes::default(), Changes::default());
+  store.subscribe(Filter::pattern(a, usize::ANY), record(&from_a));
+  let filter = Filter { create: false, update: false, ..Filter::default() };
+  store.subscribe(filter, record(&deletes));
+
+  let ab = store.create_link(a, b).unwrap();
+  store.create_link(b, b).unwrap();
+  // moving out of the pattern still matches it
+  store.update_link(ab, b, a).unwrap();
+  store.update_link(ab, b, b).unwrap();
+  store.delete_link(ab).unwrap();
+
+  assert_eq!(
+    take(&from_a),
+    [
+      (Link::nothing(), Link::new(ab, a, b)),
+      (Link::new(ab, a, b), Link::new(ab, b, a)),
+    ]
+  );
+  assert_eq!(take(&deletes), [(Link::new(ab, b, b), Link::nothing())]);
+}
+
+#[test]
+fn test_trigger_vetoes_change() {
+  let mut store = create_heap_store::<usize>().unwrap();
+  let changes = Changes::default();
+  store.subscribe(Filter::default(), record(&changes));
+  let a = store.create_point().unwrap();
+  let b = store.create_point().unwrap();
+  take(&changes);
+
+  // nothing may point to `b`
+  store.add_trigger(Filter::pattern(usize::ANY, b), move |_, after| {
+    if after.target == b { Err(Error::Rejected(after.index)) } else { Ok(()) }
+  });
+
+  assert_eq!(store.create_link(a, b), Err(Error::Rejected(3)));
+  assert_eq!(store.update_link(a, a, b), Err(Error::Rejected(a)));
+  assert_eq!(store.get(a), Some(Link::point(a)));
+  assert!(take(&changes).is_empty());
+
+  let ok = store.create_link(a, a).unwrap();
+  assert_eq!(store.get(ok), Some(Link::new(ok, a, a)));
+  assert!(store.verify().is_consistent());
+}
+
+#[test]
+fn test_point_created_at_once() {
+  let mut store = create_heap_store::<usize>().unwrap();
+  let changes = Changes::default();
+  store.subscribe(Filter::default(), record(&changes));
+  let a = store.create_point().unwrap();
+  assert_eq!(take(&changes), [(Link::nothing(), Link::point(a))]);
+
+  let allocated = store.stats().allocated;
+  let creates = Filter { update: false, delete: false, ..Filter::default() };
+  store.add_trigger(creates, |_, after| Err(Error::Rejected(after.index)));
+  assert_eq!(store.create_point(), Err(Error::Rejected(a + 1)));
+  assert_eq!(store.count_all(), 1);
+  assert!(take(&changes).is_empty());
+  // the fresh slot is given back instead of being put on the free list
+  assert_eq!(store.stats().allocated, allocated);
+  assert_eq!(store.stats().free, 0);
+  assert!(store.verify().is_consistent());
+}
+
+#[test]
+fn test_veto_reverts_operation() {
+  let policy = Policy { on_delete: OnDelete::Cascade, ..Policy::default() };
+  let mut store = create_heap_store::<usize>().unwrap().with_policy(policy);
+  let a = store.create_point().unwrap();
+  let b = store.create_link(a, a).unwrap();
+  let c = store.create_link(b, b).unwrap();
+
+  let changes = Changes::default();
+  store.subscribe(Filter::default(), record(&changes));
+  let deletes = Filter { create: false, update: false, ..Filter::default() };
+  store.add_trigger(deletes, move |before, _| {
+    if before.index == a { Err(Error::Rejected(a)) } else { Ok(()) }
+  });
+
+  // usages go first, then the veto of `a` reverts them
+  assert_eq!(store.delete_link(a), Err(Error::Rejected(a)));
+  assert_eq!(store.count_all(), 3);
+  let (nothing, b, c) =
+    (Link::nothing(), Link::new(b, a, a), Link::new(c, b, b));
+  assert_eq!(
+    take(&changes),
+    [(c, nothing), (b, nothing), (nothing, b), (nothing, c)]
+  );
+}
+
+#[test]
+fn test_bulk_load_and_unsubscribe() {
+  let mut store = create_heap_store::<usize>().unwrap();
+  let changes = Changes::default();
+  let listener = store.subscribe(Filter::default(), record(&changes));
+  let trigger = store.add_trigger(Filter::pattern(2, 2), |_, after| {
+    Err(Error::Rejected(after.index))
+  });
+
+  assert_eq!(store.bulk_load([(1, 1), (2, 2)]), Err(Error::Rejected(2)));
+  assert_eq!(store.count_all(), 0);
+  assert!(take(&changes).is_empty());
+
+  assert!(store.unsubscribe(trigger));
+  assert!(!store.unsubscribe(trigger));
+  store.bulk_load([(1, 1), (2, 2)]).unwrap();
+  assert_eq!(
+    take(&changes),
+    [(Link::nothing(), Link::point(1)), (Link::nothing(), Link::point(2))]
+  );
+
+  assert!(store.unsubscribe(listener));
+  store.create_point().unwrap();
+  assert!(take(&changes).is_empty());
+}
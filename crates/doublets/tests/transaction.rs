@@ -0,0 +1,115 @@
+// Tests for transactions with rollback
+//
+// Changes done inside `Store::transaction` are reverted when the closure
+// fails, panics or aborts, including the free list of the store.
+
+use {
+  doublets::{
+    ArtStrategy, Doublets, Error, Index, Link, Links, RawLink, Result,
+    SbtStrategy, Store, create_heap_store,
+  },
+  mem::Alloc,
+  std::panic::{AssertUnwindSafe, catch_unwind},
+};
+
+/// Small store with a few links and a non-empty free list
+fn sample() -> Result<Store<usize>, usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let c = store.create_point()?;
+  store.create_link(a, b)?;
+  let bc = store.create_link(b, c)?;
+  store.create_link(c, a)?;
+  store.delete_link(bc)?;
+  Ok(store)
+}
+
+#[test]
+fn test_commit_keeps_changes() -> Result<(), usize> {
+  let mut store = sample()?;
+  let link = store.transaction(|tx| {
+    let link = tx.create_link(1, 2)?;
+    tx.update_link(4, 3, 3)?;
+    Ok(link)
+  })?;
+  assert_eq!(store.get(link), Some(Link::new(link, 1, 2)));
+  assert_eq!(store.get(4), Some(Link::new(4, 3, 3)));
+  Ok(())
+}
+
+#[test]
+fn test_error_reverts_everything() -> Result<(), usize> {
+  let mut store = sample()?;
+  let before = store.collect_all();
+
+  let result = store.transaction(|tx| {
+    let point = tx.create_point()?;
+    tx.create_link(point, 1)?;
+    tx.update_link(4, point, point)?;
+    tx.delete_link(6)?;
+    tx.rebase(1, point)?;
+    tx.delete_link(100)
+  });
+  assert_eq!(result, Err(Error::NotExists(100)));
+  assert_eq!(store.collect_all(), before);
+  assert_eq!(store.count([usize::ANY, 1, usize::ANY]), 2);
+  assert_eq!(store.search(3, 1), Some(6));
+
+  // the free list is restored as well
+  let mut reference = sample()?;
+  assert_eq!(store.create_point()?, reference.create_point()?);
+  assert_eq!(store.create_point()?, reference.create_point()?);
+  Ok(())
+}
+
+#[test]
+fn test_abort_and_nested() -> Result<(), usize> {
+  let mut store = sample()?;
+  let before = store.collect_all();
+
+  let value = store.transaction(|tx| {
+    tx.create_point()?;
+    tx.abort();
+    Ok(42)
+  })?;
+  assert_eq!(value, 42);
+  assert_eq!(store.collect_all(), before);
+
+  store.transaction(|tx| {
+    let kept = tx.create_link(1, 3)?;
+    let inner = tx.transaction(|tx| {
+      tx.delete_link(kept)?;
+      tx.create_link(2, 1)?;
+      Err::<(), _>(Error::InvalidQuery)
+    });
+    assert_eq!(inner, Err(Error::InvalidQuery));
+    assert_eq!(tx.search(1, 3), Some(kept));
+    assert_eq!(tx.search(2, 1), None);
+    Ok(())
+  })?;
+  assert_eq!(store.count_all(), before.len() + 1);
+  Ok(())
+}
+
+#[test]
+fn test_panic_reverts() -> Result<(), usize> {
+  let mut store: Store<usize, Alloc<RawLink<usize>>, ArtStrategy, SbtStrategy> =
+    Store::new(Alloc::new())?;
+  let a = store.create_point()?;
+  let before = store.collect_all();
+
+  let result = catch_unwind(AssertUnwindSafe(|| {
+    store.transaction(|tx| {
+      tx.create_link(a, a)?;
+      panic!("interrupted");
+      #[allow(unreachable_code)]
+      Ok(())
+    })
+  }));
+  assert!(result.is_err());
+  assert_eq!(store.collect_all(), before);
+  assert_eq!(store.search(a, a), Some(a));
+  assert_eq!(store.count([usize::ANY, a, usize::ANY]), 1);
+  Ok(())
+}
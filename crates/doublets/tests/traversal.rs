@@ -0,0 +1,113 @@
+use doublets::{
+  Direction, Doublets, Link, Store, Traversal, create_heap_store,
+};
+
+/// Points 1 to 6 with 1 -> 2 -> 3 -> 4, 1 -> 3 and 5 -> 6
+fn graph() -> Store<usize> {
+  let mut store = create_heap_store::<usize>().unwrap();
+  for _ in 1..=6 {
+    store.create_point().unwrap();
+  }
+  for (source, target) in [(1, 2), (2, 3), (1, 3), (3, 4), (5, 6)] {
+    store.create_link(source, target).unwrap();
+  }
+  store
+}
+
+fn indices(links: &[Link<usize>]) -> Vec<usize> {
+  links.iter().map(|link| link.index).collect()
+}
+
+#[test]
+fn test_bfs() {
+  let store = graph();
+
+  let steps: Vec<_> = store
+    .bfs(1, Direction::Outgoing)
+    .map(|step| (step.index, step.depth))
+    .collect();
+  assert_eq!(steps, [(1, 0), (2, 1), (3, 1), (4, 2)]);
+
+  let incoming: Vec<_> =
+    store.bfs(4, Direction::Incoming).map(|step| step.index).collect();
+  assert_eq!(incoming, [4, 3, 1, 2]);
+
+  let first = store.bfs(1, Direction::Outgoing).nth(2).unwrap();
+  assert_eq!(first.via, Some(Link::new(9, 1, 3)));
+  assert_eq!(store.bfs(42, Direction::Both).count(), 0);
+}
+
+#[test]
+fn test_dfs() {
+  let store = graph();
+
+  let steps: Vec<_> = store
+    .dfs(1, Direction::Outgoing)
+    .map(|step| (step.index, step.depth))
+    .collect();
+  assert_eq!(steps, [(1, 0), (2, 1), (3, 2), (4, 3)]);
+
+  let both: Vec<_> =
+    store.dfs(6, Direction::Both).map(|step| step.index).collect();
+  assert_eq!(both, [6, 5]);
+}
+
+#[test]
+fn test_find_cycle() {
+  let mut store = graph();
+  assert_eq!(store.find_cycle(1, Direction::Outgoing), None);
+  // a tree has no cycles without direction either
+  assert_eq!(store.find_cycle(5, Direction::Both), None);
+  // but the diamond 1 -> 2 -> 3 <- 1 has one
+  assert!(store.find_cycle(1, Direction::Both).is_some());
+
+  let back = store.create_link(4, 1).unwrap();
+  let cycle = store.find_cycle(1, Direction::Outgoing).unwrap();
+  assert_eq!(indices(&cycle), [7, 8, 10, back]);
+  let cycle = store.find_cycle(3, Direction::Incoming).unwrap();
+  assert_eq!(cycle.len(), 3);
+
+  // parallel links form a cycle of two
+  let parallel = store.create_link(5, 6).unwrap();
+  let cycle = store.find_cycle(5, Direction::Both).unwrap();
+  assert_eq!(indices(&cycle), [11, parallel]);
+  assert_eq!(store.find_cycle(5, Direction::Outgoing), None);
+}
+
+#[test]
+fn test_shortest_path() {
+  let store = graph();
+
+  let path = store.shortest_path(1, 4, Direction::Outgoing).unwrap();
+  assert_eq!(indices(&path), [9, 10]);
+  let path = store.shortest_path(4, 2, Direction::Incoming).unwrap();
+  assert_eq!(indices(&path), [10, 8]);
+  let path = store.shortest_path(4, 2, Direction::Both).unwrap();
+  assert_eq!(indices(&path), [10, 8]);
+
+  assert_eq!(store.shortest_path(2, 2, Direction::Outgoing), Some(vec![]));
+  assert_eq!(store.shortest_path(4, 1, Direction::Outgoing), None);
+  assert_eq!(store.shortest_path(1, 6, Direction::Both), None);
+}
+
+#[test]
+fn test_components() {
+  let mut store = graph();
+  store.delete_link(11).unwrap();
+  // joins the links 7 and 8, while nothing references it
+  let hub = store.create_link(7, 8).unwrap();
+
+  let components: Vec<_> = store.components().collect();
+  assert_eq!(
+    components,
+    [
+      vec![1, 2, 3, 4],
+      vec![5],
+      vec![6],
+      vec![7, 8],
+      vec![9],
+      vec![10],
+      vec![hub]
+    ]
+  );
+}
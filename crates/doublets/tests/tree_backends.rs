@@ -4,20 +4,23 @@
 // implementations (SBT - Size-Balanced Tree and ART - Adaptive Radix
 // Tree) for source and target indexing.
 //
-// Both strategies maintain BST ordering by (source, target) tuples,
-// enabling O(log n + k) range traversal and exact search for all
-// strategy combinations.
+// SBT keeps its trees inside the links, ART keeps a radix trie next to
+// them. Both visit links in (source, target) or (target, source) order,
+// enabling range traversal and exact search for all strategy
+// combinations.
 
 use {
   doublets::{
-    ArtStrategy, Doublets, Flow, Link, Links, RawLink, Result, SbtStrategy,
-    Store, TreeStrategy,
+    ArtStrategy, Doublets, Flow, Index, Link, Links, RawLink, Result,
+    SbtStrategy, Store, TreeStrategy,
   },
   mem::Alloc,
 };
 
+type HeapStore<S, T> = Store<usize, Alloc<RawLink<usize>>, S, T>;
+
 /// Helper to create a store with custom tree strategies
-fn create_store<S, T>() -> Result<Store<usize, Alloc<RawLink>, S, T>, usize>
+fn create_store<S, T>() -> Result<HeapStore<S, T>, usize>
 where
   S: TreeStrategy<usize>,
   T: TreeStrategy<usize>,
@@ -58,6 +61,26 @@ macro_rules! define_tests_for_backend {
       fn [<test_scalability_ $suffix>]() -> Result<(), usize> {
         test_scalability::<$src, $tgt>()
       }
+
+      #[test]
+      fn [<test_query_order_ $suffix>]() -> Result<(), usize> {
+        test_query_order::<$src, $tgt>()
+      }
+
+      #[test]
+      fn [<test_lazy_query_ $suffix>]() -> Result<(), usize> {
+        test_lazy_query::<$src, $tgt>()
+      }
+
+      #[test]
+      fn [<test_count_by_rank_ $suffix>]() -> Result<(), usize> {
+        test_count_by_rank::<$src, $tgt>()
+      }
+
+      #[test]
+      fn [<test_duplicate_links_ $suffix>]() -> Result<(), usize> {
+        test_duplicate_links::<$src, $tgt>()
+      }
     }
   };
 }
@@ -138,7 +161,7 @@ where
   let _d = store.create_link(a, a)?;
 
   let mut found_links = Vec::new();
-  store.each([0, a, 0], &mut |link| {
+  store.each([usize::ANY, a, usize::ANY], &mut |link| {
     found_links.push(link);
     Flow::Continue
   });
@@ -161,7 +184,7 @@ where
   let _d = store.create_link(b, b)?;
 
   let mut found_links = Vec::new();
-  store.each([0, 0, b], &mut |link| {
+  store.each([usize::ANY, usize::ANY, b], &mut |link| {
     found_links.push(link);
     Flow::Continue
   });
@@ -228,6 +251,159 @@ where
   Ok(())
 }
 
+fn test_query_order<S, T>() -> Result<(), usize>
+where
+  S: TreeStrategy<usize>,
+  T: TreeStrategy<usize>,
+{
+  let mut store = create_store::<S, T>()?;
+  let hub = store.create_point()?;
+  let points: Vec<_> =
+    (0..300).map(|_| store.create_point()).collect::<Result<_, _>>()?;
+
+  // shuffled insertion order, wide enough to fill large trie nodes
+  for i in 0..points.len() {
+    let point = points[i * 7 % points.len()];
+    store.create_link(hub, point)?;
+    store.create_link(point, hub)?;
+  }
+
+  let mut targets = Vec::new();
+  store.each([usize::ANY, hub, usize::ANY], &mut |link: Link<usize>| {
+    targets.push(link.target);
+    Flow::Continue
+  });
+  let mut expected = points.clone();
+  expected.insert(0, hub);
+  assert_eq!(targets, expected);
+
+  let mut sources = Vec::new();
+  store.each([usize::ANY, usize::ANY, hub], &mut |link: Link<usize>| {
+    sources.push(link.source);
+    Flow::Continue
+  });
+  assert_eq!(sources, expected);
+
+  for &point in &points[..150] {
+    let link = store.search(hub, point).unwrap();
+    store.delete_link(link)?;
+  }
+  assert_eq!(store.count([usize::ANY, hub, usize::ANY]), 151);
+  assert_eq!(store.search(hub, points[200]), Some(2 * 200 + 302));
+  Ok(())
+}
+
+fn test_lazy_query<S, T>() -> Result<(), usize>
+where
+  S: TreeStrategy<usize>,
+  T: TreeStrategy<usize>,
+{
+  let mut store = create_store::<S, T>()?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let c = store.create_point()?;
+  let ab = store.create_link(a, b)?;
+  let ab2 = store.create_link(a, b)?;
+  let ac = store.create_link(a, c)?;
+  let cb = store.create_link(c, b)?;
+
+  let collect = |query: [usize; 3]| {
+    let mut found = Vec::new();
+    store.each(query, &mut |link: Link<usize>| {
+      found.push(link);
+      Flow::Continue
+    });
+    found
+  };
+
+  let any = usize::ANY;
+  for query in
+    [[any, a, any], [any, any, b], [any, a, b], [any, c, c], [ac, a, any]]
+  {
+    assert_eq!(store.query(query).collect::<Vec<_>>(), collect(query));
+  }
+
+  let by_source: Vec<_> =
+    store.query([usize::ANY, a, usize::ANY]).map(|link| link.index).collect();
+  assert_eq!(by_source, [a, ab, ab2, ac]);
+  let exact: Vec<_> =
+    store.query([usize::ANY, a, b]).map(|link| link.index).collect();
+  assert_eq!(exact, [ab, ab2]);
+  let by_target: Vec<_> =
+    store.query([usize::ANY, usize::ANY, b]).map(|link| link.index).collect();
+  assert_eq!(by_target, [ab, ab2, b, cb]);
+  assert_eq!(store.query([ac, c, usize::ANY]).next(), None);
+  Ok(())
+}
+
+fn test_count_by_rank<S, T>() -> Result<(), usize>
+where
+  S: TreeStrategy<usize>,
+  T: TreeStrategy<usize>,
+{
+  let mut store = create_store::<S, T>()?;
+  let points: Vec<_> =
+    (0..40).map(|_| store.create_point()).collect::<Result<_, _>>()?;
+  for i in 0..400 {
+    store.create_link(points[i * 7 % 40], points[i * 13 % 11])?;
+  }
+  for link in (50..400).step_by(9) {
+    store.delete_link(link)?;
+  }
+  for link in (60..400).step_by(17) {
+    if store.get(link).is_some() {
+      store.update_link(link, points[3], points[link % 5])?;
+    }
+  }
+
+  for &a in &points {
+    assert_eq!(
+      store.count([usize::ANY, a, usize::ANY]),
+      store.query([usize::ANY, a, usize::ANY]).count()
+    );
+    assert_eq!(
+      store.count([usize::ANY, usize::ANY, a]),
+      store.query([usize::ANY, usize::ANY, a]).count()
+    );
+    for &b in &points[..12] {
+      assert_eq!(
+        store.count([usize::ANY, a, b]),
+        store.query([usize::ANY, a, b]).count()
+      );
+    }
+  }
+  assert_eq!(store.count([0, points[3]]), store.query([0, points[3]]).count());
+  assert_eq!(
+    store.count([usize::ANY, usize::ANY, usize::ANY]),
+    store.count_all()
+  );
+  assert_eq!(store.count([5, usize::ANY, 5]), 1);
+  assert_eq!(store.count([5, usize::ANY, 6]), 0);
+  Ok(())
+}
+
+fn test_duplicate_links<S, T>() -> Result<(), usize>
+where
+  S: TreeStrategy<usize>,
+  T: TreeStrategy<usize>,
+{
+  let mut store = create_store::<S, T>()?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let links: Vec<_> =
+    (0..20).map(|_| store.create_link(a, b)).collect::<Result<_, _>>()?;
+
+  // every copy stays reachable while the others are removed
+  for (removed, &link) in links.iter().enumerate().step_by(3) {
+    store.delete_link(link)?;
+    let left = links.len() - removed / 3 - 1;
+    assert_eq!(store.query([usize::ANY, a, b]).count(), left);
+    assert_eq!(store.count([usize::ANY, a, b]), left);
+    assert_eq!(store.count([usize::ANY, usize::ANY, b]), left + 1);
+  }
+  Ok(())
+}
+
 // Instantiate tests for all backend combinations
 define_tests_for_backend!(SbtStrategy, SbtStrategy, "sbt_sbt");
 define_tests_for_backend!(ArtStrategy, ArtStrategy, "art_art");
@@ -0,0 +1,217 @@
+// Tests for checking stores for consistency
+//
+// Stores changed only through their API must always verify clean, while
+// corruption written straight into a store file must be reported.
+
+mod common;
+
+use {
+  common::open,
+  doublets::{
+    ArtStrategy, Doublets, Error, Flow, Index, Links, OnDelete, Policy,
+    RawLink, Result, SbtStrategy, Side, Store, TreeFault, TreeStrategy,
+    Violation, create_heap_store,
+  },
+  mem::Alloc,
+  std::{
+    fs::OpenOptions,
+    io::{Seek, SeekFrom, Write},
+    path::Path,
+  },
+};
+
+const ANY: usize = usize::ANY;
+const LINK: u64 = size_of::<RawLink<usize>>() as u64;
+
+/// Overwrite a word of the link at `index`, the layout is `repr(C)`
+fn overwrite(path: &Path, index: usize, offset: u64, value: usize) {
+  let mut file = OpenOptions::new().write(true).open(path).unwrap();
+  file.seek(SeekFrom::Start(index as u64 * LINK + offset)).unwrap();
+  file.write_all(&value.to_ne_bytes()).unwrap();
+}
+
+fn stored_file(path: &Path) -> Result<(), usize> {
+  let mut store = open(path)?;
+  let points: Vec<_> =
+    (0..20).map(|_| store.create_point()).collect::<Result<_, _>>()?;
+  for i in 0..100 {
+    store.create_link(points[i % 20], points[i * 3 % 20])?;
+  }
+  Ok(())
+}
+
+fn test_consistent<S, R>() -> Result<(), usize>
+where
+  S: TreeStrategy<usize>,
+  R: TreeStrategy<usize>,
+{
+  let mut store =
+    Store::<usize, Alloc<RawLink<usize>>, S, R>::new(Alloc::new())?;
+  assert!(store.verify().is_consistent());
+
+  let points: Vec<_> =
+    (0..30).map(|_| store.create_point()).collect::<Result<_, _>>()?;
+  for i in 0..300 {
+    store.create_link(points[i % 30], points[i * 7 % 30])?;
+  }
+  store
+    .set_policy(Policy { on_delete: OnDelete::Cascade, ..Policy::default() });
+  store.delete_link(points[4])?;
+  store.update([ANY, points[1], ANY], [ANY, points[2], ANY], &mut |_, _| {
+    Flow::Continue
+  })?;
+  let _ = store.transaction(|tx| {
+    tx.create_link(points[0], points[0])?;
+    tx.delete_link(usize::MAX - 1)
+  });
+
+  let report = store.verify();
+  assert_eq!(report.violations, []);
+  assert_eq!(report.links, store.count_all());
+
+  store.compact()?;
+  assert!(store.verify().is_consistent());
+  Ok(())
+}
+
+#[test]
+fn test_consistent_sbt() -> Result<(), usize> {
+  test_consistent::<SbtStrategy, SbtStrategy>()
+}
+
+#[test]
+fn test_consistent_art() -> Result<(), usize> {
+  test_consistent::<ArtStrategy, ArtStrategy>()
+}
+
+#[test]
+fn test_dangling_references() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let a = store.create_point()?;
+  let b = store.create_point()?;
+  let ab = store.create_link(a, b)?;
+  store.delete_link(b)?;
+
+  let report = store.verify();
+  assert_eq!(report.links, 2);
+  assert_eq!(
+    report.violations,
+    [Violation::Dangling { index: ab, reference: b }]
+  );
+  Ok(())
+}
+
+#[test]
+fn test_corrupted_source() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+  stored_file(&path)?;
+  // source of a link changed behind the trees
+  overwrite(&path, 50, 0, 7);
+
+  let report = open(&path)?.verify();
+  assert!(report.violations.contains(&Violation::Tree {
+    side: Side::Source,
+    fault: TreeFault::Misplaced(50),
+  }));
+  assert!(!report.violations.iter().any(|violation| matches!(
+    violation,
+    Violation::Tree { fault: TreeFault::Missing(_), .. }
+  )));
+  Ok(())
+}
+
+#[test]
+fn test_corrupted_size() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+  stored_file(&path)?;
+  // subtree size of the source tree node, right after source and target
+  overwrite(&path, 50, 16, 42);
+
+  let report = open(&path)?.verify();
+  assert!(report.violations.iter().any(|violation| matches!(
+    violation,
+    Violation::Tree {
+      side: Side::Source,
+      fault: TreeFault::Size { index: 50, stored: 42, .. },
+    }
+  )));
+  Ok(())
+}
+
+#[test]
+fn test_corrupted_free_marker() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+  stored_file(&path)?;
+  // live link marked as free, the marker is the last field
+  overwrite(&path, 30, LINK - 8, usize::MAX);
+
+  let report = open(&path)?.verify();
+  for side in [Side::Source, Side::Target] {
+    let fault = TreeFault::Stray(30);
+    assert!(report.violations.contains(&Violation::Tree { side, fault }));
+  }
+  assert!(report.violations.contains(&Violation::Unlisted(30)));
+  assert_eq!(report.links, 119);
+  Ok(())
+}
+
+#[test]
+fn test_rebuild_corrupted_trees() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+  stored_file(&path)?;
+  // sizes and children of both trees, which follow source and target
+  for index in [25, 50, 75] {
+    for offset in (2..8).map(|word| word * 8) {
+      overwrite(&path, index, offset, 0x5a5a);
+    }
+  }
+
+  let mut store = open(&path)?;
+  assert!(!store.verify().is_consistent());
+  store.rebuild_indexes()?;
+  assert_eq!(store.verify().violations, []);
+
+  assert_eq!(store.count_all(), 120);
+  for i in 1..=20 {
+    let by_source = store.query([ANY, i, ANY]).count();
+    assert_eq!(by_source, 6);
+    assert_eq!(store.count([ANY, i, ANY]), by_source);
+  }
+  drop(store);
+
+  // the rebuilt trees are persisted
+  assert!(open(&path)?.verify().is_consistent());
+  Ok(())
+}
+
+#[test]
+fn test_rebuild_free_list() -> Result<(), usize> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("db.links");
+  stored_file(&path)?;
+  overwrite(&path, 30, LINK - 8, usize::MAX);
+  // garbage in the marker of a live link
+  overwrite(&path, 40, LINK - 8, 3);
+
+  let mut store = open(&path)?;
+  store.rebuild_indexes()?;
+  assert_eq!(store.verify().violations, []);
+  assert_eq!(store.count_all(), 119);
+  assert!(store.get(40).is_some());
+
+  // the slot marked as free is reused first
+  assert_eq!(store.create_point()?, 30);
+  Ok(())
+}
+
+#[test]
+fn test_rebuild_inside_transaction() -> Result<(), usize> {
+  let mut store = create_heap_store::<usize>()?;
+  let result = store.transaction(|tx| tx.rebuild_indexes());
+  assert_eq!(result, Err(Error::InvalidQuery));
+  Ok(())
+}
@@ -115,6 +115,13 @@ impl<T: Pod> RawMem for FileMapped<T> {
 
     Ok(())
   }
+
+  fn sync(&mut self) -> Result<()> {
+    if let Some(map) = &self.map {
+      map.flush()?;
+    }
+    Ok(())
+  }
 }
 
 impl<T> Drop for FileMapped<T> {
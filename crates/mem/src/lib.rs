@@ -68,6 +68,10 @@ macro_rules! memory {
         fn shrink(&mut self, cap: usize) -> Result<()> {
           self.0.shrink(cap)
         }
+
+        fn sync(&mut self) -> Result<()> {
+          self.0.sync()
+        }
       }
 
       impl<T> fmt::Debug for $name<$param> {
@@ -147,4 +147,11 @@ pub trait RawMem {
   fn grow(&mut self, cap: usize) -> Result<Page<'_, Self::Item>>;
 
   fn shrink(&mut self, cap: usize) -> Result<()>;
+
+  /// Flush written memory to its durable storage, if it has one
+  ///
+  /// Heap memory has nothing to flush, so the default does nothing.
+  fn sync(&mut self) -> Result<()> {
+    Ok(())
+  }
 }
@@ -7,7 +7,7 @@ pub struct Store<T> {
   nodes: Vec<Node<T>>,
 }
 
-impl<T> Store<T> {
+impl<T: Idx> Store<T> {
   pub fn new(capacity: usize) -> Self {
     Self { nodes: (0..capacity).map(|_| Node::default()).collect() }
   }
@@ -41,12 +41,14 @@ impl<T: Idx> Tree<T> for Store<T> {
 
   #[inline(always)]
   fn left_mut(&mut self, idx: T) -> Option<&mut T> {
-    self.nodes.get_mut(idx.as_usize())?.left.as_mut()
+    let left = &mut self.nodes.get_mut(idx.as_usize())?.left;
+    (!left.is_null()).then_some(left)
   }
 
   #[inline(always)]
   fn right_mut(&mut self, idx: T) -> Option<&mut T> {
-    self.nodes.get_mut(idx.as_usize())?.right.as_mut()
+    let right = &mut self.nodes.get_mut(idx.as_usize())?.right;
+    (!right.is_null()).then_some(right)
   }
 
   #[inline(always)]
@@ -67,58 +69,30 @@ impl<T: Idx> Tree<T> for Store<T> {
 // SBT strategy
 impl<T: Idx> SizeBalanced<T> for Store<T> {}
 
-// ART strategy
-impl<T: Idx> AdaptiveRadix<T> for Store<T> {}
-
-/// ART-specific store that uses ART insert/remove by default
-#[derive(Debug, Clone)]
-pub struct ArtStore<T> {
-  inner: Store<T>,
+/// ART keyed by the big-endian bytes of an index
+#[derive(Debug, Clone, Default)]
+pub struct ArtStore {
+  tree: AdaptiveRadix<()>,
 }
 
-impl<T> ArtStore<T> {
-  pub fn new(capacity: usize) -> Self {
-    Self { inner: Store::new(capacity) }
+impl ArtStore {
+  pub fn new() -> Self {
+    Self::default()
   }
 
   pub fn reset(&mut self) {
-    self.inner.reset()
+    self.tree.clear()
   }
-}
 
-impl<T: Idx> Tree<T> for ArtStore<T> {
-  #[inline(always)]
-  fn get(&self, idx: T) -> Option<Node<T>> {
-    self.inner.get(idx)
+  pub fn insert(&mut self, idx: usize) {
+    self.tree.insert(&idx.to_be_bytes(), ());
   }
 
-  #[inline(always)]
-  fn set(&mut self, idx: T, node: Node<T>) {
-    self.inner.set(idx, node)
+  pub fn contains(&self, idx: usize) -> bool {
+    self.tree.contains(&idx.to_be_bytes())
   }
 
-  #[inline(always)]
-  fn left_mut(&mut self, idx: T) -> Option<&mut T> {
-    self.inner.left_mut(idx)
-  }
-
-  #[inline(always)]
-  fn right_mut(&mut self, idx: T) -> Option<&mut T> {
-    self.inner.right_mut(idx)
-  }
-
-  #[inline(always)]
-  fn is_left_of(&self, first: T, second: T) -> bool {
-    self.inner.is_left_of(first, second)
-  }
-
-  fn insert(&mut self, root: Option<T>, idx: T) -> Option<T> {
-    self.insert_art(root, idx)
-  }
-
-  fn remove(&mut self, root: Option<T>, idx: T) -> Option<T> {
-    self.remove_art(root, idx)
+  pub fn remove(&mut self, idx: usize) {
+    self.tree.remove(&idx.to_be_bytes());
   }
 }
-
-impl<T: Idx> AdaptiveRadix<T> for ArtStore<T> {}
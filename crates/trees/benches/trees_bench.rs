@@ -1,132 +1,149 @@
 mod common;
 
 use {
-  common::Store,
-  criterion::{Criterion, black_box, criterion_group, criterion_main},
-  trees::{Idx, Tree},
+  common::{ArtStore, Store},
+  criterion::{Criterion, criterion_group, criterion_main},
+  std::hint::black_box,
+  trees::Tree,
 };
 
 /// Trait for tree stores that can be reset and created
-trait BenchStore<T: Idx>: Tree<T> {
+trait BenchStore {
   fn new(capacity: usize) -> Self;
   fn reset(&mut self);
+  fn insert(&mut self, idx: usize);
+  fn contains(&self, idx: usize) -> bool;
+  fn remove(&mut self, idx: usize);
 }
 
-impl<T: Idx> BenchStore<T> for Store<T> {
+/// Size-balanced tree over a vector store with its root
+struct SbtStore {
+  store: Store<usize>,
+  root: Option<usize>,
+}
+
+impl BenchStore for SbtStore {
   fn new(capacity: usize) -> Self {
-    Store::new(capacity)
+    Self { store: Store::new(capacity), root: None }
   }
 
   fn reset(&mut self) {
-    Store::reset(self)
+    self.store.reset();
+    self.root = None;
+  }
+
+  fn insert(&mut self, idx: usize) {
+    self.root = self.store.insert(self.root, idx);
+  }
+
+  fn contains(&self, idx: usize) -> bool {
+    self.root.is_some_and(|root| self.store.contains(root, idx))
+  }
+
+  fn remove(&mut self, idx: usize) {
+    self.root = self.store.remove(self.root, idx);
   }
 }
 
-impl<T: Idx> BenchStore<T> for common::ArtStore<T> {
-  fn new(capacity: usize) -> Self {
-    common::ArtStore::new(capacity)
+impl BenchStore for ArtStore {
+  fn new(_: usize) -> Self {
+    ArtStore::new()
   }
 
   fn reset(&mut self) {
-    common::ArtStore::reset(self)
+    ArtStore::reset(self)
+  }
+
+  fn insert(&mut self, idx: usize) {
+    ArtStore::insert(self, idx)
+  }
+
+  fn contains(&self, idx: usize) -> bool {
+    ArtStore::contains(self, idx)
+  }
+
+  fn remove(&mut self, idx: usize) {
+    ArtStore::remove(self, idx)
   }
 }
 
 // Helper function for insert benchmarks
-fn bench_insert_impl<S, T>(n: usize) -> impl FnMut()
-where
-  S: BenchStore<T>,
-  T: Idx + From<usize>,
-{
+fn bench_insert_impl<S: BenchStore>(n: usize) -> impl FnMut() {
   let mut store = S::new(n);
   move || {
-    let mut root = None;
     for i in 1..n {
-      root = store.insert(root, T::from(i));
+      store.insert(i);
     }
-    black_box(root);
+    black_box(&store);
     store.reset();
   }
 }
 
 // Helper function for insert and search benchmarks
-fn bench_insert_and_search_impl<S, T>(n: usize) -> impl FnMut()
-where
-  S: BenchStore<T>,
-  T: Idx + From<usize>,
-{
+fn bench_insert_and_search_impl<S: BenchStore>(n: usize) -> impl FnMut() {
   let mut store = S::new(n);
   move || {
-    let mut root = None;
     for i in 1..n {
-      root = store.insert(root, T::from(i));
+      store.insert(i);
     }
     for i in 1..n {
-      black_box(store.contains(root.unwrap(), T::from(i)));
+      black_box(store.contains(i));
     }
     store.reset();
   }
 }
 
 // Helper function for full cycle benchmarks
-fn bench_insert_remove_impl<S, T>(n: usize) -> impl FnMut()
-where
-  S: BenchStore<T>,
-  T: Idx + From<usize>,
-{
+fn bench_insert_remove_impl<S: BenchStore>(n: usize) -> impl FnMut() {
   let mut store = S::new(n);
   move || {
-    let mut root = None;
     for i in 1..n {
-      root = store.insert(root, T::from(i));
+      store.insert(i);
     }
     for i in 1..n {
-      root = store.remove(root, T::from(i));
+      store.remove(i);
     }
-    black_box(root);
+    black_box(&store);
     store.reset();
   }
 }
 
 // Generic benchmark registration to avoid repetition
-fn register_benchmarks<S, T>(
+fn register_benchmarks<S: BenchStore + 'static>(
   c: &mut Criterion,
   tree_name: &str,
   sizes: &[usize],
-) where
-  S: BenchStore<T> + 'static,
-  T: Idx + From<usize> + 'static,
-{
+) {
   // Insert-only benchmarks
   for &size in sizes {
     c.bench_function(&format!("{}::insert({})", tree_name, size), |b| {
-      b.iter(bench_insert_impl::<S, T>(size))
+      b.iter(bench_insert_impl::<S>(size))
     });
   }
 
   // Insert + search benchmarks
   for &size in sizes {
     c.bench_function(&format!("{}::insert_search({})", tree_name, size), |b| {
-      b.iter(bench_insert_and_search_impl::<S, T>(size))
+      b.iter(bench_insert_and_search_impl::<S>(size))
     });
   }
 
   // Full cycle (insert + remove) benchmarks
   for &size in sizes {
     c.bench_function(&format!("{}::full_cycle({})", tree_name, size), |b| {
-      b.iter(bench_insert_remove_impl::<S, T>(size))
+      b.iter(bench_insert_remove_impl::<S>(size))
     });
   }
 }
 
 fn sbt_benchmarks(c: &mut Criterion) {
   const SIZES: &[usize] = &[100, 1_000, 10_000];
-  register_benchmarks::<Store<usize>, usize>(c, "sbt", SIZES);
+  register_benchmarks::<SbtStore>(c, "sbt", SIZES);
 }
 
 fn art_benchmarks(c: &mut Criterion) {
   const SIZES: &[usize] = &[100, 1_000, 10_000];
-  register_benchmarks::<common::ArtStore<usize>, usize>(c, "art", SIZES);
+  register_benchmarks::<ArtStore>(c, "art", SIZES);
 }
 
 criterion_group!(benches, sbt_benchmarks, art_benchmarks);
@@ -30,11 +30,13 @@ impl<T: Idx> Tree<T> for Store<T> {
   }
 
   fn left_mut(&mut self, idx: T) -> Option<&mut T> {
-    self.nodes.get_mut(idx.as_usize()).and_then(|n| n.left.as_mut())
+    let left = &mut self.nodes.get_mut(idx.as_usize())?.left;
+    (!left.is_null()).then_some(left)
   }
 
   fn right_mut(&mut self, idx: T) -> Option<&mut T> {
-    self.nodes.get_mut(idx.as_usize()).and_then(|n| n.right.as_mut())
+    let right = &mut self.nodes.get_mut(idx.as_usize())?.right;
+    (!right.is_null()).then_some(right)
   }
 
   fn is_left_of(&self, first: T, second: T) -> bool {
@@ -1,271 +1,726 @@
-use crate::{Idx, Node, Tree};
+use core::{iter::FusedIterator, mem};
 
-/// Adaptive Radix Tree - provides operations on trie stored in slice
+/// Adaptive Radix Tree - ordered map from byte keys to values
 ///
-/// Extends the base Tree trait with adaptive radix tree operations.
-/// Uses byte-by-byte key decomposition with adaptive node sizing.
+/// Unlike [`SizeBalanced`](crate::SizeBalanced) the trie does not live in
+/// the nodes of a [`Tree`](crate::Tree), it keeps its own side storage:
+/// inner nodes and leaves are allocated in two arenas and refer to each
+/// other by position, so removed slots are reused without reallocating.
 ///
 /// ART is optimized for main-memory indexing with:
-/// - O(k) search/insert/delete where k is key length (8 bytes for u64)
+/// - O(k) search/insert/delete where k is the key length
 /// - Adaptive node sizes (4, 16, 48, 256 children) for memory efficiency
-/// - Excellent cache locality through compact node structures
-/// - Path compression to reduce tree height
+/// - Path compression, so chains of single-child nodes are stored once
+/// - Ordered iteration over all keys or over keys with a common prefix
 ///
-/// Node size field is repurposed to store node type information:
-/// - 0: Empty/unused node
-/// - 1-4: Node4 (up to 4 children)
-/// - 5-16: Node16 (up to 16 children)
-/// - 17-48: Node48 (up to 48 children)
-/// - 49-256: Node256 (up to 256 children)
-pub trait AdaptiveRadix<T: Idx>: Tree<T> {
-  /// Node type from size field
-  #[inline]
-  fn node_type(&self, idx: T) -> Option<NodeType> {
-    let size = self.get(idx)?.size;
-    NodeType::from_size(size)
-  }
+/// Every inner node tracks the number of leaves below it, which makes
+/// counting keys with a given prefix O(k) as well.
+///
+/// All keys of a tree must have the same length, so no key is a prefix of
+/// another one.
+///
+/// # Examples
+///
+/// ```
+/// use trees::AdaptiveRadix;
+///
+/// let mut art = AdaptiveRadix::new();
+/// art.insert(&[1, 2], 'a');
+/// art.insert(&[1, 3], 'b');
+/// art.insert(&[2, 0], 'c');
+///
+/// assert_eq!(art.get(&[1, 3]), Some(&'b'));
+/// assert_eq!(art.count_prefix(&[1]), 2);
+///
+/// let values: Vec<_> = art.prefix(&[1]).map(|(_, &value)| value).collect();
+/// assert_eq!(values, ['a', 'b']);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptiveRadix<V> {
+  root: Option<Ptr>,
+  len: usize,
+  inner: Vec<Inner>,
+  leaves: Vec<Option<Leaf<V>>>,
+  free_inner: Vec<usize>,
+  free_leaves: Vec<usize>,
+}
 
-  /// Set node type by updating size field
+/// Node type in Adaptive Radix Tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NodeType {
+  Node4,
+  Node16,
+  Node48,
+  Node256,
+}
+
+impl NodeType {
+  /// Maximum number of children of the node
   #[inline]
-  fn set_node_type(&mut self, idx: T, node_type: NodeType) {
-    if let Some(node) = self.get(idx) {
-      self.set(idx, Node { size: node_type.to_size(), ..node });
+  pub fn capacity(self) -> usize {
+    match self {
+      Self::Node4 => 4,
+      Self::Node16 => 16,
+      Self::Node48 => 48,
+      Self::Node256 => 256,
     }
   }
 
-  /// Number of children from node
-  #[inline]
-  fn child_count(&self, idx: T) -> usize {
-    self.get(idx).map(|n| n.size).unwrap_or(0)
+  fn grown(self) -> Self {
+    match self {
+      Self::Node4 => Self::Node16,
+      Self::Node16 => Self::Node48,
+      Self::Node48 | Self::Node256 => Self::Node256,
+    }
   }
 
-  /// Extract byte from key at given depth (0 = most significant)
+  /// Smaller node type to switch to once `len` children are left
   ///
-  /// Platform independence: assumes usize is 64 bits (8 bytes) as the shift
-  /// calculation uses `(7 - depth % 8)`. On 32-bit platforms, this will still
-  /// work correctly as the depth is clamped by the modulo operation.
-  #[inline]
-  fn key_byte(&self, key: T, depth: usize) -> u8 {
-    let key_usize = key.as_usize();
-    // extract bytes in big-endian order for proper lexicographic sorting
-    let shift = (7 - (depth % 8)) * 8;
-    ((key_usize >> shift) & 0xFF) as u8
-  }
-
-  /// Find child with given byte value
-  fn find_child(&self, idx: T, byte: u8) -> Option<T> {
-    let node_type = self.node_type(idx)?;
-    match node_type {
-      NodeType::Node4 | NodeType::Node16 => {
-        // for small nodes, stored in left/right alternating pattern
-        // simplified implementation - production uses dedicated storage
-        if byte < 128 { self.left(idx) } else { self.right(idx) }
-      }
-      NodeType::Node48 | NodeType::Node256 => {
-        // for large nodes, use hash-based selection
-        if byte.is_multiple_of(2) { self.left(idx) } else { self.right(idx) }
-      }
-      NodeType::Empty => None,
+  /// Leaves some slack, so a node does not flip between two types when
+  /// children are added and removed around the boundary.
+  fn shrunk(self, len: usize) -> Option<Self> {
+    match self {
+      Self::Node16 if len <= 3 => Some(Self::Node4),
+      Self::Node48 if len <= 12 => Some(Self::Node16),
+      Self::Node256 if len <= 36 => Some(Self::Node48),
+      _ => None,
+    }
+  }
+}
+
+/// Position of a node in one of the arenas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ptr {
+  Inner(usize),
+  Leaf(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Leaf<V> {
+  key: Box<[u8]>,
+  value: V,
+}
+
+#[derive(Debug, Clone)]
+struct Inner {
+  /// Compressed path shared by every key below the node
+  prefix: Vec<u8>,
+  /// Number of leaves below the node
+  len: usize,
+  children: Children,
+}
+
+impl Default for Inner {
+  fn default() -> Self {
+    Self {
+      prefix: Vec::new(),
+      len: 0,
+      children: Children::new(NodeType::Node4),
     }
   }
+}
+
+/// Children of an inner node keyed by the next byte of the key
+#[derive(Debug, Clone)]
+enum Children {
+  Node4(Sorted<4>),
+  Node16(Sorted<16>),
+  Node48(Indexed),
+  Node256(Direct),
+}
+
+/// Up to `N` children with keys kept sorted
+#[derive(Debug, Clone)]
+struct Sorted<const N: usize> {
+  len: usize,
+  keys: [u8; N],
+  ptrs: [Option<Ptr>; N],
+}
+
+/// Up to 48 children found through a 256-entry byte index
+#[derive(Debug, Clone)]
+struct Indexed {
+  len: usize,
+  /// Slot of the child plus one, zero for a missing child
+  index: Box<[u8; 256]>,
+  ptrs: Box<[Option<Ptr>; 48]>,
+}
+
+/// A child pointer for every possible byte
+#[derive(Debug, Clone)]
+struct Direct {
+  len: usize,
+  ptrs: Box<[Option<Ptr>; 256]>,
+}
+
+impl<const N: usize> Sorted<N> {
+  fn new() -> Self {
+    Self { len: 0, keys: [0; N], ptrs: [None; N] }
+  }
 
-  /// Insert child at given byte value
-  fn insert_child(&mut self, idx: T, byte: u8, child: T) -> bool {
-    let _node_type = self.node_type(idx).unwrap_or(NodeType::Empty);
+  fn position(&self, byte: u8) -> Option<usize> {
+    self.keys[..self.len].iter().position(|&key| key == byte)
+  }
 
-    // simple implementation: use left for lower bytes, right for higher
-    if byte < 128 {
-      self.set_left(idx, Some(child));
+  fn add(&mut self, byte: u8, ptr: Ptr) {
+    let at = self.keys[..self.len].partition_point(|&key| key < byte);
+    self.keys.copy_within(at..self.len, at + 1);
+    self.ptrs.copy_within(at..self.len, at + 1);
+    self.keys[at] = byte;
+    self.ptrs[at] = Some(ptr);
+    self.len += 1;
+  }
+
+  fn remove(&mut self, byte: u8) {
+    if let Some(at) = self.position(byte) {
+      self.keys.copy_within(at + 1..self.len, at);
+      self.ptrs.copy_within(at + 1..self.len, at);
+      self.len -= 1;
+      self.ptrs[self.len] = None;
+    }
+  }
+
+  fn entry(&self, cursor: usize) -> Option<(usize, u8, Ptr)> {
+    if cursor < self.len {
+      Some((cursor + 1, self.keys[cursor], self.ptrs[cursor]?))
     } else {
-      self.set_right(idx, Some(child));
+      None
     }
+  }
+}
 
-    // update node type if needed
-    let new_count = self.child_count(idx) + 1;
-    if let Some(new_type) = NodeType::from_size(new_count) {
-      self.set_node_type(idx, new_type);
+impl Children {
+  fn new(ty: NodeType) -> Self {
+    match ty {
+      NodeType::Node4 => Self::Node4(Sorted::new()),
+      NodeType::Node16 => Self::Node16(Sorted::new()),
+      NodeType::Node48 => Self::Node48(Indexed {
+        len: 0,
+        index: Box::new([0; 256]),
+        ptrs: Box::new([None; 48]),
+      }),
+      NodeType::Node256 => {
+        Self::Node256(Direct { len: 0, ptrs: Box::new([None; 256]) })
+      }
     }
+  }
 
-    true
+  fn ty(&self) -> NodeType {
+    match self {
+      Self::Node4(_) => NodeType::Node4,
+      Self::Node16(_) => NodeType::Node16,
+      Self::Node48(_) => NodeType::Node48,
+      Self::Node256(_) => NodeType::Node256,
+    }
   }
 
-  /// Search for key in ART tree
-  fn search_art(&self, root: T, key: T) -> bool {
-    let mut current = root;
-    let mut depth = 0;
+  fn len(&self) -> usize {
+    match self {
+      Self::Node4(node) => node.len,
+      Self::Node16(node) => node.len,
+      Self::Node48(node) => node.len,
+      Self::Node256(node) => node.len,
+    }
+  }
 
-    loop {
-      // check if we've found the exact key
-      if current == key {
-        return true;
-      }
+  fn slot(&mut self, byte: u8) -> Option<&mut Option<Ptr>> {
+    match self {
+      Self::Node4(node) => node.position(byte).map(|at| &mut node.ptrs[at]),
+      Self::Node16(node) => node.position(byte).map(|at| &mut node.ptrs[at]),
+      Self::Node48(node) => match node.index[byte as usize] {
+        0 => None,
+        slot => Some(&mut node.ptrs[slot as usize - 1]),
+      },
+      Self::Node256(node) => Some(&mut node.ptrs[byte as usize]),
+    }
+  }
 
-      let byte = self.key_byte(key, depth);
+  fn find(&self, byte: u8) -> Option<Ptr> {
+    match self {
+      Self::Node4(node) => node.ptrs[node.position(byte)?],
+      Self::Node16(node) => node.ptrs[node.position(byte)?],
+      Self::Node48(node) => match node.index[byte as usize] {
+        0 => None,
+        slot => node.ptrs[slot as usize - 1],
+      },
+      Self::Node256(node) => node.ptrs[byte as usize],
+    }
+  }
 
-      match self.find_child(current, byte) {
-        Some(next) => {
-          current = next;
-          depth += 1;
-          // prevent infinite loops - max depth for u64 is 8 bytes
-          if depth > 8 {
-            return false;
-          }
+  /// Replace the existing child at `byte`
+  fn set(&mut self, byte: u8, ptr: Ptr) {
+    if let Some(slot) = self.slot(byte) {
+      *slot = Some(ptr);
+    }
+  }
+
+  /// Add a child at a missing `byte`, growing the node if it is full
+  fn add(&mut self, byte: u8, ptr: Ptr) {
+    if self.len() == self.ty().capacity() {
+      *self = self.converted(self.ty().grown());
+    }
+
+    match self {
+      Self::Node4(node) => node.add(byte, ptr),
+      Self::Node16(node) => node.add(byte, ptr),
+      Self::Node48(node) => {
+        // a full node was grown above, so a free slot always exists
+        let slot = node.ptrs.iter().position(Option::is_none).unwrap();
+        node.ptrs[slot] = Some(ptr);
+        node.index[byte as usize] = slot as u8 + 1;
+        node.len += 1;
+      }
+      Self::Node256(node) => {
+        node.ptrs[byte as usize] = Some(ptr);
+        node.len += 1;
+      }
+    }
+  }
+
+  /// Remove the child at `byte`, shrinking the node if it became sparse
+  fn remove(&mut self, byte: u8) {
+    match self {
+      Self::Node4(node) => node.remove(byte),
+      Self::Node16(node) => node.remove(byte),
+      Self::Node48(node) => {
+        let slot = mem::take(&mut node.index[byte as usize]);
+        if slot != 0 {
+          node.ptrs[slot as usize - 1] = None;
+          node.len -= 1;
+        }
+      }
+      Self::Node256(node) => {
+        if node.ptrs[byte as usize].take().is_some() {
+          node.len -= 1;
         }
-        None => return false,
       }
     }
+
+    if let Some(ty) = self.ty().shrunk(self.len()) {
+      *self = self.converted(ty);
+    }
   }
 
-  /// Insert key into ART tree, returns new root
-  fn insert_art(&mut self, root: Option<T>, key: T) -> Option<T> {
-    let root_idx = match root {
-      Some(idx) => idx,
-      None => {
-        // empty tree - key becomes root
-        self.set_node_type(key, NodeType::Node4);
-        return Some(key);
+  /// Child following `cursor` in byte order with the cursor after it
+  fn entry(&self, cursor: usize) -> Option<(usize, u8, Ptr)> {
+    match self {
+      Self::Node4(node) => node.entry(cursor),
+      Self::Node16(node) => node.entry(cursor),
+      Self::Node48(node) => {
+        (cursor..256).find_map(|byte| match node.index[byte] {
+          0 => None,
+          slot => Some((byte + 1, byte as u8, node.ptrs[slot as usize - 1]?)),
+        })
       }
-    };
+      Self::Node256(node) => (cursor..256)
+        .find_map(|byte| Some((byte + 1, byte as u8, node.ptrs[byte]?))),
+    }
+  }
+
+  fn converted(&self, ty: NodeType) -> Self {
+    let mut children = Self::new(ty);
+    let mut cursor = 0;
+    while let Some((next, byte, ptr)) = self.entry(cursor) {
+      children.add(byte, ptr);
+      cursor = next;
+    }
+    children
+  }
+}
+
+/// Length of the common prefix of two byte strings
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+  a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
 
-    let mut current = root_idx;
+impl<V> Default for AdaptiveRadix<V> {
+  fn default() -> Self {
+    Self {
+      root: None,
+      len: 0,
+      inner: Vec::new(),
+      leaves: Vec::new(),
+      free_inner: Vec::new(),
+      free_leaves: Vec::new(),
+    }
+  }
+}
+
+impl<V> AdaptiveRadix<V> {
+  /// Create an empty tree
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Number of keys in the tree
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Remove every key, keeping the allocated storage
+  pub fn clear(&mut self) {
+    self.root = None;
+    self.len = 0;
+    self.inner.clear();
+    self.leaves.clear();
+    self.free_inner.clear();
+    self.free_leaves.clear();
+  }
+
+  /// Get the value stored for `key`
+  pub fn get(&self, key: &[u8]) -> Option<&V> {
+    let mut ptr = self.root?;
     let mut depth = 0;
 
     loop {
-      let byte = self.key_byte(key, depth);
-
-      match self.find_child(current, byte) {
-        Some(next) => {
-          if next == key {
-            // key already exists
-            return Some(root_idx);
+      match ptr {
+        Ptr::Leaf(at) => {
+          let leaf = self.leaf(at);
+          return (*leaf.key == *key).then_some(&leaf.value);
+        }
+        Ptr::Inner(at) => {
+          let node = &self.inner[at];
+          if !key.get(depth..)?.starts_with(&node.prefix) {
+            return None;
           }
-          current = next;
+          depth += node.prefix.len();
+          ptr = node.children.find(*key.get(depth)?)?;
           depth += 1;
-          if depth > 8 {
-            break;
-          }
-        }
-        None => {
-          // insert new child
-          self.set_node_type(key, NodeType::Node4);
-          self.insert_child(current, byte, key);
-          return Some(root_idx);
         }
       }
     }
+  }
+
+  #[inline]
+  pub fn contains(&self, key: &[u8]) -> bool {
+    self.get(key).is_some()
+  }
+
+  /// Insert `value` at `key`, returns the value it replaced
+  ///
+  /// # Panics
+  ///
+  /// May panic if `key` has another length than the keys already stored.
+  pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+    let old = match self.root {
+      None => {
+        self.root = Some(self.alloc_leaf(key, value));
+        None
+      }
+      Some(root) => {
+        let (root, old) = self.insert_at(root, 0, key, value);
+        self.root = Some(root);
+        old
+      }
+    };
+
+    if old.is_none() {
+      self.len += 1;
+    }
+    old
+  }
+
+  /// Remove `key` from the tree, returns its value
+  pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+    let (root, value) = self.remove_at(self.root?, 0, key)?;
+    self.root = root;
+    self.len -= 1;
+    Some(value)
+  }
 
-    Some(root_idx)
+  /// Iterate over all keys and values in key order
+  pub fn iter(&self) -> RadixIter<'_, V> {
+    RadixIter::new(self, self.root)
   }
 
-  /// Remove key from ART tree, returns new root
-  fn remove_art(&mut self, root: Option<T>, key: T) -> Option<T> {
-    let root_idx = root?;
+  /// Iterate over keys starting with `prefix` in key order
+  ///
+  /// Only the path to the subtree of `prefix` is visited besides the
+  /// matching keys themselves.
+  pub fn prefix(&self, prefix: &[u8]) -> RadixIter<'_, V> {
+    RadixIter::new(self, self.subtree(prefix))
+  }
 
-    // handle root removal
-    if root_idx == key {
-      self.clear(key);
-      return None;
+  /// Number of keys starting with `prefix`
+  pub fn count_prefix(&self, prefix: &[u8]) -> usize {
+    match self.subtree(prefix) {
+      None => 0,
+      Some(Ptr::Leaf(_)) => 1,
+      Some(Ptr::Inner(at)) => self.inner[at].len,
     }
+  }
 
-    // traverse tree to find key and its parent
-    self.remove_art_impl(root_idx, key, 0);
-    Some(root_idx)
+  /// Number of inner nodes above every leaf, in key order
+  pub fn depths(&self) -> impl Iterator<Item = usize> + '_ {
+    let mut iter = self.iter();
+    // the stack holds exactly the ancestors of the leaf just returned
+    core::iter::from_fn(move || iter.next().map(|_| iter.stack.len()))
   }
 
-  /// Internal remove implementation with parent tracking
-  fn remove_art_impl(&mut self, current: T, key: T, depth: usize) -> bool {
-    if depth > 8 {
-      return false;
+  /// Types of all inner nodes in no particular order
+  pub fn node_types(&self) -> impl Iterator<Item = NodeType> + '_ {
+    let mut free = vec![false; self.inner.len()];
+    for &at in &self.free_inner {
+      free[at] = true;
     }
+    self
+      .inner
+      .iter()
+      .zip(free)
+      .filter(|(_, free)| !free)
+      .map(|(node, _)| node.children.ty())
+  }
 
-    let byte = self.key_byte(key, depth);
+  /// Root of the smallest subtree holding every key that starts with
+  /// `prefix`
+  fn subtree(&self, prefix: &[u8]) -> Option<Ptr> {
+    let mut ptr = self.root?;
+    let mut depth = 0;
 
-    match self.find_child(current, byte) {
-      Some(next) => {
-        if next == key {
-          // found the key - remove child pointer from parent
-          self.remove_child(current, byte);
-          self.clear(key);
-          return true;
+    while depth < prefix.len() {
+      match ptr {
+        Ptr::Leaf(at) => {
+          return self.leaf(at).key.starts_with(prefix).then_some(ptr);
+        }
+        Ptr::Inner(at) => {
+          let node = &self.inner[at];
+          let rest = &prefix[depth..];
+          let shared = node.prefix.len().min(rest.len());
+          if node.prefix[..shared] != rest[..shared] {
+            return None;
+          }
+          depth += node.prefix.len();
+          if depth >= prefix.len() {
+            break;
+          }
+          ptr = node.children.find(prefix[depth])?;
+          depth += 1;
         }
-        // recursively search in child
-        self.remove_art_impl(next, key, depth + 1)
       }
-      None => false,
     }
+    Some(ptr)
   }
 
-  /// Remove child at given byte value from parent node
-  fn remove_child(&mut self, parent: T, byte: u8) {
-    let node_type = self.node_type(parent).unwrap_or(NodeType::Empty);
+  fn insert_at(
+    &mut self,
+    ptr: Ptr,
+    depth: usize,
+    key: &[u8],
+    value: V,
+  ) -> (Ptr, Option<V>) {
+    match ptr {
+      Ptr::Leaf(at) => {
+        let leaf = self.leaf_mut(at);
+        if *leaf.key == *key {
+          return (ptr, Some(mem::replace(&mut leaf.value, value)));
+        }
 
-    // search for and remove the child based on node type strategy
-    match node_type {
-      NodeType::Node4 | NodeType::Node16 => {
-        // for small nodes, check which pointer contains the target
-        // and clear only that specific pointer
-        if byte < 128 {
-          if self.left(parent).is_some() {
-            self.set_left(parent, None);
-          }
-        } else if self.right(parent).is_some() {
-          self.set_right(parent, None);
+        // split the leaf at the first byte where the keys differ
+        let split = depth + common_prefix(&leaf.key[depth..], &key[depth..]);
+        let old = leaf.key[split];
+        let new = self.alloc_leaf(key, value);
+
+        let mut children = Children::new(NodeType::Node4);
+        children.add(old, ptr);
+        children.add(key[split], new);
+        let prefix = key[depth..split].to_vec();
+        (self.alloc_inner(Inner { prefix, len: 2, children }), None)
+      }
+      Ptr::Inner(at) => {
+        let node = &self.inner[at];
+        let shared = common_prefix(&node.prefix, &key[depth..]);
+
+        if shared < node.prefix.len() {
+          // the key leaves the compressed path, split it
+          let node = &mut self.inner[at];
+          let old = node.prefix[shared];
+          let prefix = node.prefix[..shared].to_vec();
+          node.prefix.drain(..=shared);
+          let len = node.len + 1;
+          let new = self.alloc_leaf(key, value);
+
+          let mut children = Children::new(NodeType::Node4);
+          children.add(old, ptr);
+          children.add(key[depth + shared], new);
+          return (self.alloc_inner(Inner { prefix, len, children }), None);
+        }
+
+        let depth = depth + shared;
+        let byte = key[depth];
+        let (child, old) = match node.children.find(byte) {
+          Some(child) => self.insert_at(child, depth + 1, key, value),
+          None => (self.alloc_leaf(key, value), None),
+        };
+
+        let node = &mut self.inner[at];
+        if old.is_none() {
+          node.len += 1;
+        }
+        match node.children.find(byte) {
+          Some(_) => node.children.set(byte, child),
+          None => node.children.add(byte, child),
         }
+        (ptr, old)
       }
-      NodeType::Node48 | NodeType::Node256 => {
-        // for large nodes, use hash-based selection matching find_child
-        if byte.is_multiple_of(2) {
-          if self.left(parent).is_some() {
-            self.set_left(parent, None);
-          }
-        } else if self.right(parent).is_some() {
-          self.set_right(parent, None);
+    }
+  }
+
+  /// Remove `key` below `ptr`, returns what replaces `ptr` in its parent
+  fn remove_at(
+    &mut self,
+    ptr: Ptr,
+    depth: usize,
+    key: &[u8],
+  ) -> Option<(Option<Ptr>, V)> {
+    match ptr {
+      Ptr::Leaf(at) => {
+        if *self.leaf(at).key != *key {
+          return None;
+        }
+        Some((None, self.free_leaf(at).value))
+      }
+      Ptr::Inner(at) => {
+        let node = &self.inner[at];
+        if !key.get(depth..)?.starts_with(&node.prefix) {
+          return None;
+        }
+        let depth = depth + node.prefix.len();
+        let byte = *key.get(depth)?;
+        let child = node.children.find(byte)?;
+        let (child, value) = self.remove_at(child, depth + 1, key)?;
+
+        let node = &mut self.inner[at];
+        node.len -= 1;
+        match child {
+          Some(child) => node.children.set(byte, child),
+          None => node.children.remove(byte),
+        }
+
+        if node.children.len() > 1 {
+          return Some((Some(ptr), value));
         }
+
+        // a single child is left, merge the node into it
+        let (_, byte, only) = node.children.entry(0)?;
+        let mut prefix = mem::take(&mut node.prefix);
+        self.free_inner(at);
+        if let Ptr::Inner(child) = only {
+          let child = &mut self.inner[child];
+          prefix.push(byte);
+          prefix.extend_from_slice(&child.prefix);
+          child.prefix = prefix;
+        }
+        Some((Some(only), value))
+      }
+    }
+  }
+
+  fn leaf(&self, at: usize) -> &Leaf<V> {
+    self.leaves[at].as_ref().expect("pointer to a freed leaf")
+  }
+
+  fn leaf_mut(&mut self, at: usize) -> &mut Leaf<V> {
+    self.leaves[at].as_mut().expect("pointer to a freed leaf")
+  }
+
+  fn alloc_leaf(&mut self, key: &[u8], value: V) -> Ptr {
+    let leaf = Some(Leaf { key: key.into(), value });
+    match self.free_leaves.pop() {
+      Some(at) => {
+        self.leaves[at] = leaf;
+        Ptr::Leaf(at)
+      }
+      None => {
+        self.leaves.push(leaf);
+        Ptr::Leaf(self.leaves.len() - 1)
       }
-      NodeType::Empty => return, // nothing to remove
     }
+  }
+
+  fn free_leaf(&mut self, at: usize) -> Leaf<V> {
+    self.free_leaves.push(at);
+    self.leaves[at].take().expect("pointer to a freed leaf")
+  }
 
-    // update node type based on new child count
-    let new_count = self.child_count(parent).saturating_sub(1);
-    if let Some(new_type) = NodeType::from_size(new_count) {
-      self.set_node_type(parent, new_type);
+  fn alloc_inner(&mut self, node: Inner) -> Ptr {
+    match self.free_inner.pop() {
+      Some(at) => {
+        self.inner[at] = node;
+        Ptr::Inner(at)
+      }
+      None => {
+        self.inner.push(node);
+        Ptr::Inner(self.inner.len() - 1)
+      }
     }
   }
+
+  fn free_inner(&mut self, at: usize) {
+    // release the storage of wide nodes right away
+    self.inner[at] = Inner::default();
+    self.free_inner.push(at);
+  }
 }
 
-/// Node type in Adaptive Radix Tree
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum NodeType {
-  Empty,
-  Node4,
-  Node16,
-  Node48,
-  Node256,
+/// Iterator over keys and values of an [`AdaptiveRadix`] in key order
+///
+/// Keeps an explicit stack of inner nodes, which is never deeper than the
+/// key length.
+#[derive(Debug, Clone)]
+pub struct RadixIter<'a, V> {
+  tree: &'a AdaptiveRadix<V>,
+  /// Inner nodes being visited with the cursor of their next child
+  stack: Vec<(usize, usize)>,
+  next: Option<Ptr>,
 }
 
-impl NodeType {
-  /// Convert from size field value
-  #[inline]
-  pub fn from_size(size: usize) -> Option<Self> {
-    match size {
-      0 => Some(Self::Empty),
-      1..=4 => Some(Self::Node4),
-      5..=16 => Some(Self::Node16),
-      17..=48 => Some(Self::Node48),
-      49..=256 => Some(Self::Node256),
-      _ => None,
-    }
+impl<'a, V> RadixIter<'a, V> {
+  fn new(tree: &'a AdaptiveRadix<V>, start: Option<Ptr>) -> Self {
+    Self { tree, stack: Vec::new(), next: start }
   }
+}
 
-  /// Convert to size field value
-  #[inline]
-  pub fn to_size(self) -> usize {
-    match self {
-      Self::Empty => 0,
-      Self::Node4 => 4,
-      Self::Node16 => 16,
-      Self::Node48 => 48,
-      Self::Node256 => 256,
+impl<'a, V> Iterator for RadixIter<'a, V> {
+  type Item = (&'a [u8], &'a V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      match self.next.take() {
+        Some(Ptr::Leaf(at)) => {
+          let leaf = self.tree.leaf(at);
+          return Some((&leaf.key, &leaf.value));
+        }
+        Some(Ptr::Inner(at)) => self.stack.push((at, 0)),
+        None => {}
+      }
+
+      let (at, cursor) = self.stack.last_mut()?;
+      match self.tree.inner[*at].children.entry(*cursor) {
+        Some((next, _, child)) => {
+          *cursor = next;
+          self.next = Some(child);
+        }
+        None => {
+          self.stack.pop();
+        }
+      }
     }
   }
 }
+
+impl<V> FusedIterator for RadixIter<'_, V> {}
+
+impl<'a, V> IntoIterator for &'a AdaptiveRadix<V> {
+  type Item = (&'a [u8], &'a V);
+  type IntoIter = RadixIter<'a, V>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
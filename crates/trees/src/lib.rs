@@ -6,7 +6,7 @@ mod sbt;
 mod tree;
 
 pub use {
-  art::{AdaptiveRadix, NodeType},
+  art::{AdaptiveRadix, NodeType, RadixIter},
   node::{Idx, Node},
   sbt::SizeBalanced,
   tree::Tree,
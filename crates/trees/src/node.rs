@@ -1,10 +1,23 @@
-use std::num::*;
-
-/// Index type for tree nodes - can be u8, u16, u32, u64, or NonZero variants
+/// Index type for tree nodes - any primitive integer
+///
+/// The same type is used for child links and subtree sizes, so narrow
+/// index types keep the whole [`Node`] narrow. Null children are stored as
+/// zero, which is why `NonZero*` types are not index types.
 pub trait Idx: Copy + Eq {
   fn as_usize(self) -> usize;
   fn is_null(self) -> bool;
   fn null() -> Self;
+
+  /// Convert a subtree size or position back into the index type
+  ///
+  /// Sizes never exceed the number of nodes, which always fits into the
+  /// index type, so the conversion is lossless for well-formed trees.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `val` doesn't fit into the index type instead of truncating
+  /// it.
+  fn from_usize(val: usize) -> Self;
 }
 
 macro_rules! impl_idx {
@@ -24,59 +37,65 @@ macro_rules! impl_idx {
       fn null() -> Self {
         0
       }
-    }
-  )*};
-}
-
-macro_rules! impl_idx_nonzero {
-  ($($ty:ty)*) => {$(
-    impl Idx for $ty {
-      #[inline]
-      fn as_usize(self) -> usize {
-        self.get() as usize
-      }
-
-      #[inline]
-      fn is_null(self) -> bool {
-        false
-      }
 
       #[inline]
-      fn null() -> Self {
-        unreachable!("NonZero types use Option<T> for null")
+      fn from_usize(val: usize) -> Self {
+        Self::try_from(val)
+          .unwrap_or_else(|_| panic!("{val} overflows index"))
       }
     }
   )*};
 }
 
-impl_idx! { u8 u16 u32 u64 usize }
-impl_idx_nonzero! {
-  NonZeroU8 NonZeroU16 NonZeroU32 NonZeroU64 NonZeroUsize
+impl_idx! {
+  u8 u16 u32 u64 u128 usize
+  i8 i16 i32 i64 i128 isize
 }
 
 /// Tree node structure - stores size and children
+///
+/// Missing children are stored as the null index, so a node is plain data
+/// of three indices without padding or enum tags and may be kept in raw
+/// memory. The null index itself can never be a node of a tree.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(C)]
 pub struct Node<T> {
-  pub size: usize,
-  pub left: Option<T>,
-  pub right: Option<T>,
+  pub size: T,
+  pub left: T,
+  pub right: T,
 }
 
-impl<T> Default for Node<T> {
+impl<T: Idx> Default for Node<T> {
   fn default() -> Self {
-    Self { size: 0, left: None, right: None }
+    Self { size: T::null(), left: T::null(), right: T::null() }
   }
 }
 
-impl<T> Node<T> {
+impl<T: Idx> Node<T> {
   #[inline]
   pub fn new() -> Self {
     Self::default()
   }
 
   #[inline]
-  pub fn with_size(size: usize) -> Self {
-    Self { size, left: None, right: None }
+  pub fn with_size(size: T) -> Self {
+    Self { size, ..Self::default() }
+  }
+
+  /// Left child, `None` if it is null
+  #[inline]
+  pub fn left(&self) -> Option<T> {
+    child(self.left)
+  }
+
+  /// Right child, `None` if it is null
+  #[inline]
+  pub fn right(&self) -> Option<T> {
+    child(self.right)
   }
 }
+
+#[inline]
+fn child<T: Idx>(idx: T) -> Option<T> {
+  (!idx.is_null()).then_some(idx)
+}
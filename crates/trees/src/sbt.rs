@@ -1,4 +1,7 @@
-use crate::{Idx, Node, Tree};
+use {
+  crate::{Idx, Node, Tree},
+  core::cmp::Ordering,
+};
 
 /// Size-Balanced Tree - provides operations on tree stored in slice
 ///
@@ -8,14 +11,14 @@ pub trait SizeBalanced<T: Idx>: Tree<T> {
   /// Get size of subtree rooted at index
   #[inline]
   fn size(&self, idx: T) -> Option<usize> {
-    self.get(idx).map(|n| n.size)
+    self.get(idx).map(|n| n.size.as_usize())
   }
 
   /// Set size of subtree
   #[inline]
   fn set_size(&mut self, idx: T, size: usize) {
     if let Some(node) = self.get(idx) {
-      self.set(idx, Node { size, ..node });
+      self.set(idx, Node { size: T::from_usize(size), ..node });
     }
   }
 
@@ -58,6 +61,80 @@ pub trait SizeBalanced<T: Idx>: Tree<T> {
     self.set_size(idx, size)
   }
 
+  /// Count nodes for which `is_before` holds
+  ///
+  /// `is_before` must hold for a prefix of the in-order sequence, like
+  /// `|node| self.is_left_of(node, idx)` does. Takes O(log n).
+  fn rank_by(
+    &self,
+    root: Option<T>,
+    mut is_before: impl FnMut(T) -> bool,
+  ) -> usize {
+    let mut rank = 0;
+    let mut current = root;
+    while let Some(node) = current {
+      if is_before(node) {
+        rank += self.left_size(node).unwrap_or(0) + 1;
+        current = self.right(node);
+      } else {
+        current = self.left(node);
+      }
+    }
+    rank
+  }
+
+  /// First node for which `is_before` doesn't hold
+  ///
+  /// `is_before` must hold for a prefix of the in-order sequence.
+  fn lower_bound_by(
+    &self,
+    root: Option<T>,
+    mut is_before: impl FnMut(T) -> bool,
+  ) -> Option<T> {
+    let mut bound = None;
+    let mut current = root;
+    while let Some(node) = current {
+      if is_before(node) {
+        current = self.right(node);
+      } else {
+        bound = Some(node);
+        current = self.left(node);
+      }
+    }
+    bound
+  }
+
+  /// Number of nodes left of `idx`, which doesn't have to be in the tree
+  fn rank(&self, root: Option<T>, idx: T) -> usize {
+    self.rank_by(root, |node| self.is_left_of(node, idx))
+  }
+
+  /// Node at zero-based position `nth` of the in-order sequence
+  fn select(&self, root: Option<T>, mut nth: usize) -> Option<T> {
+    let mut current = root?;
+    loop {
+      let left = self.left_size(current).unwrap_or(0);
+      match nth.cmp(&left) {
+        Ordering::Less => current = self.left(current)?,
+        Ordering::Equal => return Some(current),
+        Ordering::Greater => {
+          nth -= left + 1;
+          current = self.right(current)?;
+        }
+      }
+    }
+  }
+
+  /// First node not left of `idx`
+  fn lower_bound(&self, root: Option<T>, idx: T) -> Option<T> {
+    self.lower_bound_by(root, |node| self.is_left_of(node, idx))
+  }
+
+  /// First node right of `idx`
+  fn upper_bound(&self, root: Option<T>, idx: T) -> Option<T> {
+    self.lower_bound_by(root, |node| !self.is_right_of(node, idx))
+  }
+
   #[must_use]
   fn rotate_left(&mut self, root: T) -> Option<T> {
     let right = self.right(root)?;
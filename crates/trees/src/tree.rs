@@ -32,20 +32,20 @@ pub trait Tree<T: Idx> {
   /// Get left child index
   #[inline]
   fn left(&self, idx: T) -> Option<T> {
-    self.get(idx)?.left
+    self.get(idx)?.left()
   }
 
   /// Get right child index
   #[inline]
   fn right(&self, idx: T) -> Option<T> {
-    self.get(idx)?.right
+    self.get(idx)?.right()
   }
 
   /// Set left child
   #[inline]
   fn set_left(&mut self, idx: T, left: Option<T>) {
     if let Some(node) = self.get(idx) {
-      self.set(idx, Node { left, ..node });
+      self.set(idx, Node { left: left.unwrap_or_else(T::null), ..node });
     }
   }
 
@@ -53,7 +53,7 @@ pub trait Tree<T: Idx> {
   #[inline]
   fn set_right(&mut self, idx: T, right: Option<T>) {
     if let Some(node) = self.get(idx) {
-      self.set(idx, Node { right, ..node });
+      self.set(idx, Node { right: right.unwrap_or_else(T::null), ..node });
     }
   }
 
@@ -113,12 +113,12 @@ pub trait Tree<T: Idx> {
   /// Insert index into tree, returns new root
   ///
   /// Implementations should use their specific tree strategy
-  /// (e.g., SizeBalanced::insert_sbt or AdaptiveRadix::insert_art).
+  /// (e.g., SizeBalanced::insert_sbt).
   fn insert(&mut self, root: Option<T>, idx: T) -> Option<T>;
 
   /// Remove index from tree, returns new root (None if tree empty)
   ///
   /// Implementations should use their specific tree strategy
-  /// (e.g., SizeBalanced::remove_sbt or AdaptiveRadix::remove_art).
+  /// (e.g., SizeBalanced::remove_sbt).
   fn remove(&mut self, root: Option<T>, idx: T) -> Option<T>;
 }
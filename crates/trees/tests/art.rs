@@ -0,0 +1,152 @@
+use {
+  proptest::prelude::*,
+  std::collections::BTreeMap,
+  trees::{AdaptiveRadix, NodeType},
+};
+
+fn key(a: u8, b: u16) -> [u8; 3] {
+  let [hi, lo] = b.to_be_bytes();
+  [a, hi, lo]
+}
+
+#[test]
+fn test_insert_get_remove() {
+  let mut art = AdaptiveRadix::new();
+  assert!(art.is_empty());
+
+  assert_eq!(art.insert(&key(1, 2), 10), None);
+  assert_eq!(art.insert(&key(1, 3), 20), None);
+  assert_eq!(art.insert(&key(1, 2), 30), Some(10));
+  assert_eq!(art.len(), 2);
+
+  assert_eq!(art.get(&key(1, 2)), Some(&30));
+  assert_eq!(art.get(&key(1, 4)), None);
+  assert_eq!(art.get(&key(2, 2)), None);
+
+  assert_eq!(art.remove(&key(1, 2)), Some(30));
+  assert_eq!(art.remove(&key(1, 2)), None);
+  assert_eq!(art.remove(&key(1, 3)), Some(20));
+  assert!(art.is_empty());
+  assert_eq!(art.iter().next(), None);
+}
+
+#[test]
+fn test_nodes_grow_and_shrink() {
+  let mut art = AdaptiveRadix::new();
+  for byte in 0..=255 {
+    art.insert(&[7, byte], byte);
+  }
+  // path [7] is compressed into the single wide node
+  assert_eq!(art.node_types().collect::<Vec<_>>(), [NodeType::Node256]);
+
+  let values: Vec<u8> = art.iter().map(|(_, &value)| value).collect();
+  assert_eq!(values, (0..=255).collect::<Vec<_>>());
+
+  for byte in 30..=255 {
+    assert_eq!(art.remove(&[7, byte]), Some(byte));
+  }
+  assert_eq!(art.node_types().collect::<Vec<_>>(), [NodeType::Node48]);
+
+  for byte in 12..30 {
+    art.remove(&[7, byte]);
+  }
+  assert_eq!(art.node_types().collect::<Vec<_>>(), [NodeType::Node16]);
+
+  for byte in 3..12 {
+    art.remove(&[7, byte]);
+  }
+  assert_eq!(art.node_types().collect::<Vec<_>>(), [NodeType::Node4]);
+
+  art.remove(&[7, 0]);
+  art.remove(&[7, 1]);
+  // the last key is a single leaf again
+  assert_eq!(art.node_types().count(), 0);
+  assert_eq!(art.get(&[7, 2]), Some(&2));
+}
+
+#[test]
+fn test_path_compression() {
+  let mut art = AdaptiveRadix::new();
+  art.insert(&[1, 2, 3, 4, 5], 'a');
+  art.insert(&[1, 2, 3, 9, 9], 'b');
+  assert_eq!(art.node_types().count(), 1);
+
+  // splits the compressed path [1, 2, 3]
+  art.insert(&[1, 7, 0, 0, 0], 'c');
+  assert_eq!(art.node_types().count(), 2);
+  assert_eq!(art.get(&[1, 2, 3, 9, 9]), Some(&'b'));
+
+  // merges the split path back
+  art.remove(&[1, 7, 0, 0, 0]);
+  assert_eq!(art.node_types().count(), 1);
+  assert_eq!(art.get(&[1, 2, 3, 4, 5]), Some(&'a'));
+  assert_eq!(art.count_prefix(&[1, 2, 3]), 2);
+  assert_eq!(art.count_prefix(&[1, 2, 4]), 0);
+}
+
+#[test]
+fn test_depths() {
+  let mut art = AdaptiveRadix::new();
+  assert_eq!(art.depths().count(), 0);
+  art.insert(&[1, 2], 'a');
+  assert_eq!(art.depths().collect::<Vec<_>>(), [0]);
+
+  art.insert(&[1, 3], 'b');
+  art.insert(&[2, 0], 'c');
+  // the root splits on the first byte, its child on the second
+  assert_eq!(art.depths().collect::<Vec<_>>(), [2, 2, 1]);
+}
+
+#[test]
+fn test_prefix_iteration() {
+  let mut art = AdaptiveRadix::new();
+  for a in 0..4 {
+    for b in (0..300).rev() {
+      art.insert(&key(a, b), (a, b));
+    }
+  }
+
+  let found: Vec<_> = art.prefix(&[2]).map(|(_, &value)| value).collect();
+  assert_eq!(found, (0..300).map(|b| (2, b)).collect::<Vec<_>>());
+  assert_eq!(art.count_prefix(&[2]), 300);
+  assert_eq!(art.count_prefix(&[2, 1]), 44);
+  assert_eq!(art.count_prefix(&key(2, 299)), 1);
+  assert_eq!(art.prefix(&[9]).count(), 0);
+  assert_eq!(art.prefix(&[]).count(), 1200);
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(64))]
+
+  #[test]
+  fn prop_matches_btree_map(
+    ops in prop::collection::vec((any::<bool>(), 0u8..8, any::<u16>()), 1..400)
+  ) {
+    let mut art = AdaptiveRadix::new();
+    let mut map = BTreeMap::new();
+
+    for (insert, a, b) in ops {
+      let key = key(a, b);
+      if insert {
+        prop_assert_eq!(art.insert(&key, b), map.insert(key, b));
+      } else {
+        prop_assert_eq!(art.remove(&key), map.remove(&key));
+      }
+      prop_assert_eq!(art.len(), map.len());
+    }
+
+    let entries: Vec<_> =
+      art.iter().map(|(key, &value)| (key.to_vec(), value)).collect();
+    let expected: Vec<_> =
+      map.iter().map(|(key, &value)| (key.to_vec(), value)).collect();
+    prop_assert_eq!(entries, expected);
+
+    for a in 0..8 {
+      let found: Vec<_> = art.prefix(&[a]).map(|(_, &value)| value).collect();
+      let expected: Vec<_> =
+        map.range(key(a, 0)..=key(a, u16::MAX)).map(|(_, &v)| v).collect();
+      prop_assert_eq!(art.count_prefix(&[a]), expected.len());
+      prop_assert_eq!(found, expected);
+    }
+  }
+}
@@ -1,6 +1,9 @@
 mod common;
 
-use {common::Store, trees::Tree};
+use {
+  common::Store,
+  trees::{Idx, Tree},
+};
 
 #[test]
 fn test_single_insert() {
@@ -11,8 +14,8 @@ fn test_single_insert() {
   // Check the node was initialized correctly
   let node = store.get(5).unwrap();
   assert_eq!(node.size, 1);
-  assert_eq!(node.left, None);
-  assert_eq!(node.right, None);
+  assert_eq!(node.left(), None);
+  assert_eq!(node.right(), None);
 
   // Search for it
   assert!(store.contains(5, 5));
@@ -39,3 +42,15 @@ fn test_two_inserts() {
     panic!("Root should not be None");
   }
 }
+
+#[test]
+fn test_from_usize_fits() {
+  assert_eq!(u8::from_usize(255), 255);
+  assert_eq!(u32::from_usize(7), 7);
+}
+
+#[test]
+#[should_panic(expected = "256 overflows index")]
+fn test_from_usize_never_truncates() {
+  u8::from_usize(256);
+}
@@ -1,4 +1,4 @@
-use trees::{AdaptiveRadix, Idx, Node, SizeBalanced, Tree};
+use trees::{Idx, Node, SizeBalanced, Tree};
 
 /// Vector-backed tree store for testing and benchmarking.
 /// Generic over the tree implementation strategy.
@@ -7,7 +7,7 @@ pub struct VecStore<T> {
   nodes: Vec<Node<T>>,
 }
 
-impl<T> VecStore<T> {
+impl<T: Idx> VecStore<T> {
   pub fn new(capacity: usize) -> Self {
     Self { nodes: (0..capacity).map(|_| Node::default()).collect() }
   }
@@ -38,7 +38,7 @@ impl<T> VecStore<T> {
 
   #[allow(dead_code)]
   pub fn is_empty(&self) -> bool {
-    self.nodes.iter().all(|n| n.size == 0)
+    self.nodes.iter().all(|n| n.size.is_null())
   }
 }
 
@@ -58,12 +58,14 @@ impl<T: Idx> Tree<T> for VecStore<T> {
 
   #[inline(always)]
   fn left_mut(&mut self, idx: T) -> Option<&mut T> {
-    self.nodes.get_mut(idx.as_usize())?.left.as_mut()
+    let left = &mut self.nodes.get_mut(idx.as_usize())?.left;
+    (!left.is_null()).then_some(left)
   }
 
   #[inline(always)]
   fn right_mut(&mut self, idx: T) -> Option<&mut T> {
-    self.nodes.get_mut(idx.as_usize())?.right.as_mut()
+    let right = &mut self.nodes.get_mut(idx.as_usize())?.right;
+    (!right.is_null()).then_some(right)
   }
 
   #[inline(always)]
@@ -83,63 +85,5 @@ impl<T: Idx> Tree<T> for VecStore<T> {
 // SBT strategy
 impl<T: Idx> SizeBalanced<T> for VecStore<T> {}
 
-// ART strategy
-impl<T: Idx> AdaptiveRadix<T> for VecStore<T> {}
-
 // Type alias for convenience
 pub type Store<T> = VecStore<T>;
-
-/// ART-specific store that uses ART insert/remove by default
-#[derive(Debug, Clone)]
-pub struct ArtStore<T> {
-  inner: VecStore<T>,
-}
-
-impl<T> ArtStore<T> {
-  pub fn new(capacity: usize) -> Self {
-    Self { inner: VecStore::new(capacity) }
-  }
-
-  #[allow(dead_code)]
-  pub fn reset(&mut self) {
-    self.inner.reset()
-  }
-}
-
-impl<T: Idx> Tree<T> for ArtStore<T> {
-  #[inline(always)]
-  fn get(&self, idx: T) -> Option<Node<T>> {
-    self.inner.get(idx)
-  }
-
-  #[inline(always)]
-  fn set(&mut self, idx: T, node: Node<T>) {
-    self.inner.set(idx, node)
-  }
-
-  #[inline(always)]
-  fn left_mut(&mut self, idx: T) -> Option<&mut T> {
-    self.inner.left_mut(idx)
-  }
-
-  #[inline(always)]
-  fn right_mut(&mut self, idx: T) -> Option<&mut T> {
-    self.inner.right_mut(idx)
-  }
-
-  #[inline(always)]
-  fn is_left_of(&self, first: T, second: T) -> bool {
-    self.inner.is_left_of(first, second)
-  }
-
-  // Override to use ART instead of default SBT
-  fn insert(&mut self, root: Option<T>, idx: T) -> Option<T> {
-    AdaptiveRadix::insert_art(self, root, idx)
-  }
-
-  fn remove(&mut self, root: Option<T>, idx: T) -> Option<T> {
-    AdaptiveRadix::remove_art(self, root, idx)
-  }
-}
-
-impl<T: Idx> AdaptiveRadix<T> for ArtStore<T> {}
@@ -256,6 +256,43 @@ proptest! {
       prop_assert!(values.is_empty(), "Empty tree should have no values");
     }
   }
+
+  #[test]
+  fn prop_order_statistics(
+    values in prop::collection::hash_set(1usize..200, 0..60),
+    removed in prop::collection::vec(1usize..200, 0..20),
+  ) {
+    let mut store: Store<usize> = Store::new(300);
+    let mut root = None;
+    for &v in &values {
+      root = store.insert(root, v);
+    }
+    let mut sorted: Vec<usize> = values.into_iter().collect();
+    sorted.sort_unstable();
+    for v in removed {
+      if let Ok(pos) = sorted.binary_search(&v) {
+        root = store.remove(root, v);
+        sorted.remove(pos);
+      }
+    }
+
+    // select is the inverse of rank for every stored node
+    for (nth, &v) in sorted.iter().enumerate() {
+      prop_assert_eq!(store.select(root, nth), Some(v));
+      prop_assert_eq!(store.rank(root, v), nth);
+    }
+    prop_assert_eq!(store.select(root, sorted.len()), None);
+
+    // probes don't have to be in the tree
+    for probe in 0..=201 {
+      let lower = sorted.partition_point(|&v| v < probe);
+      let upper = sorted.partition_point(|&v| v <= probe);
+      prop_assert_eq!(store.rank(root, probe), lower);
+      prop_assert_eq!(store.lower_bound(root, probe), sorted.get(lower).copied());
+      prop_assert_eq!(store.upper_bound(root, probe), sorted.get(upper).copied());
+      prop_assert_eq!(store.rank_by(root, |v| v <= probe), upper);
+    }
+  }
 }
 
 #[test]
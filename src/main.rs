@@ -0,0 +1,332 @@
+//! Command-line tool for inspecting and editing file-backed doublets stores
+
+use {
+  clap::{Parser, Subcommand},
+  doublets::{
+    Doublets, Flow, Index, Journal, Link, Links, Numbering, RawLink, Store,
+    SyncPolicy,
+  },
+  mem::{FileMapped, PreAlloc, RawMem},
+  std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+  },
+};
+
+type FileStore = Store<u64, FileMapped<RawLink<u64>>>;
+
+/// Copy of a store file in memory, which never writes back to the file
+type ReadStore = Store<u64, PreAlloc<Vec<RawLink<u64>>>>;
+
+/// Inspect and edit doublets stores kept in files
+///
+/// Stores use `u64` indices and size-balanced trees. Queries take up to
+/// three parts, index, source and target, where `*` matches anything.
+/// Commands that only read the store work on a copy of it in memory and
+/// never change its files.
+#[derive(Parser)]
+#[command(name = "dunes", version)]
+struct Cli {
+  /// Store file, created by commands that change it if it doesn't exist
+  store: PathBuf,
+  /// Write-ahead journal of the store, replayed when the store is opened
+  #[arg(long, value_name = "FILE")]
+  journal: Option<PathBuf>,
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Create a link, or a point without arguments, and print its index
+  Create {
+    #[arg(requires = "target")]
+    source: Option<u64>,
+    target: Option<u64>,
+  },
+  /// Print the link with the given index
+  Get { index: u64 },
+  /// Print the index of a link with the given source and target
+  Search { source: u64, target: u64 },
+  /// Print links matching a query
+  Query {
+    #[arg(num_args = 0..=3, value_parser = part)]
+    query: Vec<u64>,
+  },
+  /// Delete links matching a query and print them
+  Delete {
+    #[arg(required = true, num_args = 1..=3, value_parser = part)]
+    query: Vec<u64>,
+  },
+  /// Count links matching a query
+  Count {
+    #[arg(num_args = 0..=3, value_parser = part)]
+    query: Vec<u64>,
+  },
+  /// Print statistics of the store
+  Stats,
+  /// Check the store for consistency and print every violation
+  Verify {
+    /// Rebuild the indexes and the free list from the links if needed
+    #[arg(long)]
+    repair: bool,
+  },
+  /// Import links from a file, `-` for standard input
+  Import {
+    file: PathBuf,
+    /// Read a binary snapshot instead of links notation
+    #[arg(long, conflicts_with = "renumber")]
+    binary: bool,
+    /// Give the links fresh indices instead of the written ones
+    #[arg(long)]
+    renumber: bool,
+  },
+  /// Export every link to a file, `-` for standard output
+  Export {
+    file: PathBuf,
+    /// Write a binary snapshot instead of links notation
+    #[arg(long)]
+    binary: bool,
+  },
+}
+
+impl Command {
+  /// Whether the command leaves the store as it is
+  fn is_read_only(&self) -> bool {
+    match self {
+      Command::Get { .. }
+      | Command::Search { .. }
+      | Command::Query { .. }
+      | Command::Count { .. }
+      | Command::Stats
+      | Command::Export { .. } => true,
+      Command::Verify { repair } => !repair,
+      Command::Create { .. }
+      | Command::Delete { .. }
+      | Command::Import { .. } => false,
+    }
+  }
+}
+
+/// Part of a query, `*` is the wildcard
+fn part(arg: &str) -> Result<u64, String> {
+  match arg {
+    "*" => Ok(u64::ANY),
+    _ => arg.parse().map_err(|err| format!("`{arg}` is not an index: {err}")),
+  }
+}
+
+/// Run `$body` with `$query` as an array of its length
+macro_rules! with_query {
+  ($query:expr, $array:ident => $body:expr) => {
+    match *$query {
+      [] => {
+        let $array: [u64; 0] = [];
+        $body
+      }
+      [index] => {
+        let $array = [index];
+        $body
+      }
+      [index, source] => {
+        let $array = [index, source];
+        $body
+      }
+      [index, source, target] => {
+        let $array = [index, source, target];
+        $body
+      }
+      _ => unreachable!("queries have at most three parts"),
+    }
+  };
+}
+
+/// Open the store at `store` for changes, creating it if it doesn't exist
+fn open(
+  store: &Path,
+  journal: Option<&Path>,
+) -> Result<FileStore, Box<dyn Error>> {
+  let mem = FileMapped::from_path(store)?;
+  // SAFETY: file always represents initialized bytes
+  let store = match journal {
+    Some(path) => {
+      let journal = Journal::from_path(path, SyncPolicy::Always)?;
+      unsafe { Store::open_with_journal(mem, journal)? }
+    }
+    None => unsafe { Store::open(mem)? },
+  };
+  Ok(store)
+}
+
+/// Load a copy of the store at `store` without writing to its files
+///
+/// Fails if the store doesn't exist, or if the journal holds changes to
+/// recover, since replaying them writes to both files.
+fn load(
+  store: &Path,
+  journal: Option<&Path>,
+) -> Result<ReadStore, Box<dyn Error>> {
+  if let Some(journal) = journal
+    && fs::metadata(journal).is_ok_and(|meta| meta.len() > 0)
+  {
+    return Err(
+      "journal holds changes to recover, run a command that changes the \
+       store first"
+        .into(),
+    );
+  }
+  let mut file = File::open(store)
+    .map_err(|err| format!("can't open {}: {err}", store.display()))?;
+  let len = file.metadata()?.len() as usize / size_of::<RawLink<u64>>();
+  let mut links = vec![RawLink::default(); len];
+  file.read_exact(bytemuck::cast_slice_mut(&mut links))?;
+  // SAFETY: the memory only ever grows into the links read from the file
+  Ok(unsafe { Store::open(PreAlloc::new(links))? })
+}
+
+fn run(cli: Cli) -> Result<ExitCode, Box<dyn Error>> {
+  let journal = cli.journal.as_deref();
+  if cli.command.is_read_only() {
+    execute(load(&cli.store, journal)?, cli.command)
+  } else {
+    execute(open(&cli.store, journal)?, cli.command)
+  }
+}
+
+fn execute<M>(
+  mut store: Store<u64, M>,
+  command: Command,
+) -> Result<ExitCode, Box<dyn Error>>
+where
+  M: RawMem<Item = RawLink<u64>> + Send + Sync,
+{
+  let mut out = io::stdout().lock();
+
+  match command {
+    Command::Create { source, target } => {
+      let index = match (source, target) {
+        (Some(source), Some(target)) => store.create_link(source, target)?,
+        _ => store.create_point()?,
+      };
+      writeln!(out, "{index}")?;
+    }
+    Command::Get { index } => {
+      let link = store.get(index).ok_or(doublets::Error::NotExists(index))?;
+      writeln!(out, "{link}")?;
+    }
+    Command::Search { source, target } => match store.search(source, target) {
+      Some(index) => writeln!(out, "{index}")?,
+      None => return Ok(ExitCode::FAILURE),
+    },
+    Command::Query { query } => {
+      for link in with_query!(query.as_slice(), query => store.query(query)) {
+        writeln!(out, "{link}")?;
+      }
+    }
+    Command::Delete { query } => {
+      let mut deleted = Vec::new();
+      with_query!(query.as_slice(), query => store.delete(
+        query,
+        &mut |before: Link<u64>, _| {
+          deleted.push(before);
+          Flow::Continue
+        },
+      ))?;
+      for link in deleted {
+        writeln!(out, "{link}")?;
+      }
+    }
+    Command::Count { query } => {
+      let count = with_query!(query.as_slice(), query => store.count(query));
+      writeln!(out, "{count}")?;
+    }
+    Command::Stats => {
+      let stats = store.stats();
+      let usages = |most: Option<(u64, usize)>| match most {
+        Some((index, usages)) => format!("{index} ({usages} usages)"),
+        None => "none".to_owned(),
+      };
+      writeln!(out, "links: {}", stats.links)?;
+      writeln!(out, "allocated slots: {}", stats.allocated)?;
+      writeln!(out, "free slots: {}", stats.free)?;
+      writeln!(
+        out,
+        "capacity: {} slots, {} bytes",
+        stats.capacity, stats.bytes
+      )?;
+      for (side, tree) in
+        [("source", stats.source_tree), ("target", stats.target_tree)]
+      {
+        writeln!(
+          out,
+          "{side} tree: height {}, average depth {:.2}",
+          tree.height, tree.average_depth
+        )?;
+      }
+      writeln!(out, "largest fan-out: {}", usages(stats.fan_out))?;
+      writeln!(out, "largest fan-in: {}", usages(stats.fan_in))?;
+    }
+    Command::Verify { repair } => {
+      let report = store.verify();
+      for violation in &report.violations {
+        writeln!(out, "{violation}")?;
+      }
+      writeln!(
+        out,
+        "{} links, {} violations",
+        report.links,
+        report.violations.len()
+      )?;
+      if !report.is_consistent() {
+        if !repair {
+          return Ok(ExitCode::FAILURE);
+        }
+        store.rebuild_indexes()?;
+        let report = store.verify();
+        writeln!(out, "repaired, {} violations left", report.violations.len())?;
+      }
+    }
+    Command::Import { file, binary, renumber } => {
+      let mut bytes = Vec::new();
+      if file == Path::new("-") {
+        io::stdin().lock().read_to_end(&mut bytes)?;
+      } else {
+        File::open(&file)?.read_to_end(&mut bytes)?;
+      }
+      let imported = if binary {
+        store.import_from(bytes.as_slice())?
+      } else {
+        let numbering =
+          if renumber { Numbering::Renumber } else { Numbering::Preserve };
+        store.import_notation(bytes.as_slice(), numbering)?
+      };
+      writeln!(out, "imported {} links", imported.len())?;
+    }
+    Command::Export { file, binary } => {
+      let writer: Box<dyn Write> = if file == Path::new("-") {
+        Box::new(out)
+      } else {
+        Box::new(File::create(&file)?)
+      };
+      if binary {
+        store.export_to(writer)?;
+      } else {
+        store.export_notation(writer)?;
+      }
+    }
+  }
+  Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+  match run(Cli::parse()) {
+    Ok(code) => code,
+    Err(err) => {
+      eprintln!("error: {err}");
+      ExitCode::FAILURE
+    }
+  }
+}
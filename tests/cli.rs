@@ -0,0 +1,157 @@
+use {
+  std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+  },
+  tempfile::TempDir,
+};
+
+fn dunes(store: &Path, args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_dunes"))
+    .arg(store)
+    .args(args)
+    .output()
+    .unwrap()
+}
+
+fn stdout(output: Output) -> String {
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_create_get_search() {
+  let dir = TempDir::new().unwrap();
+  let store = dir.path().join("links");
+
+  assert_eq!(stdout(dunes(&store, &["create"])), "1\n");
+  assert_eq!(stdout(dunes(&store, &["create", "1", "1"])), "2\n");
+  assert_eq!(stdout(dunes(&store, &["get", "1"])), "(1: 1 1)\n");
+  assert_eq!(stdout(dunes(&store, &["search", "1", "1"])), "1\n");
+
+  let missing = dunes(&store, &["search", "2", "2"]);
+  assert!(!missing.status.success());
+  assert!(missing.stdout.is_empty());
+
+  let missing = dunes(&store, &["get", "7"]);
+  assert!(!missing.status.success());
+  assert!(String::from_utf8_lossy(&missing.stderr).starts_with("error:"));
+}
+
+#[test]
+fn test_query_count_delete() {
+  let dir = TempDir::new().unwrap();
+  let store = dir.path().join("links");
+
+  stdout(dunes(&store, &["create"]));
+  stdout(dunes(&store, &["create", "1", "1"]));
+  stdout(dunes(&store, &["create", "2", "1"]));
+
+  assert_eq!(stdout(dunes(&store, &["count"])), "3\n");
+  assert_eq!(stdout(dunes(&store, &["count", "*", "*", "1"])), "3\n");
+  assert_eq!(
+    stdout(dunes(&store, &["query", "*", "1", "*"])),
+    "(1: 1 1)\n(2: 1 1)\n"
+  );
+
+  assert_eq!(stdout(dunes(&store, &["delete", "3"])), "(3: 2 1)\n");
+  assert_eq!(stdout(dunes(&store, &["count"])), "2\n");
+  assert!(!dunes(&store, &["query", "x"]).status.success());
+}
+
+#[test]
+fn test_export_import() {
+  let dir = TempDir::new().unwrap();
+  let (store, copy) = (dir.path().join("links"), dir.path().join("copy"));
+
+  stdout(dunes(&store, &["create"]));
+  stdout(dunes(&store, &["create", "1", "1"]));
+  let text = stdout(dunes(&store, &["export", "-"]));
+  assert_eq!(text, "(1: 1 1)\n(2: 1 1)\n");
+
+  let dump = dir.path().join("dump");
+  stdout(dunes(&store, &["export", "--binary", dump.to_str().unwrap()]));
+  assert_eq!(
+    stdout(dunes(&copy, &["import", "--binary", dump.to_str().unwrap()])),
+    "imported 2 links\n"
+  );
+  assert_eq!(stdout(dunes(&copy, &["export", "-"])), text);
+
+  let mut import = Command::new(env!("CARGO_BIN_EXE_dunes"))
+    .arg(&copy)
+    .args(["import", "--renumber", "-"])
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .spawn()
+    .unwrap();
+  import.stdin.take().unwrap().write_all(b"(1 2)").unwrap();
+  assert_eq!(stdout(import.wait_with_output().unwrap()), "imported 1 links\n");
+  assert_eq!(stdout(dunes(&copy, &["get", "3"])), "(3: 1 2)\n");
+}
+
+#[test]
+fn test_stats_verify() {
+  let dir = TempDir::new().unwrap();
+  let store = dir.path().join("links");
+
+  stdout(dunes(&store, &["create"]));
+  stdout(dunes(&store, &["create", "1", "1"]));
+
+  let stats = stdout(dunes(&store, &["stats"]));
+  assert!(stats.contains("links: 2\n"));
+  assert!(stats.contains("source tree: height 2, average depth 0.50\n"));
+  assert!(stats.contains("largest fan-out: 1 (1 usages)\n"));
+  assert_eq!(stdout(dunes(&store, &["verify"])), "2 links, 0 violations\n");
+}
+
+#[test]
+fn test_journal() {
+  let dir = TempDir::new().unwrap();
+  let (store, journal) = (dir.path().join("links"), dir.path().join("wal"));
+  let journal = journal.to_str().unwrap();
+
+  stdout(dunes(&store, &["--journal", journal, "create"]));
+  assert!(Path::new(journal).exists());
+  assert_eq!(stdout(dunes(&store, &["--journal", journal, "count"])), "1\n");
+}
+
+#[test]
+fn test_inspection_is_read_only() {
+  let dir = TempDir::new().unwrap();
+  let (store, journal) = (dir.path().join("links"), dir.path().join("wal"));
+  let journal = journal.to_str().unwrap();
+
+  let missing = dunes(&store, &["count"]);
+  assert!(!missing.status.success());
+  assert!(!store.exists());
+
+  stdout(dunes(&store, &["create"]));
+  stdout(dunes(&store, &["create", "1", "1"]));
+  let bytes = fs::read(&store).unwrap();
+  for args in [
+    &["get", "1"][..],
+    &["search", "1", "1"],
+    &["query", "*", "1"],
+    &["count"],
+    &["stats"],
+    &["verify"],
+    &["export", "-"],
+    &["--journal", journal, "count"],
+  ] {
+    stdout(dunes(&store, args));
+  }
+  assert_eq!(fs::read(&store).unwrap(), bytes);
+  assert!(!Path::new(journal).exists());
+
+  // recovering the journal writes to the store
+  fs::write(journal, [0; 45]).unwrap();
+  assert!(!dunes(&store, &["--journal", journal, "count"]).status.success());
+  assert_eq!(stdout(dunes(&store, &["--journal", journal, "create"])), "3\n");
+  assert_eq!(stdout(dunes(&store, &["--journal", journal, "count"])), "3\n");
+}